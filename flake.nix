@@ -34,12 +34,13 @@
           libxkbcommon
         ];
 
-        # Include standard Cargo sources plus bundled SVG faces
+        # Include standard Cargo sources plus bundled SVG faces and the embedded fallback font
         src = pkgs.lib.cleanSourceWith {
           src = ./.;
           filter = path: type:
             (craneLib.filterCargoSources path type)
-            || (builtins.match ".*faces/.*\\.svg$" path != null);
+            || (builtins.match ".*faces/.*\\.svg$" path != null)
+            || (builtins.match ".*fonts/.*\\.ttf$" path != null);
         };
 
         commonArgs = {
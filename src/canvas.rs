@@ -1,26 +1,71 @@
-use tiny_skia::{Color, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke, Transform};
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tiny_skia::{Color, GradientStop, LinearGradient, Paint, PathBuilder, Pixmap, PixmapPaint, Point, Rect, SpreadMode, Stroke, Transform};
 
+use crate::config::expand_path;
+
+/// A drawing surface addressed in logical pixels. With a fractional output scale
+/// the backing pixmap is larger than the logical size; shapes are scaled by a
+/// transform and text is rasterized at device resolution so it stays crisp.
 pub struct Canvas {
     pub pixmap: Pixmap,
+    width: u32,
+    height: u32,
+    scale: f32,
+    /// Anti-alias shape fills and strokes (text is always smoothed)
+    antialias: bool,
 }
 
+/// DejaVu Sans Mono (Bitstream Vera license, see fonts/LICENSE-DejaVu.txt),
+/// used only when no font can be found on the filesystem.
+const EMBEDDED_FONT: &[u8] = include_bytes!("../fonts/DejaVuSansMono.ttf");
+
 pub struct FontState {
     font: fontdue::Font,
+    /// File the font was loaded from; `None` for the embedded fallback.
+    path: Option<std::path::PathBuf>,
 }
 
 impl Canvas {
     pub fn new(width: u32, height: u32) -> Self {
+        Self::with_scale(width, height, 1.0)
+    }
+
+    /// Create a canvas of `width`×`height` logical pixels backed by a pixmap at `scale`.
+    pub fn with_scale(width: u32, height: u32, scale: f32) -> Self {
+        let (dw, dh) = device_size(width, height, scale);
         Self {
-            pixmap: Pixmap::new(width, height).expect("Failed to create pixmap"),
+            pixmap: Pixmap::new(dw, dh).expect("Failed to create pixmap"),
+            width,
+            height,
+            scale,
+            antialias: true,
         }
     }
 
+    /// Turn shape anti-aliasing on or off; off is cheaper on slow CPUs.
+    pub fn set_antialias(&mut self, antialias: bool) {
+        self.antialias = antialias;
+    }
+
+    /// Logical width.
     pub fn width(&self) -> u32 {
-        self.pixmap.width()
+        self.width
     }
 
+    /// Logical height.
     pub fn height(&self) -> u32 {
-        self.pixmap.height()
+        self.height
+    }
+
+    /// Device pixels per logical pixel.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::from_scale(self.scale, self.scale)
     }
 
     pub fn clear(&mut self, color: [u8; 4]) {
@@ -31,11 +76,32 @@ impl Canvas {
         if let Some(rect) = Rect::from_xywh(x, y, w, h) {
             let mut paint = Paint::default();
             paint.set_color_rgba8(color[0], color[1], color[2], color[3]);
-            paint.anti_alias = true;
-            self.pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+            paint.anti_alias = self.antialias;
+            self.pixmap.fill_rect(rect, &paint, self.transform(), None);
         }
     }
 
+    /// Fill a rectangle with a vertical gradient from `top` to `bottom`.
+    pub fn fill_rect_vertical_gradient(&mut self, x: f32, y: f32, w: f32, h: f32, top: [u8; 4], bottom: [u8; 4]) {
+        let (Some(rect), Some(shader)) = (
+            Rect::from_xywh(x, y, w, h),
+            LinearGradient::new(
+                Point::from_xy(x, y),
+                Point::from_xy(x, y + h),
+                vec![
+                    GradientStop::new(0.0, Color::from_rgba8(top[0], top[1], top[2], top[3])),
+                    GradientStop::new(1.0, Color::from_rgba8(bottom[0], bottom[1], bottom[2], bottom[3])),
+                ],
+                SpreadMode::Pad,
+                Transform::identity(),
+            ),
+        ) else {
+            return;
+        };
+        let paint = Paint { shader, anti_alias: self.antialias, ..Paint::default() };
+        self.pixmap.fill_rect(rect, &paint, self.transform(), None);
+    }
+
     pub fn draw_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, color: [u8; 4], width: f32) {
         let mut pb = PathBuilder::new();
         pb.move_to(x1, y1);
@@ -43,16 +109,16 @@ impl Canvas {
         if let Some(path) = pb.finish() {
             let mut paint = Paint::default();
             paint.set_color_rgba8(color[0], color[1], color[2], color[3]);
-            paint.anti_alias = true;
+            paint.anti_alias = self.antialias;
             let stroke = Stroke { width, ..Stroke::default() };
-            self.pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+            self.pixmap.stroke_path(&path, &paint, &stroke, self.transform(), None);
         }
     }
 
     pub fn draw_circle(&mut self, cx: f32, cy: f32, r: f32, color: [u8; 4], fill: bool, stroke_width: f32) {
         let mut pb = PathBuilder::new();
         // Approximate circle with 4 cubic bezier curves
-        let k = 0.5522847498; // magic constant for cubic bezier circle
+        let k = 0.552_284_7; // magic constant for cubic bezier circle
         let kr = k * r;
         pb.move_to(cx, cy - r);
         pb.cubic_to(cx + kr, cy - r, cx + r, cy - kr, cx + r, cy);
@@ -64,16 +130,67 @@ impl Canvas {
         if let Some(path) = pb.finish() {
             let mut paint = Paint::default();
             paint.set_color_rgba8(color[0], color[1], color[2], color[3]);
-            paint.anti_alias = true;
+            paint.anti_alias = self.antialias;
             if fill {
-                self.pixmap.fill_path(&path, &paint, tiny_skia::FillRule::Winding, Transform::identity(), None);
+                self.pixmap.fill_path(&path, &paint, tiny_skia::FillRule::Winding, self.transform(), None);
             } else {
                 let stroke = Stroke { width: stroke_width, ..Stroke::default() };
-                self.pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+                self.pixmap.stroke_path(&path, &paint, &stroke, self.transform(), None);
             }
         }
     }
 
+    /// Stroke a circular arc starting at `start_deg` (0 = 3 o'clock, clockwise)
+    /// and sweeping `sweep_deg` degrees.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_arc(&mut self, cx: f32, cy: f32, r: f32, start_deg: f32, sweep_deg: f32, color: [u8; 4], width: f32) {
+        if sweep_deg.abs() < 0.01 { return; }
+        // Split into cubic segments of at most 90 degrees
+        let segments = (sweep_deg.abs() / 90.0).ceil() as usize;
+        let step = (sweep_deg / segments as f32).to_radians();
+        let k = 4.0 / 3.0 * (step / 4.0).tan() * r;
+
+        let mut pb = PathBuilder::new();
+        let mut a = start_deg.to_radians();
+        pb.move_to(cx + r * a.cos(), cy + r * a.sin());
+        for _ in 0..segments {
+            let b = a + step;
+            let (p0x, p0y) = (cx + r * a.cos(), cy + r * a.sin());
+            let (p3x, p3y) = (cx + r * b.cos(), cy + r * b.sin());
+            pb.cubic_to(
+                p0x - k * a.sin(), p0y + k * a.cos(),
+                p3x + k * b.sin(), p3y - k * b.cos(),
+                p3x, p3y,
+            );
+            a = b;
+        }
+
+        if let Some(path) = pb.finish() {
+            let mut paint = Paint::default();
+            paint.set_color_rgba8(color[0], color[1], color[2], color[3]);
+            paint.anti_alias = self.antialias;
+            let stroke = Stroke { width, line_cap: tiny_skia::LineCap::Round, ..Stroke::default() };
+            self.pixmap.stroke_path(&path, &paint, &stroke, self.transform(), None);
+        }
+    }
+
+    pub fn stroke_polygon(&mut self, points: &[(f32, f32)], color: [u8; 4], width: f32) {
+        if points.len() < 3 { return; }
+        let mut pb = PathBuilder::new();
+        pb.move_to(points[0].0, points[0].1);
+        for &(x, y) in &points[1..] {
+            pb.line_to(x, y);
+        }
+        pb.close();
+        if let Some(path) = pb.finish() {
+            let mut paint = Paint::default();
+            paint.set_color_rgba8(color[0], color[1], color[2], color[3]);
+            paint.anti_alias = self.antialias;
+            let stroke = Stroke { width, line_join: tiny_skia::LineJoin::Round, ..Stroke::default() };
+            self.pixmap.stroke_path(&path, &paint, &stroke, self.transform(), None);
+        }
+    }
+
     pub fn fill_polygon(&mut self, points: &[(f32, f32)], color: [u8; 4]) {
         if points.len() < 3 { return; }
         let mut pb = PathBuilder::new();
@@ -85,12 +202,23 @@ impl Canvas {
         if let Some(path) = pb.finish() {
             let mut paint = Paint::default();
             paint.set_color_rgba8(color[0], color[1], color[2], color[3]);
-            paint.anti_alias = true;
-            self.pixmap.fill_path(&path, &paint, tiny_skia::FillRule::Winding, Transform::identity(), None);
+            paint.anti_alias = self.antialias;
+            self.pixmap.fill_path(&path, &paint, tiny_skia::FillRule::Winding, self.transform(), None);
         }
     }
 
     pub fn draw_image(&mut self, img: &Pixmap, x: i32, y: i32) {
+        self.pixmap.draw_pixmap(
+            x, y, img.as_ref(),
+            &PixmapPaint::default(),
+            self.transform(),
+            None,
+        );
+    }
+
+    /// Draw an image already sized in device pixels at device position (`x`, `y`), unscaled.
+    /// Use `device_size` to size backgrounds so they stay sharp under fractional scaling.
+    pub fn draw_device_image(&mut self, img: &Pixmap, x: i32, y: i32) {
         self.pixmap.draw_pixmap(
             x, y, img.as_ref(),
             &PixmapPaint::default(),
@@ -99,18 +227,51 @@ impl Canvas {
         );
     }
 
-    #[allow(dead_code)]
+    /// Device pixel size of the backing pixmap.
+    pub fn device_size(&self) -> (u32, u32) {
+        (self.pixmap.width(), self.pixmap.height())
+    }
+
     pub fn draw_scaled_image(&mut self, img: &Pixmap, x: f32, y: f32, target_w: f32, target_h: f32) {
         let sx = target_w / img.width() as f32;
         let sy = target_h / img.height() as f32;
         self.pixmap.draw_pixmap(
             0, 0, img.as_ref(),
             &PixmapPaint::default(),
-            Transform::from_scale(sx, sy).post_translate(x, y),
+            Transform::from_scale(sx, sy).post_translate(x, y).post_scale(self.scale, self.scale),
             None,
         );
     }
 
+    /// Fade the whole canvas to `opacity` (0–1). The pixmap is premultiplied,
+    /// so colour and alpha are scaled together; rounding keeps a 50% fade of
+    /// opaque white at 128 rather than drifting darker.
+    pub fn apply_opacity(&mut self, opacity: f32) {
+        let scale = (opacity.clamp(0.0, 1.0) * 255.0).round() as u32;
+        if scale == 255 {
+            return;
+        }
+        for c in self.pixmap.data_mut() {
+            *c = ((*c as u32 * scale + 127) / 255) as u8;
+        }
+    }
+
+    /// Blend every pixel `amount` (0..1) of the way towards white, keeping its
+    /// alpha so transparent areas stay transparent.
+    pub fn brighten(&mut self, amount: f32) {
+        let k = (amount.clamp(0.0, 1.0) * 255.0).round() as u32;
+        if k == 0 {
+            return;
+        }
+        // Premultiplied: white at alpha `a` is (a, a, a)
+        for px in self.pixmap.data_mut().chunks_exact_mut(4) {
+            let a = px[3] as u32;
+            for c in &mut px[..3] {
+                *c = ((*c as u32 * (255 - k) + a * k + 127) / 255) as u8;
+            }
+        }
+    }
+
     /// Convert RGBA pixels to BGRA (ARGB8888 in little-endian) for wl_shm
     pub fn pixels_argb8888(&self) -> Vec<u8> {
         let data = self.pixmap.data();
@@ -130,13 +291,40 @@ impl Canvas {
     }
 }
 
+/// Device pixel dimensions for a logical size at a given scale.
+pub fn device_size(width: u32, height: u32, scale: f32) -> (u32, u32) {
+    (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
+
+/// Bounding box `(x, y, w, h)` of the pixels that differ between two frames of
+/// the same size (4 bytes per pixel), or `None` if they are identical.
+pub fn diff_bounds(prev: &[u8], next: &[u8], width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let row_len = width as usize * 4;
+    let mut bounds: Option<(u32, u32, u32, u32)> = None; // (x0, y0, x1, y1) inclusive
+    for y in 0..height {
+        let start = y as usize * row_len;
+        let (a, b) = (&prev[start..start + row_len], &next[start..start + row_len]);
+        if a == b { continue; }
+        let first = (0..width as usize).find(|&x| a[x * 4..x * 4 + 4] != b[x * 4..x * 4 + 4]).unwrap_or(0) as u32;
+        let last = (0..width as usize).rev().find(|&x| a[x * 4..x * 4 + 4] != b[x * 4..x * 4 + 4]).unwrap_or(0) as u32;
+        bounds = Some(match bounds {
+            None => (first, y, last, y),
+            Some((x0, y0, x1, _)) => (x0.min(first), y0, x1.max(last), y),
+        });
+    }
+    bounds.map(|(x0, y0, x1, y1)| (x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+}
+
 impl FontState {
-    pub fn new(font_name: &str) -> Self {
+    /// Load `font_name` as a path, else find a system monospace font, else use the
+    /// embedded one. Only fails if even the embedded font can't be parsed.
+    pub fn new(font_name: &str) -> anyhow::Result<Self> {
         // Try loading as a file path first
-        if let Ok(data) = std::fs::read(font_name) {
-            if let Ok(font) = fontdue::Font::from_bytes(data, fontdue::FontSettings::default()) {
-                return Self { font };
-            }
+        if let Some(font) = Self::from_path(font_name) {
+            return Ok(font);
         }
 
         // Search common system font paths
@@ -148,8 +336,8 @@ impl FontState {
 
         // Try to find a monospace font
         for base in &search_paths {
-            if let Some(font) = Self::search_font_dir(base, font_name) {
-                return Self { font };
+            if let Some((font, path)) = Self::search_font_dir(base, font_name) {
+                return Ok(Self { font, path: Some(path) });
             }
         }
 
@@ -165,22 +353,38 @@ impl FontState {
             if let Ok(data) = std::fs::read(path) {
                 if let Ok(font) = fontdue::Font::from_bytes(data, fontdue::FontSettings::default()) {
                     log::info!("Using fallback font: {}", path);
-                    return Self { font };
+                    return Ok(Self { font, path: Some(path.into()) });
                 }
             }
         }
 
         // Last resort: use built-in minimal font data won't work, so search nix store
-        if let Some(font) = Self::search_nix_fonts() {
-            return Self { font };
+        if let Some((font, path)) = Self::search_nix_fonts() {
+            return Ok(Self { font, path: Some(path) });
         }
 
-        log::warn!("No system fonts found, text rendering will fail");
-        // Create a dummy font from embedded data - we'll use a minimal approach
         Self::with_builtin_fallback()
     }
 
-    fn search_font_dir(dir: &str, _name: &str) -> Option<fontdue::Font> {
+    /// Whether a `[clock] font` value names a file rather than a font family.
+    pub fn is_path(font_name: &str) -> bool {
+        font_name.contains('/') || font_name.ends_with(".ttf") || font_name.ends_with(".otf")
+    }
+
+    /// Load a font file, returning None if it is missing or not a valid font.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = expand_path(path);
+        let data = std::fs::read(&path).ok()?;
+        let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default()).ok()?;
+        Some(Self { font, path: Some(path.into()) })
+    }
+
+    /// The file the font was loaded from, or `None` for the embedded fallback.
+    pub fn path(&self) -> Option<&std::path::Path> {
+        self.path.as_deref()
+    }
+
+    fn search_font_dir(dir: &str, _name: &str) -> Option<(fontdue::Font, std::path::PathBuf)> {
         let dir_path = std::path::Path::new(dir);
         if !dir_path.exists() { return None; }
 
@@ -188,7 +392,7 @@ impl FontState {
         Self::walk_for_font(dir_path)
     }
 
-    fn walk_for_font(dir: &std::path::Path) -> Option<fontdue::Font> {
+    fn walk_for_font(dir: &std::path::Path) -> Option<(fontdue::Font, std::path::PathBuf)> {
         let entries = std::fs::read_dir(dir).ok()?;
         for entry in entries.flatten() {
             let path = entry.path();
@@ -202,7 +406,7 @@ impl FontState {
                     if let Ok(data) = std::fs::read(&path) {
                         if let Ok(font) = fontdue::Font::from_bytes(data, fontdue::FontSettings::default()) {
                             log::info!("Found font: {}", path.display());
-                            return Some(font);
+                            return Some((font, path));
                         }
                     }
                 }
@@ -211,7 +415,7 @@ impl FontState {
         None
     }
 
-    fn search_nix_fonts() -> Option<fontdue::Font> {
+    fn search_nix_fonts() -> Option<(fontdue::Font, std::path::PathBuf)> {
         // Search /nix/store for font packages
         let nix_store = std::path::Path::new("/nix/store");
         if !nix_store.exists() { return None; }
@@ -229,18 +433,27 @@ impl FontState {
         None
     }
 
-    fn with_builtin_fallback() -> Self {
-        // We can't ship a font, but fontdue requires one.
-        // As a last resort, create a font from the first .ttf we can find anywhere
+    fn with_builtin_fallback() -> anyhow::Result<Self> {
+        // Prefer any font on the filesystem over the embedded one: take the first .ttf we can find
         for base in &["/usr/share/fonts", "/nix/store"] {
-            if let Some(font) = Self::walk_for_any_font(std::path::Path::new(base)) {
-                return Self { font };
+            if let Some((font, path)) = Self::walk_for_any_font(std::path::Path::new(base)) {
+                return Ok(Self { font, path: Some(path) });
             }
         }
-        panic!("No fonts found on system. Please install a TTF font or specify a font path in config.");
+        // True last resort: the embedded DejaVu Sans Mono, so minimal systems still get text
+        log::warn!("No system fonts found, using embedded DejaVu Sans Mono");
+        Self::embedded()
+    }
+
+    /// The embedded DejaVu Sans Mono, whatever fonts the system has. Measuring
+    /// with it gives the same sizes on every machine.
+    pub fn embedded() -> anyhow::Result<Self> {
+        let font = fontdue::Font::from_bytes(EMBEDDED_FONT, fontdue::FontSettings::default())
+            .map_err(|e| anyhow::anyhow!("Embedded fallback font failed to load: {}", e))?;
+        Ok(Self { font, path: None })
     }
 
-    fn walk_for_any_font(dir: &std::path::Path) -> Option<fontdue::Font> {
+    fn walk_for_any_font(dir: &std::path::Path) -> Option<(fontdue::Font, std::path::PathBuf)> {
         let entries = std::fs::read_dir(dir).ok()?;
         for entry in entries.flatten() {
             let path = entry.path();
@@ -254,7 +467,7 @@ impl FontState {
                     if let Ok(data) = std::fs::read(&path) {
                         if let Ok(font) = fontdue::Font::from_bytes(data, fontdue::FontSettings::default()) {
                             log::info!("Found fallback font: {}", path.display());
-                            return Some(font);
+                            return Some((font, path));
                         }
                     }
                 }
@@ -275,21 +488,53 @@ impl FontState {
         (width, max_height)
     }
 
+    /// The font's ascent above and descent below the baseline at `size`, both
+    /// positive. `draw_text` puts the baseline `size` below `y`, so a line can
+    /// reach `ascent - size` above its box and `descent` below it.
+    pub fn line_metrics(&self, size: f32) -> (f32, f32) {
+        match self.font.horizontal_line_metrics(size) {
+            Some(m) => (m.ascent, -m.descent),
+            None => (size * 0.8, size * 0.2),
+        }
+    }
+
     /// Draw text with a contrasting outline for readability on varied backgrounds.
     /// Draws text at 8 compass offsets in `outline_color`, then the actual text on top.
-    pub fn draw_text_outlined(&self, canvas: &mut Canvas, text: &str, x: f32, y: f32, size: f32, color: [u8; 4], outline_color: [u8; 4]) {
-        let r = (size * 0.04).max(0.8).min(1.5);
-        let offsets: [(f32, f32); 8] = [
-            (-r, 0.0), (r, 0.0), (0.0, -r), (0.0, r),
-            (-r, -r), (r, -r), (-r, r), (r, r),
-        ];
-        for (dx, dy) in &offsets {
-            self.draw_text(canvas, text, x + dx, y + dy, size, outline_color);
+    /// `radius` overrides the size-derived outline radius; past 1.5px more
+    /// offsets around the circle keep a wide outline free of gaps.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text_outlined(&self, canvas: &mut Canvas, text: &str, x: f32, y: f32, size: f32, color: [u8; 4], outline_color: [u8; 4], radius: Option<f32>) {
+        let r = radius.unwrap_or_else(|| (size * 0.04).clamp(0.8, 1.5));
+        if r <= 1.5 {
+            let offsets: [(f32, f32); 8] = [
+                (-r, 0.0), (r, 0.0), (0.0, -r), (0.0, r),
+                (-r, -r), (r, -r), (-r, r), (r, r),
+            ];
+            for (dx, dy) in &offsets {
+                self.draw_text(canvas, text, x + dx, y + dy, size, outline_color);
+            }
+        } else {
+            let steps = (std::f32::consts::TAU * r).ceil() as usize;
+            for i in 0..steps {
+                let angle = i as f32 * std::f32::consts::TAU / steps as f32;
+                self.draw_text(canvas, text, x + r * angle.cos(), y + r * angle.sin(), size, outline_color);
+            }
         }
         self.draw_text(canvas, text, x, y, size, color);
     }
 
+    /// Draw text over a single copy of itself offset by `offset` in `shadow_color`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text_shadowed(&self, canvas: &mut Canvas, text: &str, x: f32, y: f32, size: f32, color: [u8; 4], shadow_color: [u8; 4], offset: (f32, f32)) {
+        self.draw_text(canvas, text, x + offset.0, y + offset.1, size, shadow_color);
+        self.draw_text(canvas, text, x, y, size, color);
+    }
+
     pub fn draw_text(&self, canvas: &mut Canvas, text: &str, x: f32, y: f32, size: f32, color: [u8; 4]) {
+        // Rasterize at device resolution; positions are given in logical pixels
+        let scale = canvas.scale();
+        let (x, y, size) = (x * scale, y * scale, size * scale);
+        let (dev_w, dev_h) = canvas.device_size();
         let mut cursor_x = x;
         for ch in text.chars() {
             let (metrics, bitmap) = self.font.rasterize(ch, size);
@@ -302,7 +547,7 @@ impl FontState {
                         if coverage > 0 {
                             let px = gx + col as i32;
                             let py = gy + row as i32;
-                            if px >= 0 && py >= 0 && (px as u32) < canvas.width() && (py as u32) < canvas.height() {
+                            if px >= 0 && py >= 0 && (px as u32) < dev_w && (py as u32) < dev_h {
                                 let alpha = (coverage as u32 * color[3] as u32) / 255;
                                 if alpha > 0 {
                                     blend_pixel(&mut canvas.pixmap, px as u32, py as u32, color, alpha as u8);
@@ -321,8 +566,11 @@ impl FontState {
 /// Samples every 4th pixel for performance.
 pub fn sample_region_luminance(canvas: &Canvas, x: u32, y: u32, w: u32, h: u32) -> f32 {
     let data = canvas.pixmap.data();
-    let cw = canvas.width();
-    let ch = canvas.height();
+    let (cw, ch) = canvas.device_size();
+    // Regions are given in logical pixels
+    let scale = canvas.scale();
+    let (x, y) = ((x as f32 * scale) as u32, (y as f32 * scale) as u32);
+    let (w, h) = ((w as f32 * scale).ceil() as u32, (h as f32 * scale).ceil() as u32);
     let x_end = (x + w).min(cw);
     let y_end = (y + h).min(ch);
     let mut sum = 0.0f64;
@@ -333,10 +581,7 @@ pub fn sample_region_luminance(canvas: &Canvas, x: u32, y: u32, w: u32, h: u32)
         while px < x_end {
             let idx = ((py * cw + px) * 4) as usize;
             if idx + 2 < data.len() {
-                let r = data[idx] as f64;
-                let g = data[idx + 1] as f64;
-                let b = data[idx + 2] as f64;
-                sum += 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                sum += linear_luminance(data[idx], data[idx + 1], data[idx + 2]) as f64;
                 count += 1;
             }
             px += 4;
@@ -344,7 +589,52 @@ pub fn sample_region_luminance(canvas: &Canvas, x: u32, y: u32, w: u32, h: u32)
         py += 4;
     }
     if count == 0 { return 0.0; }
-    (sum / count as f64) as f32
+    // Average in linear light, then report on the sRGB 0–255 scale
+    linear_to_srgb((sum / count as f64) as f32) as f32
+}
+
+/// sRGB ⇄ linear lookup tables, built once.
+struct GammaTables {
+    to_linear: [f32; 256],
+    /// Indexed by linear value × 4095
+    to_srgb: [u8; 4096],
+}
+
+fn gamma_tables() -> &'static GammaTables {
+    static TABLES: OnceLock<GammaTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut to_linear = [0.0; 256];
+        for (i, v) in to_linear.iter_mut().enumerate() {
+            let c = i as f32 / 255.0;
+            *v = if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+        }
+        let mut to_srgb = [0u8; 4096];
+        for (i, v) in to_srgb.iter_mut().enumerate() {
+            let l = i as f32 / 4095.0;
+            let c = if l <= 0.003_130_8 { l * 12.92 } else { 1.055 * l.powf(1.0 / 2.4) - 0.055 };
+            *v = (c * 255.0).round() as u8;
+        }
+        GammaTables { to_linear, to_srgb }
+    })
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    gamma_tables().to_linear[c as usize]
+}
+
+fn linear_to_srgb(l: f32) -> u8 {
+    gamma_tables().to_srgb[(l.clamp(0.0, 1.0) * 4095.0).round() as usize]
+}
+
+/// Relative luminance (0–1, linear light) of an sRGB colour.
+fn linear_luminance(r: u8, g: u8, b: u8) -> f32 {
+    0.2126 * srgb_to_linear(r) + 0.7152 * srgb_to_linear(g) + 0.0722 * srgb_to_linear(b)
+}
+
+/// Perceived brightness of an sRGB colour on the 0–255 scale: luminance is
+/// computed in linear light and re-encoded, so mid-grey stays near 128.
+pub fn perceived_luminance(color: [u8; 4]) -> f32 {
+    linear_to_srgb(linear_luminance(color[0], color[1], color[2])) as f32
 }
 
 fn blend_pixel(pixmap: &mut Pixmap, x: u32, y: u32, color: [u8; 4], alpha: u8) {
@@ -353,21 +643,29 @@ fn blend_pixel(pixmap: &mut Pixmap, x: u32, y: u32, color: [u8; 4], alpha: u8) {
     let data = pixmap.data_mut();
     if idx + 3 >= data.len() { return; }
 
-    let a = alpha as u32;
-    let inv_a = 255 - a;
-    data[idx]     = ((color[0] as u32 * a + data[idx] as u32 * inv_a) / 255) as u8;
-    data[idx + 1] = ((color[1] as u32 * a + data[idx + 1] as u32 * inv_a) / 255) as u8;
-    data[idx + 2] = ((color[2] as u32 * a + data[idx + 2] as u32 * inv_a) / 255) as u8;
-    data[idx + 3] = (a + data[idx + 3] as u32 * inv_a / 255).min(255) as u8;
-}
-
-fn expand_tilde(path: &str) -> String {
-    if let Some(rest) = path.strip_prefix("~/") {
-        if let Ok(home) = std::env::var("HOME") {
-            return format!("{}/{}", home, rest);
+    // Fast paths: full coverage replaces the pixel, and nothing underneath
+    // means there is nothing to blend with
+    let dst_a = data[idx + 3];
+    if alpha == 255 || dst_a == 0 {
+        let a = alpha as u32;
+        for c in 0..3 {
+            data[idx + c] = ((color[c] as u32 * a + 127) / 255) as u8;
         }
+        data[idx + 3] = alpha;
+        return;
+    }
+
+    // Blend in linear light. The pixmap is premultiplied, so unpremultiply the
+    // destination first and premultiply the result again.
+    let a = alpha as f32 / 255.0;
+    let da = dst_a as f32 / 255.0;
+    let out_a = a + da * (1.0 - a);
+    for c in 0..3 {
+        let dst = ((data[idx + c] as f32 / da).round() as u32).min(255) as u8;
+        let lin = (srgb_to_linear(color[c]) * a + srgb_to_linear(dst) * da * (1.0 - a)) / out_a;
+        data[idx + c] = (linear_to_srgb(lin) as f32 * out_a).round() as u8;
     }
-    path.to_string()
+    data[idx + 3] = (out_a * 255.0).round() as u8;
 }
 
 pub fn is_svg(path: &str) -> bool {
@@ -377,7 +675,7 @@ pub fn is_svg(path: &str) -> bool {
 
 pub fn load_svg(path: &str, width: u32, height: u32) -> Option<Pixmap> {
     if path.is_empty() { return None; }
-    let expanded = expand_tilde(path);
+    let expanded = expand_path(path);
     let data = std::fs::read(&expanded).ok()?;
     let tree = resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default()).ok()?;
     let mut pixmap = Pixmap::new(width, height)?;
@@ -390,8 +688,44 @@ pub fn load_svg(path: &str, width: u32, height: u32) -> Option<Pixmap> {
 
 pub fn load_image(path: &str) -> Option<Pixmap> {
     if path.is_empty() { return None; }
-    let expanded = expand_tilde(path);
-    let img = image::open(&expanded).ok()?.to_rgba8();
+    let expanded = expand_path(path);
+    // Sniff the format from the file contents so a wrong or upper-case extension still decodes
+    let reader = match image::ImageReader::open(&expanded).and_then(|r| r.with_guessed_format()) {
+        Ok(r) => r,
+        Err(e) => {
+            warn_once(&expanded, format!("Failed to open image {}: {}", expanded, e));
+            return None;
+        }
+    };
+    let format = reader.format();
+    let img = match reader.decode() {
+        Ok(img) => img.to_rgba8(),
+        Err(image::ImageError::Unsupported(e)) => {
+            let name = format.map_or_else(|| "unknown".to_string(), |f| format!("{:?}", f));
+            warn_once(&expanded, format!("Unsupported image format ({}) for {}: {}", name, expanded, e));
+            return None;
+        }
+        Err(e) => {
+            warn_once(&expanded, format!("Failed to decode image {}: {}", expanded, e));
+            return None;
+        }
+    };
+    rgba_to_pixmap(&img)
+}
+
+/// Log an image loading warning once per path; images are reloaded on every
+/// redraw, so repeating the warning would flood the log.
+fn warn_once(path: &str, msg: String) {
+    static WARNED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+    let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+    if !warned.iter().any(|p| p == path) {
+        warned.push(path.to_string());
+        log::warn!("{}", msg);
+    }
+}
+
+/// Convert a straight-alpha RGBA image into a premultiplied tiny-skia pixmap.
+fn rgba_to_pixmap(img: &image::RgbaImage) -> Option<Pixmap> {
     let (w, h) = img.dimensions();
     let mut pixmap = Pixmap::new(w, h)?;
     // image crate gives RGBA, tiny-skia premultiplied RGBA
@@ -407,41 +741,139 @@ pub fn load_image(path: &str) -> Option<Pixmap> {
     Some(pixmap)
 }
 
-pub fn scale_image(src: &Pixmap, target_w: u32, target_h: u32, mode: &str) -> Pixmap {
+/// Decoded frames of an animated GIF or APNG, with per-frame display durations.
+pub struct Animation {
+    pub frames: Vec<Rc<Pixmap>>,
+    pub delays: Vec<Duration>,
+}
+
+/// Load all frames of an animated GIF/APNG.
+/// Returns `None` for still images (including single-frame GIFs and plain PNGs),
+/// which should keep going through `load_image`.
+pub fn load_animation(path: &str) -> Option<Animation> {
+    use image::AnimationDecoder;
+
+    if path.is_empty() { return None; }
+    let expanded = expand_path(path);
+    let lower = expanded.to_ascii_lowercase();
+    let open = || std::fs::File::open(&expanded).ok().map(std::io::BufReader::new);
+
+    let frames = if lower.ends_with(".gif") {
+        let decoder = image::codecs::gif::GifDecoder::new(open()?).ok()?;
+        decoder.into_frames().collect_frames().ok()?
+    } else if lower.ends_with(".png") || lower.ends_with(".apng") {
+        let decoder = image::codecs::png::PngDecoder::new(open()?).ok()?;
+        if !decoder.is_apng().ok()? { return None; }
+        decoder.apng().ok()?.into_frames().collect_frames().ok()?
+    } else {
+        return None;
+    };
+    if frames.len() < 2 { return None; }
+
+    let mut anim = Animation { frames: Vec::new(), delays: Vec::new() };
+    for frame in frames {
+        let delay = Duration::from(frame.delay());
+        // Browsers treat near-zero delays as 100ms; do the same to avoid busy redraws
+        let delay = if delay < Duration::from_millis(20) { Duration::from_millis(100) } else { delay };
+        anim.frames.push(Rc::new(rgba_to_pixmap(frame.buffer())?));
+        anim.delays.push(delay);
+    }
+    log::info!("Loaded animated image {} ({} frames)", expanded, anim.frames.len());
+    Some(anim)
+}
+
+/// Where a scaled image sits in its target when it doesn't cover it exactly:
+/// `x`/`y` run 0.0 (left/top) to 1.0 (right/bottom), then the offsets (device
+/// pixels) are added.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageAlign {
+    pub x: f32,
+    pub y: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+impl ImageAlign {
+    pub const CENTER: Self = Self { x: 0.5, y: 0.5, offset_x: 0.0, offset_y: 0.0 };
+}
+
+pub fn scale_image(src: &Pixmap, target_w: u32, target_h: u32, mode: &str, align: ImageAlign) -> Pixmap {
     let mut dest = Pixmap::new(target_w, target_h).unwrap();
-    let sw = src.width() as f32;
-    let sh = src.height() as f32;
+    let (sx, sy, tx, ty) = fit_transform(src.width() as f32, src.height() as f32, target_w, target_h, mode, align);
+
+    dest.draw_pixmap(
+        0, 0, src.as_ref(),
+        &PixmapPaint::default(),
+        Transform::from_scale(sx, sy).post_translate(tx, ty),
+        None,
+    );
+    dest
+}
+
+/// Render an SVG into a `target_w`×`target_h` pixmap using the same scale
+/// modes as `scale_image`, rasterizing at the final size so it stays sharp.
+pub fn load_svg_scaled(path: &str, target_w: u32, target_h: u32, mode: &str, align: ImageAlign) -> Option<Pixmap> {
+    if path.is_empty() { return None; }
+    let expanded = expand_path(path);
+    let data = std::fs::read(&expanded).ok()?;
+    let tree = resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default()).ok()?;
+    let mut pixmap = Pixmap::new(target_w, target_h)?;
+    let svg_size = tree.size();
+    let (sx, sy, tx, ty) = fit_transform(svg_size.width(), svg_size.height(), target_w, target_h, mode, align);
+    resvg::render(&tree, Transform::from_scale(sx, sy).post_translate(tx, ty), &mut pixmap.as_mut());
+    Some(pixmap)
+}
+
+/// Scale and offset that place a `sw`×`sh` source into the target for a scale mode.
+fn fit_transform(sw: f32, sh: f32, target_w: u32, target_h: u32, mode: &str, align: ImageAlign) -> (f32, f32, f32, f32) {
     let tw = target_w as f32;
     let th = target_h as f32;
 
-    let (sx, sy, tx, ty) = match mode {
-        "fill" => {
-            let scale = (tw / sw).max(th / sh);
-            let ox = (tw - sw * scale) / 2.0;
-            let oy = (th - sh * scale) / 2.0;
-            (scale, scale, ox, oy)
-        }
+    let (sx, sy) = match mode {
         "fit" => {
             let scale = (tw / sw).min(th / sh);
-            let ox = (tw - sw * scale) / 2.0;
-            let oy = (th - sh * scale) / 2.0;
-            (scale, scale, ox, oy)
+            (scale, scale)
         }
-        "stretch" => (tw / sw, th / sh, 0.0, 0.0),
-        "center" => (1.0, 1.0, (tw - sw) / 2.0, (th - sh) / 2.0),
-        _ => {
+        "stretch" => (tw / sw, th / sh),
+        "center" => (1.0, 1.0),
+        _ /* "fill" */ => {
             let scale = (tw / sw).max(th / sh);
-            let ox = (tw - sw * scale) / 2.0;
-            let oy = (th - sh * scale) / 2.0;
-            (scale, scale, ox, oy)
+            (scale, scale)
         }
     };
+    // Spare (or cropped, when negative) space is shared out by the alignment
+    let tx = (tw - sw * sx) * align.x + align.offset_x;
+    let ty = (th - sh * sy) * align.y + align.offset_y;
+    (sx, sy, tx, ty)
+}
 
-    dest.draw_pixmap(
-        0, 0, src.as_ref(),
-        &PixmapPaint::default(),
-        Transform::from_scale(sx, sy).post_translate(tx, ty),
-        None,
-    );
-    dest
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(px: [u8; 4]) -> Canvas {
+        let mut canvas = Canvas::new(2, 2);
+        for chunk in canvas.pixmap.data_mut().chunks_exact_mut(4) {
+            chunk.copy_from_slice(&px);
+        }
+        canvas
+    }
+
+    #[test]
+    fn apply_opacity_scales_every_channel() {
+        let mut canvas = filled([200, 100, 50, 255]);
+        canvas.apply_opacity(0.5);
+        for px in canvas.pixmap.data().chunks_exact(4) {
+            assert_eq!(px, [100, 50, 25, 128]);
+        }
+    }
+
+    #[test]
+    fn apply_opacity_full_is_unchanged() {
+        let mut canvas = filled([200, 100, 50, 255]);
+        canvas.apply_opacity(1.0);
+        for px in canvas.pixmap.data().chunks_exact(4) {
+            assert_eq!(px, [200, 100, 50, 255]);
+        }
+    }
 }
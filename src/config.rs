@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// A gallery source: either a folder path (all images inside are used) or an
@@ -37,17 +38,51 @@ impl<'de> Deserialize<'de> for GallerySetting {
     }
 }
 
-fn expand_tilde_path(path: &str) -> String {
-    if let Some(rest) = path.strip_prefix("~/") {
-        if let Ok(home) = std::env::var("HOME") {
-            return format!("{}/{}", home, rest);
+/// Expand a leading `~/` and `$VAR`/`${VAR}` references in a path. Unset
+/// variables expand to nothing, with a warning; a `$` not followed by a name
+/// is kept as is.
+pub fn expand_path(path: &str) -> String {
+    let path = match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    };
+    if !path.contains('$') {
+        return path;
+    }
+
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path.as_str();
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, tail) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after.find(|c: char| !is_name(c)).unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if name.is_empty() || !name.chars().all(is_name) {
+            out.push('$');
+            rest = after;
+            continue;
+        }
+        match std::env::var(name) {
+            Ok(value) => out.push_str(&value),
+            Err(_) => log::warn!("${} in path {:?} is not set, expanding it to nothing", name, path),
         }
+        rest = tail;
     }
-    path.to_string()
+    out.push_str(rest);
+    out
 }
 
 fn discover_images_in_dir(dir: &str) -> Vec<String> {
-    const EXTS: &[&str] = &["png", "jpg", "jpeg", "webp", "svg", "svgz"];
+    const EXTS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "svgz"];
     let mut images: Vec<String> = Vec::new();
     if let Ok(entries) = std::fs::read_dir(dir) {
         for entry in entries.flatten() {
@@ -78,7 +113,7 @@ impl GallerySetting {
                     log::warn!("analogue_gallery = \"bundled\" but no bundled faces directory found");
                     return Vec::new();
                 }
-                let expanded = expand_tilde_path(path);
+                let expanded = expand_path(path);
                 let p = std::path::Path::new(&expanded);
                 if p.is_dir() {
                     discover_images_in_dir(&expanded)
@@ -91,7 +126,7 @@ impl GallerySetting {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClockConfig {
     #[serde(default)]
     pub window: WindowConfig,
@@ -106,11 +141,15 @@ pub struct ClockConfig {
     #[serde(default)]
     pub battery: BatteryConfig,
     #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
     pub timezone: Vec<TimezoneEntry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowConfig {
+    #[serde(default = "default_backend")]
+    pub backend: Backend,
     #[serde(default = "default_layer")]
     pub layer: String,
     #[serde(default = "default_anchor")]
@@ -127,8 +166,102 @@ pub struct WindowConfig {
     pub opacity: f32,
     #[serde(default)]
     pub compact: bool,
+    /// Size of the clock in compact mode relative to full size; unset uses the
+    /// face's own default (see `WindowConfig::compact_scale`).
+    #[serde(default)]
+    pub compact_scale: Option<f32>,
+    /// Length of the analogue hands in compact mode relative to full size.
+    #[serde(default = "default_compact_hand_scale")]
+    pub compact_hand_scale: f32,
     #[serde(default)]
     pub output: Option<String>,
+    /// Where the clock was last placed on each output, by output name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub positions: BTreeMap<String, OutputPosition>,
+    /// Move back to an unplugged output when it is connected again, instead of
+    /// staying on the output the clock fell back to.
+    #[serde(default)]
+    pub return_to_output: bool,
+    #[serde(default)]
+    pub snap: bool,
+    #[serde(default = "default_snap_threshold")]
+    pub snap_threshold: i32,
+    #[serde(default = "default_drag_button")]
+    pub drag_button: DragButton,
+    /// Toggle drag lock with a right click.
+    #[serde(default)]
+    pub right_click_lock: bool,
+    /// Keep a faint padlock in the corner while dragging is locked.
+    #[serde(default)]
+    pub show_lock_indicator: bool,
+    #[serde(default)]
+    pub hide_when_fullscreen: bool,
+    /// Seconds after the pointer leaves before the clock dims, 0 to never dim.
+    #[serde(default)]
+    pub idle_dim_after: u64,
+    /// Opacity the clock dims to while idle.
+    #[serde(default = "default_idle_opacity")]
+    pub idle_opacity: f32,
+    #[serde(default = "default_true")]
+    pub antialias: bool,
+    /// Shortest sleep between animation frames, in milliseconds.
+    #[serde(default = "default_tick_ms")]
+    pub tick_ms: u64,
+    #[serde(default = "default_size_mode")]
+    pub size_mode: SizeMode,
+    /// Share of the output's width and height taken in `output` size mode.
+    #[serde(default = "default_output_fraction")]
+    pub output_fraction: f32,
+    /// Largest window width in pixels, 0 for no limit but the output's.
+    #[serde(default)]
+    pub max_width: u32,
+    /// Largest window height in pixels, 0 for no limit but the output's.
+    #[serde(default)]
+    pub max_height: u32,
+    /// Where the clock sits in a window larger than it: "center", or edges like "top left".
+    #[serde(default = "default_content_gravity")]
+    pub content_gravity: String,
+}
+
+/// Anchor and margins remembered for one output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputPosition {
+    #[serde(default = "default_anchor")]
+    pub anchor: String,
+    #[serde(default)]
+    pub margin_top: i32,
+    #[serde(default)]
+    pub margin_right: i32,
+    #[serde(default)]
+    pub margin_bottom: i32,
+    #[serde(default)]
+    pub margin_left: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// A wlr-layer-shell surface placed by anchor and margins
+    Layer,
+    /// A borderless xdg-shell toplevel, for compositors without layer-shell
+    Xdg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DragButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeMode {
+    /// The window is as large as the measured content
+    Content,
+    /// The window is a fraction of the output and the content is scaled to fill it
+    Output,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,16 +272,59 @@ pub struct ClockSettings {
     pub hour_format: u8,
     #[serde(default = "default_true")]
     pub show_seconds: bool,
+    /// Seconds on the sub-clocks; unset follows `show_seconds`.
+    #[serde(default)]
+    pub subclock_show_seconds: Option<bool>,
+    /// A short date line under each sub-clock, for zones a day ahead or behind.
+    #[serde(default)]
+    pub subclock_show_date: bool,
     #[serde(default = "default_true")]
     pub show_date: bool,
+    #[serde(default = "default_date_position")]
+    pub date_position: DatePosition,
+    #[serde(default)]
+    pub show_week: bool,
+    #[serde(default)]
+    pub secondary_line: String,
     #[serde(default = "default_date_format")]
     pub date_format: String,
+    /// Full strftime format for the main time, replacing `hour_format` and `show_seconds`.
+    #[serde(default)]
+    pub time_format: Option<String>,
+    /// Locale for day and month names, e.g. "de_DE"; empty follows `$LC_TIME`.
+    #[serde(default)]
+    pub locale: String,
     #[serde(default = "default_font")]
     pub font: String,
     #[serde(default = "default_font_size")]
     pub font_size: f32,
     #[serde(default = "default_diameter")]
     pub diameter: u32,
+    /// Bounds for `font_size` when it is changed at runtime.
+    #[serde(default = "default_min_font_size")]
+    pub min_font_size: f32,
+    #[serde(default = "default_max_font_size")]
+    pub max_font_size: f32,
+    /// Bounds for `diameter` when it is changed at runtime.
+    #[serde(default = "default_min_diameter")]
+    pub min_diameter: u32,
+    #[serde(default = "default_max_diameter")]
+    pub max_diameter: u32,
+    #[serde(default = "default_subclock_style")]
+    pub subclock_style: SubclockStyle,
+    /// Size of the sub-clocks relative to the size derived from the main face.
+    #[serde(default = "default_subclock_scale")]
+    pub subclock_scale: f32,
+    #[serde(default = "default_subclock_separator")]
+    pub subclock_separator: SubclockSeparator,
+    /// Colour of the separator; unset uses the text colour at 40% alpha.
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub subclock_separator_color: Option<[u8; 4]>,
+    #[serde(default = "default_ampm_style")]
+    pub ampm_style: AmPmStyle,
+    /// Briefly brighten the whole clock at the top of each hour.
+    #[serde(default)]
+    pub hour_flash: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -158,26 +334,94 @@ pub enum FaceMode {
     Analogue,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubclockStyle {
+    /// Label above the time as text
+    Digital,
+    /// A small dial with hands, label below
+    Analogue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubclockSeparator {
+    /// A thin solid rule
+    Line,
+    /// No rule
+    None,
+    /// A dotted rule
+    Dots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatePosition {
+    /// Under the time, above the week and secondary lines
+    Below,
+    /// Over the time, under the battery indicator
+    Above,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AmPmStyle {
+    /// " PM" after the time at full size
+    Inline,
+    /// A reduced "PM" to the right of the time, bottom-aligned
+    Small,
+    /// A reduced "PM" centred above the time
+    Stacked,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextEffect {
+    /// Plain text
+    None,
+    /// A contrasting outline all around the text
+    Outline,
+    /// A single offset drop shadow
+    Shadow,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThemeConfig {
-    #[serde(default = "default_fg_color", deserialize_with = "deserialize_color")]
+    #[serde(default = "default_fg_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
     pub fg_color: [u8; 4],
-    #[serde(default = "default_bg_color", deserialize_with = "deserialize_color")]
+    #[serde(default = "default_bg_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
     pub bg_color: [u8; 4],
-    #[serde(default = "default_fg_color", deserialize_with = "deserialize_color")]
+    #[serde(default = "default_fg_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
     pub hour_hand_color: [u8; 4],
-    #[serde(default = "default_fg_color", deserialize_with = "deserialize_color")]
+    #[serde(default = "default_fg_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
     pub minute_hand_color: [u8; 4],
-    #[serde(default = "default_second_hand_color", deserialize_with = "deserialize_color")]
+    #[serde(default = "default_second_hand_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
     pub second_hand_color: [u8; 4],
-    #[serde(default = "default_tick_color", deserialize_with = "deserialize_color")]
+    #[serde(default = "default_tick_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
     pub tick_color: [u8; 4],
     /// Draw a contrasting outline around all text for readability
     #[serde(default = "default_true")]
     pub text_outline: bool,
+    /// Effect behind all text; unset follows `text_outline`.
+    #[serde(default)]
+    pub text_effect: Option<TextEffect>,
+    /// Offset of the text shadow in pixels.
+    #[serde(default = "default_text_shadow_offset")]
+    pub text_shadow_dx: f32,
+    #[serde(default = "default_text_shadow_offset")]
+    pub text_shadow_dy: f32,
+    #[serde(default = "default_text_shadow_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
+    pub text_shadow_color: [u8; 4],
+    /// Outline radius in pixels; unset scales it with the text size.
+    #[serde(default)]
+    pub outline_width: Option<f32>,
+    /// Outline colour; unset picks black or white against the text colour.
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub outline_color: Option<[u8; 4]>,
     /// Auto-contrast mode: "auto" | "always" | "never"
-    /// "auto" activates when a gallery is configured, "always" always samples background
-    #[serde(default = "default_auto_contrast")]
+    /// "auto" activates when a gallery is configured, "always" always samples background.
+    /// Booleans are accepted as shorthand: `true` = "always", `false` = "never".
+    #[serde(default = "default_auto_contrast", deserialize_with = "deserialize_auto_contrast")]
     pub auto_contrast: String,
 }
 
@@ -192,6 +436,20 @@ pub struct BackgroundConfig {
     pub face_preset: String,
     #[serde(default = "default_image_scale")]
     pub image_scale: String,
+    /// Edge(s) the image is anchored to when cropped or letterboxed, e.g. "top" or "bottom left".
+    #[serde(default = "default_image_align")]
+    pub image_align: String,
+    /// Extra shift of the image in pixels (positive = right / down).
+    #[serde(default)]
+    pub image_offset_x: i32,
+    #[serde(default)]
+    pub image_offset_y: i32,
+    /// Darkening over background images (0–1), independent of `bg_color`'s alpha.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrim_opacity: Option<f32>,
+    /// Fade the digital scrim from transparent at the top to full strength at the bottom.
+    #[serde(default)]
+    pub scrim_gradient: bool,
     /// Gallery source for digital mode: a folder path or an explicit list of image paths.
     #[serde(default)]
     pub digital_gallery: Option<GallerySetting>,
@@ -206,6 +464,48 @@ pub struct BackgroundConfig {
     /// Persisted gallery index for analogue mode (restored on restart).
     #[serde(default)]
     pub gallery_analogue_index: usize,
+    /// Time-of-day/weekday slots that pick which gallery images are shown.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gallery_schedule: Vec<GallerySchedule>,
+}
+
+/// One `[[background.gallery_schedule]]` slot: while it matches, the gallery
+/// shows (and rotates through) only the listed image indices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GallerySchedule {
+    /// Hour range "START-END" (24h, END exclusive); wraps past midnight, e.g. "22-6".
+    pub hours: String,
+    /// Weekdays the slot applies to ("mon", "tuesday", ...); empty = every day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<String>,
+    /// Digital gallery indices for this slot; empty leaves the digital gallery alone.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub digital: Vec<usize>,
+    /// Analogue gallery indices for this slot; empty leaves the analogue gallery alone.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub analogue: Vec<usize>,
+}
+
+impl GallerySchedule {
+    /// The `hours` range as (start, end), or `None` if it doesn't parse.
+    pub fn hour_range(&self) -> Option<(u32, u32)> {
+        let (start, end) = self.hours.split_once('-')?;
+        let start: u32 = start.trim().parse().ok()?;
+        let end: u32 = end.trim().parse().ok()?;
+        (start < 24 && end <= 24 && start != end).then_some((start, end))
+    }
+
+    /// Whether the slot covers `hour` on `weekday`. Unparseable hours or days never match.
+    pub fn matches(&self, weekday: chrono::Weekday, hour: u32) -> bool {
+        let Some((start, end)) = self.hour_range() else { return false };
+        let in_hours = if start < end {
+            (start..end).contains(&hour)
+        } else {
+            hour >= start || hour < end
+        };
+        in_hours && (self.days.is_empty()
+            || self.days.iter().any(|d| d.parse::<chrono::Weekday>().is_ok_and(|d| d == weekday)))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -214,16 +514,98 @@ pub struct BatteryConfig {
     pub enabled: bool,
     #[serde(default = "default_true")]
     pub show_percentage: bool,
+    #[serde(default = "default_battery_style")]
+    pub style: BatteryStyle,
+    #[serde(default = "default_battery_orientation")]
+    pub orientation: BatteryOrientation,
+    /// Sweep a highlight across the icon's fill while charging
+    #[serde(default)]
+    pub charge_animation: bool,
+}
+
+/// Shell commands run on clock events. Empty strings (the default) run nothing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Run at the start of every local hour
+    #[serde(default)]
+    pub on_hour: String,
+    /// Run when an alarm set with `clockie ctl alarm` goes off
+    #[serde(default)]
+    pub on_alarm: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatteryStyle {
+    /// Battery icon in the top-right corner
+    Icon,
+    /// Charge arc around the dial rim (analogue face only)
+    Ring,
 }
 
+fn default_battery_style() -> BatteryStyle { BatteryStyle::Icon }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatteryOrientation {
+    /// Nub on the right, fills left to right
+    Horizontal,
+    /// Nub on top, fills bottom to top
+    Vertical,
+}
+
+fn default_battery_orientation() -> BatteryOrientation { BatteryOrientation::Horizontal }
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimezoneEntry {
     pub label: String,
     pub tz: String,
+    /// Colour of this sub-clock's time (or dial); unset uses the theme text colour.
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub color: Option<[u8; 4]>,
+    /// Colour of the label; unset keeps the muted text colour.
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub label_color: Option<[u8; 4]>,
+    /// Draw this sub-clock larger and first; at most one entry may set it.
+    #[serde(default)]
+    pub primary: bool,
+}
+
+impl ClockConfig {
+    /// Move the primary sub-clock to the front so it is drawn first. Only the
+    /// first entry marked `primary` keeps the flag; later ones are cleared
+    /// with a warning.
+    pub fn order_timezones(&mut self) {
+        let Some(first) = self.timezone.iter().position(|tz| tz.primary) else { return };
+        for tz in self.timezone.iter_mut().skip(first + 1).filter(|tz| tz.primary) {
+            log::warn!("Sub-clock {:?}: only one [[timezone]] can be primary, ignoring it here", tz.label);
+            tz.primary = false;
+        }
+        let primary = self.timezone.remove(first);
+        self.timezone.insert(0, primary);
+    }
+}
+
+impl TimezoneEntry {
+    /// An entry labelled with its zone name and default colours, as `--tz1`/`--tz2` create.
+    pub fn from_tz(tz: &str) -> Self {
+        Self { label: tz.to_string(), tz: tz.to_string(), color: None, label_color: None, primary: false }
+    }
 }
 
 // ── Analogue theming ─────────────────────────────────────────
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SweepEasing {
+    /// Jump once a second
+    Tick,
+    /// Glide continuously round the dial
+    Linear,
+    /// Jump, overshoot and settle back, like a mechanical movement
+    Bounce,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum HandCap {
@@ -255,6 +637,10 @@ pub enum NumeralStyle {
     None,
     Arabic,
     Roman,
+    /// Arabic numerals at 12, 3, 6 and 9 only
+    Quarters,
+    /// Labels taken from `numeral_labels`
+    Custom,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -276,14 +662,30 @@ pub struct AnalogueConfig {
     pub second_hand_length: f32,
     #[serde(default = "default_second_hand_width")]
     pub second_hand_width: f32,
+    #[serde(default = "default_sweep_easing")]
+    pub sweep_easing: SweepEasing,
     #[serde(default)]
     pub hand_shadow: bool,
+    #[serde(default)]
+    pub hand_outline: bool,
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub hand_outline_color: Option<[u8; 4]>,
 
     // Ticks
     #[serde(default = "default_tick_visibility")]
     pub show_ticks: TickVisibility,
     #[serde(default = "default_tick_style")]
     pub tick_style: TickStyle,
+    /// Line width in pixels; Dot/Diamond scale their size by it
+    #[serde(default = "default_hour_tick_width")]
+    pub hour_tick_width: f32,
+    #[serde(default = "default_minute_tick_width")]
+    pub minute_tick_width: f32,
+    /// Tick length as a fraction of the radius, measured in from the rim
+    #[serde(default = "default_hour_tick_length")]
+    pub hour_tick_length: f32,
+    #[serde(default = "default_minute_tick_length")]
+    pub minute_tick_length: f32,
 
     // Numerals
     #[serde(default = "default_numeral_style")]
@@ -292,21 +694,42 @@ pub struct AnalogueConfig {
     pub numeral_size: f32,
     #[serde(default = "default_numeral_inset")]
     pub numeral_inset: f32,
+    #[serde(default)]
+    pub numeral_labels: Vec<String>,
+    #[serde(default)]
+    pub minute_numbers: bool,
+    /// Small HH:MM readout between the centre and 6 o'clock
+    #[serde(default)]
+    pub digital_readout: bool,
+    /// Still draw ticks and numerals when a face image replaces the procedural face
+    #[serde(default)]
+    pub ticks_over_image: bool,
 
     // Decorations
-    #[serde(default, deserialize_with = "deserialize_optional_color")]
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
     pub face_fill: Option<[u8; 4]>,
     #[serde(default)]
     pub bezel_width: f32,
-    #[serde(default = "default_fg_color", deserialize_with = "deserialize_color")]
-    pub bezel_color: [u8; 4],
+    /// Colour of the bezel ring, the thin default one included; see [`AnalogueConfig::bezel_color`].
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub bezel_color: Option<[u8; 4]>,
     #[serde(default)]
     pub minute_track_width: f32,
-    #[serde(default = "default_tick_color", deserialize_with = "deserialize_color")]
+    #[serde(default = "default_tick_color", deserialize_with = "deserialize_color", serialize_with = "serialize_color")]
     pub minute_track_color: [u8; 4],
+    #[serde(default = "default_boss_radius")]
+    pub boss_radius: f32,
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub boss_color: Option<[u8; 4]>,
+    #[serde(default, deserialize_with = "deserialize_optional_color", serialize_with = "serialize_optional_color", skip_serializing_if = "Option::is_none")]
+    pub boss_inner_color: Option<[u8; 4]>,
+    /// Space around the dial at its natural size, in pixels.
+    #[serde(default = "default_analogue_padding")]
+    pub padding: f32,
 }
 
 fn default_hand_cap() -> HandCap { HandCap::Round }
+fn default_sweep_easing() -> SweepEasing { SweepEasing::Tick }
 fn default_hour_hand_length() -> f32 { 0.55 }
 fn default_hour_hand_width() -> f32 { 0.06 }
 fn default_minute_hand_length() -> f32 { 0.75 }
@@ -315,9 +738,15 @@ fn default_second_hand_length() -> f32 { 0.85 }
 fn default_second_hand_width() -> f32 { 0.02 }
 fn default_tick_visibility() -> TickVisibility { TickVisibility::All60 }
 fn default_tick_style() -> TickStyle { TickStyle::Line }
+fn default_hour_tick_width() -> f32 { 2.5 }
+fn default_minute_tick_width() -> f32 { 1.0 }
+fn default_hour_tick_length() -> f32 { 0.13 }
+fn default_minute_tick_length() -> f32 { 0.06 }
 fn default_numeral_style() -> NumeralStyle { NumeralStyle::None }
 fn default_numeral_size() -> f32 { 0.18 }
 fn default_numeral_inset() -> f32 { 0.15 }
+fn default_boss_radius() -> f32 { 0.05 }
+fn default_analogue_padding() -> f32 { 12.0 }
 
 impl Default for AnalogueConfig {
     fn default() -> Self {
@@ -330,21 +759,61 @@ impl Default for AnalogueConfig {
             minute_hand_width: default_minute_hand_width(),
             second_hand_length: default_second_hand_length(),
             second_hand_width: default_second_hand_width(),
+            sweep_easing: default_sweep_easing(),
             hand_shadow: false,
+            hand_outline: false,
+            hand_outline_color: None,
             show_ticks: default_tick_visibility(),
             tick_style: default_tick_style(),
+            hour_tick_width: default_hour_tick_width(),
+            minute_tick_width: default_minute_tick_width(),
+            hour_tick_length: default_hour_tick_length(),
+            minute_tick_length: default_minute_tick_length(),
             numerals: default_numeral_style(),
             numeral_size: default_numeral_size(),
             numeral_inset: default_numeral_inset(),
+            numeral_labels: Vec::new(),
+            minute_numbers: false,
+            digital_readout: false,
+            ticks_over_image: false,
             face_fill: None,
             bezel_width: 0.0,
-            bezel_color: default_fg_color(),
+            bezel_color: None,
             minute_track_width: 0.0,
             minute_track_color: default_tick_color(),
+            boss_radius: default_boss_radius(),
+            boss_color: None,
+            boss_inner_color: None,
+            padding: default_analogue_padding(),
         }
     }
 }
 
+impl AnalogueConfig {
+    /// The bezel ring's colour: `bezel_color`, else white for a `bezel_width`
+    /// ring and light grey for the thin default one, as they have always been.
+    pub fn bezel_color(&self) -> [u8; 4] {
+        match self.bezel_color {
+            Some(color) => color,
+            None if self.bezel_width > 0.0 => default_fg_color(),
+            None => default_tick_color(),
+        }
+    }
+
+    /// Hand lengths above 1.0, which would reach past the dial; they are drawn
+    /// clamped to the inside of the bezel.
+    pub fn overlong_hands(&self) -> Vec<(&'static str, f32)> {
+        [
+            ("hour_hand_length", self.hour_hand_length),
+            ("minute_hand_length", self.minute_hand_length),
+            ("second_hand_length", self.second_hand_length),
+        ]
+        .into_iter()
+        .filter(|&(_, length)| length > 1.0)
+        .collect()
+    }
+}
+
 fn deserialize_optional_color<'de, D: Deserializer<'de>>(d: D) -> Result<Option<[u8; 4]>, D::Error> {
     let s = Option::<String>::deserialize(d)?;
     match s {
@@ -355,21 +824,42 @@ fn deserialize_optional_color<'de, D: Deserializer<'de>>(d: D) -> Result<Option<
 
 // Defaults
 
+fn default_backend() -> Backend { Backend::Layer }
 fn default_layer() -> String { "top".into() }
 fn default_anchor() -> String { "top right".into() }
 fn default_margin() -> i32 { 20 }
 fn default_true() -> bool { true }
 fn default_opacity() -> f32 { 1.0 }
+fn default_idle_opacity() -> f32 { 0.4 }
+fn default_snap_threshold() -> i32 { 16 }
+fn default_drag_button() -> DragButton { DragButton::Left }
+fn default_tick_ms() -> u64 { 50 }
+fn default_size_mode() -> SizeMode { SizeMode::Content }
+fn default_output_fraction() -> f32 { 1.0 }
+fn default_content_gravity() -> String { "center".into() }
+fn default_compact_hand_scale() -> f32 { 0.8 }
+fn default_subclock_scale() -> f32 { 1.0 }
 fn default_face() -> FaceMode { FaceMode::Digital }
+fn default_subclock_style() -> SubclockStyle { SubclockStyle::Digital }
+fn default_subclock_separator() -> SubclockSeparator { SubclockSeparator::Line }
+fn default_ampm_style() -> AmPmStyle { AmPmStyle::Inline }
+fn default_date_position() -> DatePosition { DatePosition::Below }
 fn default_hour_format() -> u8 { 12 }
 fn default_date_format() -> String { "%A, %d %B %Y".into() }
 fn default_font() -> String { "monospace".into() }
 fn default_font_size() -> f32 { 48.0 }
 fn default_diameter() -> u32 { 180 }
+fn default_min_font_size() -> f32 { 10.0 }
+fn default_max_font_size() -> f32 { 1000.0 }
+fn default_min_diameter() -> u32 { 40 }
+fn default_max_diameter() -> u32 { 4000 }
 fn default_image_scale() -> String { "fill".into() }
+fn default_image_align() -> String { "center".into() }
 
 fn default_auto_contrast() -> String { "auto".into() }
 fn default_fg_color() -> [u8; 4] { [0xFF, 0xFF, 0xFF, 0xFF] }
+fn default_text_shadow_offset() -> f32 { 1.5 }
+fn default_text_shadow_color() -> [u8; 4] { [0x00, 0x00, 0x00, 0x99] }
 fn default_bg_color() -> [u8; 4] { [0x00, 0x00, 0x00, 0xCC] }
 fn default_second_hand_color() -> [u8; 4] { [0xFF, 0x44, 0x44, 0xFF] }
 fn default_tick_color() -> [u8; 4] { [0xCC, 0xCC, 0xCC, 0xFF] }
@@ -379,6 +869,42 @@ fn deserialize_color<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 4], D::Erro
     parse_color(&s).map_err(serde::de::Error::custom)
 }
 
+fn serialize_color<S: serde::Serializer>(color: &[u8; 4], s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&format_color(*color))
+}
+
+fn serialize_optional_color<S: serde::Serializer>(color: &Option<[u8; 4]>, s: S) -> Result<S::Ok, S::Error> {
+    match color {
+        Some(c) => s.serialize_str(&format_color(*c)),
+        None => s.serialize_none(),
+    }
+}
+
+/// Format a colour as `RRGGBBAA`, the inverse of `parse_color`.
+fn format_color(c: [u8; 4]) -> String {
+    format!("{:02X}{:02X}{:02X}{:02X}", c[0], c[1], c[2], c[3])
+}
+
+fn deserialize_auto_contrast<'de, D: Deserializer<'de>>(d: D) -> Result<String, D::Error> {
+    struct Visitor;
+    impl<'de> serde::de::Visitor<'de> for Visitor {
+        type Value = String;
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("\"auto\", \"always\", \"never\", or a boolean")
+        }
+        fn visit_bool<E: serde::de::Error>(self, v: bool) -> Result<Self::Value, E> {
+            Ok(if v { "always" } else { "never" }.to_string())
+        }
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            match v {
+                "auto" | "always" | "never" => Ok(v.to_string()),
+                other => Err(E::custom(format!("unknown auto_contrast mode: {}", other))),
+            }
+        }
+    }
+    d.deserialize_any(Visitor)
+}
+
 pub fn parse_color(s: &str) -> Result<[u8; 4]> {
     let s = s.trim_start_matches('#');
     anyhow::ensure!(s.len() == 6 || s.len() == 8, "Color must be RRGGBB or RRGGBBAA");
@@ -391,25 +917,59 @@ pub fn parse_color(s: &str) -> Result<[u8; 4]> {
 
 // Implementations
 
-impl Default for ClockConfig {
+impl Default for BatteryConfig {
     fn default() -> Self {
         Self {
-            window: WindowConfig::default(),
-            clock: ClockSettings::default(),
-            theme: ThemeConfig::default(),
-            background: BackgroundConfig::default(),
-            analogue: AnalogueConfig::default(),
-            battery: BatteryConfig::default(),
-            timezone: Vec::new(),
+            enabled: false,
+            show_percentage: true,
+            style: default_battery_style(),
+            orientation: default_battery_orientation(),
+            charge_animation: false,
         }
     }
 }
 
-impl Default for BatteryConfig {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            show_percentage: true,
+impl WindowConfig {
+    /// `content_gravity` as fractions of the free space left of and above the
+    /// clock: 0.0 hugs the left/top edge, 1.0 the right/bottom one.
+    pub fn content_gravity(&self) -> (f32, f32) {
+        alignment_fractions(&self.content_gravity)
+    }
+
+    /// The current anchor and margins.
+    pub fn position(&self) -> OutputPosition {
+        OutputPosition {
+            anchor: self.anchor.clone(),
+            margin_top: self.margin_top,
+            margin_right: self.margin_right,
+            margin_bottom: self.margin_bottom,
+            margin_left: self.margin_left,
+        }
+    }
+
+    /// Take the anchor and margins from a remembered position.
+    pub fn set_position(&mut self, position: &OutputPosition) {
+        self.anchor = position.anchor.clone();
+        self.margin_top = position.margin_top;
+        self.margin_right = position.margin_right;
+        self.margin_bottom = position.margin_bottom;
+        self.margin_left = position.margin_left;
+    }
+
+    /// Redraw interval of animations (charging, animated backgrounds), at least 10ms.
+    pub fn frame_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.tick_ms.max(10))
+    }
+
+    /// Compact size relative to full size: `compact_scale` (kept within
+    /// 0.1..=1.0), else 70% for the digital time or 75% for the analogue dial.
+    pub fn compact_scale(&self, face: FaceMode) -> f32 {
+        match self.compact_scale {
+            Some(scale) => scale.clamp(0.1, 1.0),
+            None => match face {
+                FaceMode::Digital => 0.7,
+                FaceMode::Analogue => 0.75,
+            },
         }
     }
 }
@@ -417,6 +977,7 @@ impl Default for BatteryConfig {
 impl Default for WindowConfig {
     fn default() -> Self {
         Self {
+            backend: default_backend(),
             layer: default_layer(),
             anchor: default_anchor(),
             margin_top: default_margin(),
@@ -425,7 +986,26 @@ impl Default for WindowConfig {
             margin_right: default_margin(),
             opacity: default_opacity(),
             compact: false,
+            compact_scale: None,
+            compact_hand_scale: default_compact_hand_scale(),
             output: None,
+            positions: BTreeMap::new(),
+            return_to_output: false,
+            snap: false,
+            snap_threshold: default_snap_threshold(),
+            drag_button: default_drag_button(),
+            right_click_lock: false,
+            show_lock_indicator: false,
+            hide_when_fullscreen: false,
+            idle_dim_after: 0,
+            idle_opacity: default_idle_opacity(),
+            antialias: true,
+            tick_ms: default_tick_ms(),
+            size_mode: default_size_mode(),
+            output_fraction: default_output_fraction(),
+            content_gravity: default_content_gravity(),
+            max_width: 0,
+            max_height: 0,
         }
     }
 }
@@ -436,15 +1016,39 @@ impl Default for ClockSettings {
             face: default_face(),
             hour_format: default_hour_format(),
             show_seconds: true,
+            subclock_show_seconds: None,
+            subclock_show_date: false,
             show_date: true,
+            date_position: default_date_position(),
+            show_week: false,
+            secondary_line: String::new(),
             date_format: default_date_format(),
+            time_format: None,
+            locale: String::new(),
             font: default_font(),
             font_size: default_font_size(),
             diameter: default_diameter(),
+            min_font_size: default_min_font_size(),
+            max_font_size: default_max_font_size(),
+            min_diameter: default_min_diameter(),
+            max_diameter: default_max_diameter(),
+            subclock_style: default_subclock_style(),
+            subclock_scale: default_subclock_scale(),
+            subclock_separator: default_subclock_separator(),
+            subclock_separator_color: None,
+            ampm_style: default_ampm_style(),
+            hour_flash: false,
         }
     }
 }
 
+impl ThemeConfig {
+    /// The configured `text_effect`, or an outline or nothing per `text_outline`.
+    pub fn effective_text_effect(&self) -> TextEffect {
+        self.text_effect.unwrap_or(if self.text_outline { TextEffect::Outline } else { TextEffect::None })
+    }
+}
+
 impl Default for ThemeConfig {
     fn default() -> Self {
         Self {
@@ -455,6 +1059,12 @@ impl Default for ThemeConfig {
             second_hand_color: default_second_hand_color(),
             tick_color: default_tick_color(),
             text_outline: true,
+            text_effect: None,
+            text_shadow_dx: default_text_shadow_offset(),
+            text_shadow_dy: default_text_shadow_offset(),
+            text_shadow_color: default_text_shadow_color(),
+            outline_width: None,
+            outline_color: None,
             auto_contrast: default_auto_contrast(),
         }
     }
@@ -467,10 +1077,16 @@ impl Default for BackgroundConfig {
             analogue_face_image: String::new(),
             face_preset: String::new(),
             image_scale: default_image_scale(),
+            image_align: default_image_align(),
+            image_offset_x: 0,
+            image_offset_y: 0,
+            scrim_opacity: None,
+            scrim_gradient: false,
             digital_gallery: None,
             analogue_gallery: None,
             gallery_interval: 0,
             gallery_digital_index: 0,
+            gallery_schedule: Vec::new(),
             gallery_analogue_index: 0,
         }
     }
@@ -483,6 +1099,7 @@ impl Default for BackgroundConfig {
 ///   direct path (with tilde expansion) and returns it if the file exists.
 /// - Otherwise treats `name` as a preset and searches XDG data dirs for
 ///   `clockie/faces/{name}.svg`.
+///
 /// Search for the bundled faces directory: checks XDG data dirs first,
 /// then falls back to a path relative to the running executable (for Nix).
 pub fn find_bundled_faces_dir() -> Option<String> {
@@ -528,7 +1145,7 @@ pub fn resolve_face_preset(name: &str) -> Option<String> {
     if name.contains(std::path::MAIN_SEPARATOR) || name.contains('/')
         || name.ends_with(".svg") || name.ends_with(".svgz")
     {
-        let expanded = expand_tilde_path(name);
+        let expanded = expand_path(name);
         return if std::path::Path::new(&expanded).exists() {
             Some(expanded)
         } else {
@@ -550,6 +1167,30 @@ pub fn resolve_face_preset(name: &str) -> Option<String> {
 }
 
 impl BackgroundConfig {
+    /// Colour of the overlay drawn over a background image: `bg_color` with
+    /// its alpha replaced by `scrim_opacity` when that is set.
+    pub fn scrim_color(&self, bg_color: [u8; 4]) -> [u8; 4] {
+        match self.scrim_opacity {
+            Some(opacity) => {
+                let alpha = (opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+                [bg_color[0], bg_color[1], bg_color[2], alpha]
+            }
+            None => bg_color,
+        }
+    }
+
+    /// Image placement from `image_align` and the pixel offsets, for a canvas
+    /// at `scale` device pixels per logical pixel. Unknown words are ignored.
+    pub fn image_alignment(&self, scale: f32) -> crate::canvas::ImageAlign {
+        let (x, y) = alignment_fractions(&self.image_align);
+        crate::canvas::ImageAlign {
+            x,
+            y,
+            offset_x: self.image_offset_x as f32 * scale,
+            offset_y: self.image_offset_y as f32 * scale,
+        }
+    }
+
     /// Return the effective list of digital background images.
     /// Resolves `digital_gallery` if set, else falls back to `digital_image`.
     pub fn effective_digital_images(&self) -> Vec<String> {
@@ -582,6 +1223,44 @@ impl BackgroundConfig {
     }
 }
 
+impl ClockSettings {
+    /// The locale the strftime formats spell day and month names in.
+    pub fn locale(&self) -> chrono::Locale {
+        crate::time_utils::resolve_locale(&self.locale)
+    }
+
+    /// The custom time format, if set and valid. Invalid formats fall back to
+    /// the simple fields (`--check` reports them).
+    pub fn effective_time_format(&self) -> Option<&str> {
+        self.time_format.as_deref()
+            .filter(|fmt| !fmt.is_empty() && crate::time_utils::is_valid_format(fmt))
+    }
+
+    /// Whether the sub-clocks show seconds.
+    pub fn subclock_seconds(&self) -> bool {
+        self.subclock_show_seconds.unwrap_or(self.show_seconds)
+    }
+
+    /// The secondary line format, if non-empty and valid.
+    pub fn effective_secondary_line(&self) -> Option<&str> {
+        Some(self.secondary_line.as_str())
+            .filter(|fmt| !fmt.is_empty() && crate::time_utils::is_valid_format(fmt))
+    }
+
+    /// Clamp a font size to `min_font_size..=max_font_size` (a max below the
+    /// min is treated as equal to it).
+    pub fn clamp_font_size(&self, size: f32) -> f32 {
+        size.max(self.min_font_size).min(self.max_font_size.max(self.min_font_size))
+    }
+
+    /// Clamp a diameter to `min_diameter..=max_diameter`; takes a signed value
+    /// so deltas that overshoot zero clamp cleanly.
+    pub fn clamp_diameter(&self, diameter: i64) -> u32 {
+        let max = self.max_diameter.max(self.min_diameter);
+        diameter.clamp(self.min_diameter as i64, max as i64) as u32
+    }
+}
+
 impl FaceMode {
     pub fn toggle(&self) -> Self {
         match self {
@@ -657,6 +1336,16 @@ pub fn save_margins_to_config(path: &std::path::Path, top: i32, right: i32, bott
     log::info!("Persisted margins to {}", path.display());
 }
 
+pub fn save_anchor_to_config(path: &std::path::Path, anchor: &str) {
+    let Some(mut doc) = read_config_doc(path) else { return };
+    ensure_window_table(&mut doc);
+
+    doc["window"]["anchor"] = toml_edit::value(anchor);
+
+    write_config_doc(path, &doc);
+    log::info!("Persisted anchor={} to {}", anchor, path.display());
+}
+
 pub fn save_output_to_config(path: &std::path::Path, output_name: &str) {
     let Some(mut doc) = read_config_doc(path) else { return };
     ensure_window_table(&mut doc);
@@ -667,6 +1356,34 @@ pub fn save_output_to_config(path: &std::path::Path, output_name: &str) {
     log::info!("Persisted output to {}", path.display());
 }
 
+/// Remember `position` for the output `output_name` under
+/// `[window.positions."<name>"]`, replacing what was stored for it.
+pub fn save_output_position_to_config(path: &std::path::Path, output_name: &str, position: &OutputPosition) {
+    let Some(mut doc) = read_config_doc(path) else { return };
+    ensure_window_table(&mut doc);
+
+    let Some(window) = doc["window"].as_table_mut() else { return };
+    let positions = window.entry("positions").or_insert_with(|| {
+        let mut table = toml_edit::Table::new();
+        table.set_implicit(true);
+        toml_edit::Item::Table(table)
+    });
+    let Some(positions) = positions.as_table_mut() else {
+        log::warn!("[window] positions in {} is not a table, not saving the position", path.display());
+        return;
+    };
+    let mut entry = toml_edit::Table::new();
+    entry["anchor"] = toml_edit::value(position.anchor.as_str());
+    entry["margin_top"] = toml_edit::value(position.margin_top as i64);
+    entry["margin_right"] = toml_edit::value(position.margin_right as i64);
+    entry["margin_bottom"] = toml_edit::value(position.margin_bottom as i64);
+    entry["margin_left"] = toml_edit::value(position.margin_left as i64);
+    positions[output_name] = toml_edit::Item::Table(entry);
+
+    write_config_doc(path, &doc);
+    log::info!("Persisted position on {} to {}", output_name, path.display());
+}
+
 pub fn save_face_to_config(path: &std::path::Path, face: FaceMode) {
     let Some(mut doc) = read_config_doc(path) else { return };
     ensure_clock_table(&mut doc);
@@ -701,6 +1418,26 @@ pub fn save_diameter_to_config(path: &std::path::Path, diameter: u32) {
     log::info!("Persisted diameter={} to {}", diameter, path.display());
 }
 
+pub fn save_hour_format_to_config(path: &std::path::Path, hour_format: u8) {
+    let Some(mut doc) = read_config_doc(path) else { return };
+    ensure_clock_table(&mut doc);
+
+    doc["clock"]["hour_format"] = toml_edit::value(hour_format as i64);
+
+    write_config_doc(path, &doc);
+    log::info!("Persisted hour_format={} to {}", hour_format, path.display());
+}
+
+pub fn save_date_format_to_config(path: &std::path::Path, date_format: &str) {
+    let Some(mut doc) = read_config_doc(path) else { return };
+    ensure_clock_table(&mut doc);
+
+    doc["clock"]["date_format"] = toml_edit::value(date_format);
+
+    write_config_doc(path, &doc);
+    log::info!("Persisted date_format={:?} to {}", date_format, path.display());
+}
+
 pub fn save_font_size_to_config(path: &std::path::Path, font_size: f32) {
     let Some(mut doc) = read_config_doc(path) else { return };
     ensure_clock_table(&mut doc);
@@ -729,8 +1466,42 @@ pub fn save_gallery_indices_to_config(path: &std::path::Path, digital_index: usi
     log::info!("Persisted gallery indices digital={} analogue={} to {}", digital_index, analogue_index, path.display());
 }
 
+/// The first `clockie/config.toml` under `$XDG_CONFIG_DIRS` (default
+/// `/etc/xdg`), for system-wide defaults shipped by a distribution.
+pub fn system_config_path() -> Option<PathBuf> {
+    let dirs = std::env::var("XDG_CONFIG_DIRS").ok().filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/etc/xdg".into());
+    dirs.split(':')
+        .filter(|d| !d.is_empty())
+        .map(|d| PathBuf::from(d).join("clockie").join("config.toml"))
+        .find(|p| p.is_file())
+}
+
+/// Config to use when `path` doesn't exist, without creating it: the
+/// system-wide config if there is one, else the built-in defaults.
+pub fn fallback_config() -> Result<ClockConfig> {
+    match system_config_path() {
+        Some(system) => load_config(&system),
+        None => Ok(ClockConfig::default()),
+    }
+}
+
 pub fn load_config(path: &std::path::Path) -> Result<ClockConfig> {
     if !path.exists() {
+        if let Some(system) = system_config_path() {
+            // Layer the user's file over the system one so runtime changes land there
+            log::info!("Config file not found at {}, creating one that includes {}", path.display(), system.display());
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            return match std::fs::write(path, generate_user_config(&system)) {
+                Ok(()) => load_config(path),
+                Err(e) => {
+                    log::warn!("Failed to write config: {}, using {} directly", e, system.display());
+                    load_config(&system)
+                }
+            };
+        }
         log::info!("Config file not found at {}, generating default", path.display());
         let content = generate_default_config();
         if let Some(parent) = path.parent() {
@@ -744,17 +1515,418 @@ pub fn load_config(path: &std::path::Path) -> Result<ClockConfig> {
     }
     let content = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read config: {}", path.display()))?;
-    let config: ClockConfig = toml::from_str(&content)
+    let raw: toml::Table = toml::from_str(&content)
         .with_context(|| format!("Failed to parse config: {}", path.display()))?;
+    // Parsing the text directly keeps line numbers in errors when there is nothing to merge
+    let mut config: ClockConfig = if raw.contains_key("include") {
+        read_config_table(path)?.try_into()
+            .with_context(|| format!("Failed to parse config: {} (with its includes)", path.display()))?
+    } else {
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config: {}", path.display()))?
+    };
+    if let Some(fmt) = config.clock.time_format.as_deref().filter(|f| !crate::time_utils::is_valid_format(f)) {
+        log::warn!("Invalid time_format {:?}, using hour_format/show_seconds instead", fmt);
+    }
+    if !config.clock.locale.is_empty() && crate::time_utils::parse_locale(&config.clock.locale).is_none() {
+        log::warn!("Unknown locale {:?}, using English day and month names", config.clock.locale);
+    }
+    if !crate::time_utils::is_valid_format(&config.clock.secondary_line) {
+        log::warn!("Invalid secondary_line {:?}, hiding it", config.clock.secondary_line);
+    }
+    if config.analogue.numerals == NumeralStyle::Custom && config.analogue.numeral_labels.len() != 12 {
+        log::warn!(
+            "numeral_labels has {} entries, expected 12 (missing positions are left blank, extras ignored)",
+            config.analogue.numeral_labels.len(),
+        );
+    }
+    for (key, length) in config.analogue.overlong_hands() {
+        log::warn!("[analogue] {} = {} reaches past the dial, clamping it to the bezel", key, length);
+    }
+    for tz in config.timezone.iter().filter(|tz| tz.tz.parse::<chrono_tz::Tz>().is_err()) {
+        log::warn!("Sub-clock {:?}: unknown timezone {:?} (expected an IANA name like Europe/London)", tz.label, tz.tz);
+    }
+    config.order_timezones();
     Ok(config)
 }
 
+/// Read a config file as a TOML table with the files in its top-level
+/// `include` array merged underneath it. Includes are resolved relative to the
+/// including file (`~/` is expanded), later ones override earlier ones, and
+/// the including file overrides them all. Tables merge key by key; any other
+/// value, including arrays such as `[[timezone]]`, is replaced whole.
+pub fn read_config_table(path: &std::path::Path) -> Result<toml::Table> {
+    read_layered_table(path, &mut Vec::new())
+}
+
+/// `stack` holds the files currently being read, to detect cyclic includes.
+fn read_layered_table(path: &std::path::Path, stack: &mut Vec<PathBuf>) -> Result<toml::Table> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if let Some(start) = stack.iter().position(|p| *p == canonical) {
+        let cycle: Vec<String> = stack[start..].iter().chain([&canonical])
+            .map(|p| p.display().to_string())
+            .collect();
+        anyhow::bail!("Cyclic config include: {}", cycle.join(" -> "));
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config: {}", path.display()))?;
+    let mut table: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config: {}", path.display()))?;
+    let includes = match table.remove("include") {
+        None => return Ok(table),
+        Some(toml::Value::Array(items)) => items,
+        Some(_) => anyhow::bail!("{}: include must be an array of paths", path.display()),
+    };
+
+    let base_dir = path.parent().unwrap_or(std::path::Path::new("."));
+    stack.push(canonical);
+    let mut merged = toml::Table::new();
+    for item in includes {
+        let Some(name) = item.as_str() else {
+            anyhow::bail!("{}: include entries must be strings, got {}", path.display(), item);
+        };
+        let included = base_dir.join(expand_path(name));
+        merge_tables(&mut merged, read_layered_table(&included, stack)?);
+    }
+    stack.pop();
+    merge_tables(&mut merged, table);
+    Ok(merged)
+}
+
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Horizontal and vertical fractions for a position like "center" or
+/// "bottom left", 0.0 at the left/top. Unknown words are ignored.
+fn alignment_fractions(position: &str) -> (f32, f32) {
+    let (mut x, mut y) = (0.5, 0.5);
+    for word in position.split_whitespace() {
+        match word.to_lowercase().as_str() {
+            "left" => x = 0.0,
+            "right" => x = 1.0,
+            "top" => y = 0.0,
+            "bottom" => y = 1.0,
+            _ => {}
+        }
+    }
+    (x, y)
+}
+
+/// The first word of a position that `alignment_fractions` doesn't know.
+fn unknown_alignment_word(position: &str) -> Option<&str> {
+    position.split_whitespace()
+        .find(|w| !["center", "top", "bottom", "left", "right"].contains(&w.to_lowercase().as_str()))
+}
+
+/// Whether `key = ""` loads in `[section]`. It does for every colour read with
+/// `deserialize_optional_color`, where empty means unset.
+fn empty_color_allowed(section: &str, key: &str) -> bool {
+    let mut table = toml::Table::from_iter([(key.to_string(), toml::Value::from(""))]);
+    let value = if section == "timezone" {
+        table.entry("label").or_insert_with(|| "UTC".into());
+        table.entry("tz").or_insert_with(|| "UTC".into());
+        toml::Value::Array(vec![toml::Value::Table(table)])
+    } else {
+        toml::Value::Table(table)
+    };
+    let raw = toml::Table::from_iter([(section.to_string(), value)]);
+    raw.try_into::<ClockConfig>().is_ok()
+}
+
+/// Report and remove the colours in `table` that don't parse. `section` is the
+/// config section it belongs to and `name` how problems refer to it.
+fn drop_bad_colors(table: &mut toml::Table, section: &str, name: &str, problems: &mut Vec<String>) {
+    let mut bad = Vec::new();
+    for (key, value) in table.iter() {
+        if !(key.ends_with("_color") || key == "face_fill" || (section == "timezone" && key == "color")) { continue; }
+        let problem = match value.as_str() {
+            Some("") if empty_color_allowed(section, key) => None,
+            Some(v) => parse_color(v).err().map(|e| format!("{} {} = {:?}: {}", name, key, v, e)),
+            None => Some(format!("{} {} must be a colour string", name, key)),
+        };
+        if let Some(problem) = problem {
+            problems.push(problem);
+            bad.push(key.clone());
+        }
+    }
+    for key in bad {
+        table.remove(&key);
+    }
+}
+
+/// Load and fully validate a config file without side effects (no default
+/// config is generated). Returns every problem found, so a CI run can report
+/// them all at once; the parsed config is returned when it deserializes.
+pub fn validate_config(path: &std::path::Path) -> (Option<ClockConfig>, Vec<String>) {
+    let mut problems = Vec::new();
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            problems.push(format!("cannot read {}: {}", path.display(), e));
+            return (None, problems);
+        }
+    };
+    let mut raw: toml::Table = match toml::from_str(&content) {
+        Ok(t) => t,
+        Err(e) => {
+            problems.push(format!("invalid TOML: {}", e));
+            return (None, problems);
+        }
+    };
+    if raw.contains_key("include") {
+        match read_config_table(path) {
+            Ok(merged) => raw = merged,
+            Err(e) => {
+                problems.push(format!("{:#}", e));
+                return (None, problems);
+            }
+        }
+    }
+
+    // Colours are checked on the raw table first, and bad ones dropped, since
+    // deserialization would otherwise stop at the first one
+    for section in ["clock", "theme", "analogue"] {
+        if let Some(table) = raw.get_mut(section).and_then(|v| v.as_table_mut()) {
+            drop_bad_colors(table, section, &format!("[{}]", section), &mut problems);
+        }
+    }
+    if let Some(entries) = raw.get_mut("timezone").and_then(|v| v.as_array_mut()) {
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let Some(table) = entry.as_table_mut() else { continue };
+            let name = match table.get("label").and_then(|v| v.as_str()) {
+                Some(label) => format!("[[timezone]] {:?}", label),
+                None => format!("[[timezone]] #{}", i + 1),
+            };
+            drop_bad_colors(table, "timezone", &name, &mut problems);
+        }
+    }
+
+    let config: ClockConfig = match raw.try_into() {
+        Ok(c) => c,
+        Err(e) => {
+            problems.push(format!("invalid config: {}", e.message()));
+            return (None, problems);
+        }
+    };
+
+    // [window]
+    let window = &config.window;
+    if !["background", "bottom", "top", "overlay"].contains(&window.layer.as_str()) {
+        problems.push(format!("[window] layer = {:?}: expected background, bottom, top or overlay", window.layer));
+    }
+    for token in window.anchor.split_whitespace() {
+        if !["top", "bottom", "left", "right"].contains(&token.to_lowercase().as_str()) {
+            problems.push(format!("[window] anchor: unknown edge {:?}", token));
+        }
+    }
+    if !(0.0..=1.0).contains(&window.opacity) {
+        problems.push(format!("[window] opacity = {}: must be between 0.0 and 1.0", window.opacity));
+    }
+    if window.right_click_lock && window.drag_button == DragButton::Right {
+        problems.push("[window] right_click_lock has no effect with drag_button = \"right\"".into());
+    }
+    if !(0.0..=1.0).contains(&window.idle_opacity) {
+        problems.push(format!("[window] idle_opacity = {}: must be between 0.0 and 1.0", window.idle_opacity));
+    }
+    if let Some(width) = config.theme.outline_width.filter(|w| !(0.0..=20.0).contains(w)) {
+        problems.push(format!("[theme] outline_width = {}: must be between 0 and 20", width));
+    }
+    if window.tick_ms < 10 {
+        problems.push(format!("[window] tick_ms = {}: must be at least 10", window.tick_ms));
+    }
+    if let Some(bad) = unknown_alignment_word(&window.content_gravity) {
+        problems.push(format!("[window] content_gravity: unknown position {:?} (expected center, top, bottom, left or right)", bad));
+    }
+    if !(window.output_fraction > 0.0 && window.output_fraction <= 1.0) {
+        problems.push(format!("[window] output_fraction = {}: must be greater than 0.0 and at most 1.0", window.output_fraction));
+    }
+    if let Some(scale) = window.compact_scale.filter(|s| !(*s > 0.0 && *s <= 1.0)) {
+        problems.push(format!("[window] compact_scale = {}: must be greater than 0.0 and at most 1.0", scale));
+    }
+    if !(window.compact_hand_scale > 0.0 && window.compact_hand_scale <= 1.0) {
+        problems.push(format!("[window] compact_hand_scale = {}: must be greater than 0.0 and at most 1.0", window.compact_hand_scale));
+    }
+
+    // [clock]
+    if !(config.clock.subclock_scale > 0.0 && config.clock.subclock_scale.is_finite()) {
+        problems.push(format!("[clock] subclock_scale = {}: must be greater than 0.0", config.clock.subclock_scale));
+    }
+    if config.clock.hour_format != 12 && config.clock.hour_format != 24 {
+        problems.push(format!("[clock] hour_format = {}: expected 12 or 24", config.clock.hour_format));
+    }
+    let clock = &config.clock;
+    if clock.min_font_size <= 0.0 || clock.min_font_size > clock.max_font_size {
+        problems.push(format!(
+            "[clock] min_font_size = {}, max_font_size = {}: expected 0 < min <= max",
+            clock.min_font_size, clock.max_font_size,
+        ));
+    }
+    if clock.min_diameter == 0 || clock.min_diameter > clock.max_diameter {
+        problems.push(format!(
+            "[clock] min_diameter = {}, max_diameter = {}: expected 0 < min <= max",
+            clock.min_diameter, clock.max_diameter,
+        ));
+    }
+
+    if !crate::time_utils::is_valid_format(&config.clock.date_format) {
+        problems.push(format!("[clock] date_format = {:?}: invalid strftime format", config.clock.date_format));
+    }
+    if let Some(fmt) = &config.clock.time_format {
+        if !crate::time_utils::is_valid_format(fmt) {
+            problems.push(format!("[clock] time_format = {:?}: invalid strftime format", fmt));
+        }
+    }
+    if !config.clock.locale.is_empty() && crate::time_utils::parse_locale(&config.clock.locale).is_none() {
+        problems.push(format!("[clock] locale = {:?}: unknown locale (expected a name like de_DE)", config.clock.locale));
+    }
+    if !crate::time_utils::is_valid_format(&config.clock.secondary_line) {
+        problems.push(format!("[clock] secondary_line = {:?}: invalid strftime format", config.clock.secondary_line));
+    }
+
+    // [background]
+    let bg = &config.background;
+    if let Some(opacity) = bg.scrim_opacity.filter(|o| !(0.0..=1.0).contains(o)) {
+        problems.push(format!("[background] scrim_opacity = {}: expected 0.0 to 1.0", opacity));
+    }
+    if let Some(bad) = unknown_alignment_word(&bg.image_align) {
+        problems.push(format!("[background] image_align: unknown position {:?} (expected center, top, bottom, left or right)", bad));
+    }
+    for (key, value) in [("digital_image", &bg.digital_image), ("analogue_face_image", &bg.analogue_face_image)] {
+        if !value.is_empty() && !std::path::Path::new(&expand_path(value)).exists() {
+            problems.push(format!("[background] {}: file not found: {}", key, value));
+        }
+    }
+    if !bg.face_preset.is_empty() && resolve_face_preset(&bg.face_preset).is_none() {
+        problems.push(format!("[background] face_preset {:?} not found", bg.face_preset));
+    }
+    for (key, gallery) in [("digital_gallery", &bg.digital_gallery), ("analogue_gallery", &bg.analogue_gallery)] {
+        let missing: Vec<&String> = match gallery {
+            Some(GallerySetting::Dir(dir)) if dir == "bundled" => {
+                if find_bundled_faces_dir().is_none() {
+                    problems.push(format!("[background] {} = \"bundled\" but no bundled faces directory was found", key));
+                }
+                Vec::new()
+            }
+            Some(GallerySetting::Dir(dir)) => vec![dir],
+            Some(GallerySetting::Images(images)) => images.iter().collect(),
+            None => Vec::new(),
+        };
+        for path in missing {
+            if !std::path::Path::new(&expand_path(path)).exists() {
+                problems.push(format!("[background] {}: not found: {}", key, path));
+            }
+        }
+    }
+    let (digital_count, analogue_count) = (bg.effective_digital_images().len(), bg.effective_analogue_face_images().len());
+    for (i, slot) in bg.gallery_schedule.iter().enumerate() {
+        if slot.hour_range().is_none() {
+            problems.push(format!("[[background.gallery_schedule]] #{}: hours = {:?}: expected \"START-END\" with hours 0-24", i + 1, slot.hours));
+        }
+        for day in slot.days.iter().filter(|d| d.parse::<chrono::Weekday>().is_err()) {
+            problems.push(format!("[[background.gallery_schedule]] #{}: unknown day {:?}", i + 1, day));
+        }
+        for (key, indices, count) in [("digital", &slot.digital, digital_count), ("analogue", &slot.analogue, analogue_count)] {
+            if let Some(bad) = indices.iter().find(|&&idx| idx >= count) {
+                problems.push(format!("[[background.gallery_schedule]] #{}: {} index {} is out of range ({} images)", i + 1, key, bad, count));
+            }
+        }
+    }
+
+    // [analogue]
+    if config.analogue.numerals == NumeralStyle::Custom && config.analogue.numeral_labels.len() != 12 {
+        problems.push(format!(
+            "[analogue] numeral_labels has {} entries, expected 12",
+            config.analogue.numeral_labels.len(),
+        ));
+    }
+    if config.analogue.padding < 0.0 {
+        problems.push(format!("[analogue] padding = {}: must not be negative", config.analogue.padding));
+    }
+    for (key, length) in config.analogue.overlong_hands() {
+        problems.push(format!("[analogue] {} = {}: longer than the dial radius (1.0), drawn clamped to the bezel", key, length));
+    }
+
+    // [[timezone]]
+    for tz in &config.timezone {
+        if tz.tz.parse::<chrono_tz::Tz>().is_err() {
+            problems.push(format!("[[timezone]] {:?}: unknown timezone {:?}", tz.label, tz.tz));
+        }
+    }
+    for tz in config.timezone.iter().filter(|tz| tz.primary).skip(1) {
+        problems.push(format!("[[timezone]] {:?}: primary is already set on an earlier entry, ignored", tz.label));
+    }
+
+    (Some(config), problems)
+}
+
+/// Serialize a config back to TOML, e.g. for `--dump-config`.
+pub fn to_toml_string(config: &ClockConfig) -> Result<String> {
+    // Floats are stored as f32; print their shortest f32 form instead of the
+    // widened f64 (0.55 rather than 0.550000011920929)
+    fn tidy_value(value: &mut toml_edit::Value) {
+        match value {
+            toml_edit::Value::Float(f) => {
+                if let Ok(short) = (*f.value() as f32).to_string().parse::<f64>() {
+                    *value = toml_edit::Value::from(short);
+                }
+            }
+            toml_edit::Value::Array(items) => items.iter_mut().for_each(tidy_value),
+            _ => {}
+        }
+    }
+    fn tidy_table(table: &mut toml_edit::Table) {
+        for (_, item) in table.iter_mut() {
+            match item {
+                toml_edit::Item::Value(v) => tidy_value(v),
+                toml_edit::Item::Table(t) => tidy_table(t),
+                toml_edit::Item::ArrayOfTables(a) => a.iter_mut().for_each(tidy_table),
+                toml_edit::Item::None => {}
+            }
+        }
+    }
+
+    let mut doc: toml_edit::DocumentMut = toml::to_string_pretty(config)?.parse()?;
+    tidy_table(doc.as_table_mut());
+    Ok(doc.to_string())
+}
+
+/// A user config that only includes the system-wide one.
+fn generate_user_config(system: &std::path::Path) -> String {
+    let include = toml::Value::String(system.display().to_string());
+    format!(
+        "# clockie user configuration, generated on first run.\n\
+         # The system-wide config included below provides the defaults; settings\n\
+         # added here, and those clockie saves at runtime, override it.\n\
+         include = [{}]\n",
+        include,
+    )
+}
+
 fn generate_default_config() -> String {
     r#"# clockie — Wayland layer-shell desktop clock
 # Configuration file — generated automatically on first run.
 # Uncomment and edit values to customise. Defaults are shown.
 
+# Merge other config files underneath this one (later files win, this file
+# wins over all); must stay above the first [section]
+# include = ["~/.config/clockie/theme.toml"]
+
 [window]
+# "layer" (wlr-layer-shell) | "xdg" (plain window, e.g. on GNOME; no
+# layer, anchor or margins)
+backend = "layer"
 # Layer: background | bottom | top | overlay
 layer  = "top"
 # Anchor edges: top | bottom | left | right (space-separated)
@@ -768,8 +1940,49 @@ margin_left   = 0
 opacity = 1.0
 # Start in compact mode
 compact = false
+# Compact size relative to full size (default 0.7 digital, 0.75 analogue)
+# compact_scale = 0.7
+# Analogue hand length in compact mode, relative to full length
+compact_hand_scale = 0.8
 # Output to display on (empty = compositor default)
 # output = "HDMI-A-1"
+# Where you last dragged the clock on each output is saved below, as
+# [window.positions."HDMI-A-1"] tables with anchor and margin_* keys, and
+# restored when the clock lands on that output again
+# Move back to an unplugged output once it is reconnected (otherwise the clock
+# stays on the output it fell back to, which is saved as output above)
+return_to_output = false
+# Snap to output edges and centre lines while dragging
+snap = false
+# Snap distance (px)
+snap_threshold = 16
+# Mouse button that drags the clock: "left", "middle" or "right"
+drag_button = "left"
+# Right-click the clock to lock or unlock dragging
+right_click_lock = false
+# Show a faint padlock in the bottom-left corner while dragging is locked
+show_lock_indicator = false
+# Hide while a fullscreen window is reported via `clockie ctl fullscreen on`
+hide_when_fullscreen = false
+# Dim to idle_opacity this many seconds after the pointer leaves (0 = never);
+# it brightens again when the pointer comes back
+idle_dim_after = 0
+idle_opacity = 0.4
+# Anti-alias shapes (hands, ticks, icons); off saves CPU on slow machines
+antialias = true
+# Shortest sleep between animation frames (ms); raise it to save power.
+# Without animations the clock sleeps until the next second or minute
+tick_ms = 50
+# Where the clock sits when the window is larger than it (output size mode,
+# tiled xdg windows): "center", or edges like "top" / "bottom left"
+content_gravity = "center"
+# "content" sizes the window to the clock; "output" makes it output_fraction
+# of the output and scales the clock to fill it (e.g. a background-layer clock)
+size_mode = "content"
+output_fraction = 1.0
+# Largest window size in px (0 = only the output's); a larger clock is scaled down to fit
+max_width = 0
+max_height = 0
 
 [clock]
 # "digital" | "analogue"
@@ -778,16 +1991,46 @@ face = "digital"
 hour_format = 12
 # Show seconds on digital face
 show_seconds = true
+# Seconds on the timezone sub-clocks (unset = same as show_seconds)
+# subclock_show_seconds = false
+# Short date ("Wed 03 Jan") under each sub-clock, for zones across the date line
+subclock_show_date = false
 # Show date line on digital face
 show_date = true
 # Date format string (chrono strftime)
 date_format = "%A, %d %B %Y"
+# Show the ISO week number ("Wk 32") below the date
+show_week = false
+# Optional extra line under the date, formatted with strftime (empty = off)
+# secondary_line = "%H:%M %Z"
+# Full strftime format for the time itself; overrides hour_format and show_seconds
+# time_format = "%-I:%M %p"
+# Language of day and month names in the formats above (empty = from LC_TIME)
+# locale = "de_DE"
 # Font: system font name or path to .ttf/.otf
 font = "monospace"
 # Digital mode: main time text size in px (window auto-sizes to fit)
 font_size = 48.0
 # Analogue mode: clock face diameter in px (window auto-sizes to fit)
 diameter = 180
+# Limits for resizing with `clockie ctl size` and the CLI overrides
+min_font_size = 10.0
+max_font_size = 1000.0
+min_diameter = 40
+max_diameter = 4000
+# Timezone sub-clocks: "digital" (text) | "analogue" (mini dials)
+subclock_style = "digital"
+# Grow or shrink the sub-clocks relative to the main face (1.0 = derived size)
+subclock_scale = 1.0
+# Rule above the sub-clocks: "line" | "dots" | "none"
+subclock_separator = "line"
+# subclock_separator_color = "FFFFFF66"
+# 12-hour AM/PM suffix: "inline" | "small" (reduced, beside the time) | "stacked" (reduced, above it)
+ampm_style = "inline"
+# Date line placement on the digital face: "below" | "above" the time
+date_position = "below"
+# Brighten the clock for a second at the top of each hour
+hour_flash = false
 
 [theme]
 # Colours in RRGGBB or RRGGBBAA hex (# prefix optional)
@@ -801,18 +2044,39 @@ second_hand_color = "ef4444FF"
 tick_color        = "CCCCCCFF"
 # Draw a contrasting outline around all text for readability
 text_outline      = true
+# Or pick the effect: "outline" | "shadow" | "none" (overrides text_outline)
+# text_effect = "shadow"
+# Drop shadow offset in px and colour, for text_effect = "shadow"
+# text_shadow_dx    = 1.5
+# text_shadow_dy    = 1.5
+# text_shadow_color = "00000099"
+# Outline radius in px (unset = scaled with text size, 0.8–1.5) and colour
+# (unset = black or white against the text)
+# outline_width = 2.5
+# outline_color = "000000CC"
 # Auto-contrast: "auto" (active when gallery configured) | "always" | "never"
+# (true/false are shorthand for "always"/"never")
 auto_contrast     = "auto"
 
 [background]
-# Path to a PNG/JPEG behind the digital clock text (empty = bg_color fill)
+# Path to a PNG/JPEG/GIF/WebP behind the digital clock text (empty = bg_color fill)
+# Animated GIF/APNG backgrounds play back automatically
 digital_image = ""
-# Path to a PNG/JPEG for the analogue face (replaces drawn ticks)
+# Path to a PNG/JPEG/WebP for the analogue face (replaces drawn ticks)
 analogue_face_image = ""
 # Bundled preset face: "classic", "minimal", "modern", "bare", or a path to an SVG
 # face_preset = "classic"
 # Scale mode: "fill" | "fit" | "stretch" | "center"
 image_scale = "fill"
+# Anchor for cropped/letterboxed images: "center", or edges like "top" / "bottom left"
+image_align = "center"
+# Darken background images by this much (0.0-1.0) instead of bg_color's alpha
+# scrim_opacity = 0.3
+# Fade the digital scrim in from the top, so the image stays clear up there
+scrim_gradient = false
+# Extra image shift in pixels (positive = right / down)
+# image_offset_x = 0
+# image_offset_y = 0
 # Gallery: a folder path (all images inside) or an explicit list of paths
 # digital_gallery = "~/wallpapers/"
 # digital_gallery = ["~/wallpapers/a.png", "~/wallpapers/b.jpg"]
@@ -820,6 +2084,12 @@ image_scale = "fill"
 # analogue_gallery = ["~/faces/classic.png", "~/faces/minimal.png"]
 # Auto-rotate interval in seconds (0 = disabled)
 # gallery_interval = 300
+# Show different gallery images by time of day / weekday (first matching slot wins;
+# rotation then cycles only through that slot's images)
+# [[background.gallery_schedule]]
+# hours   = "6-12"
+# days    = ["mon", "tue", "wed", "thu", "fri"]
+# digital = [0, 1]
 
 # [analogue]
 # Hand tip style: "round" | "flat" | "arrow"
@@ -833,36 +2103,82 @@ image_scale = "fill"
 # minute_hand_width  = 0.04
 # second_hand_length = 0.85
 # second_hand_width  = 0.02
+# Second hand motion: "tick" | "linear" (smooth sweep) | "bounce" (overshoot on each tick)
+# sweep_easing = "tick"
 # Draw a subtle drop shadow behind each hand
 # hand_shadow = false
+# Stroke a crisp contrasting edge around each hand
+# hand_outline = false
+# Outline colour (omit to pick black or white against each hand colour)
+# hand_outline_color = "000000"
 # Which tick marks to show: "all60" | "hours_only" | "quarters_only" | "none"
 # show_ticks = "all60"
 # Tick shape: "line" | "dot" | "diamond"
 # tick_style = "line"
-# Numeral labels: "none" | "arabic" | "roman"
+# Tick widths in px (dot/diamond scale by them) and lengths as fraction of radius
+# hour_tick_width    = 2.5
+# minute_tick_width  = 1.0
+# hour_tick_length   = 0.13
+# minute_tick_length = 0.06
+# Numeral labels: "none" | "arabic" | "roman" | "quarters" | "custom"
 # numerals = "none"
 # numeral_size = 0.18
 # numeral_inset = 0.15
+# With numerals = "custom": 12 labels clockwise from 12, "" skips a position
+# numeral_labels = ["12", "", "", "3", "", "", "6", "", "", "9", "", ""]
+# Small minute labels (05, 10, ... 60) just inside the minute track
+# minute_numbers = false
+# Digital HH:MM readout between the centre and 6 o'clock (hidden in compact mode)
+# digital_readout = false
+# Draw ticks and numerals over analogue_face_image too
+# ticks_over_image = false
 # Fill colour behind the procedural face (empty = transparent)
 # face_fill = ""
 # Bezel ring width as fraction of radius (0 = thin 2px default)
 # bezel_width = 0.0
+# Bezel colour, also used for the thin default ring
+# (default FFFFFFFF with bezel_width, CCCCCCFF without)
 # bezel_color = "FFFFFFFF"
 # Minute track ring width as fraction of radius (0 = hidden)
 # minute_track_width = 0.0
 # minute_track_color = "CCCCCCFF"
+# Centre boss radius as fraction of radius (0 = hidden)
+# boss_radius = 0.05
+# Centre boss colour (empty = follow text colour)
+# boss_color = ""
+# Optional inner fill for a two-tone boss
+# boss_inner_color = ""
+# Space around the dial in px; raise it for hand shadows, 0 for a tight fit
+# padding = 12.0
 
 [battery]
 # Show a battery indicator in the top-right corner
 enabled = false
 # Display percentage text next to the icon
 show_percentage = true
+# "icon" | "ring" (charge arc around the dial, analogue face only)
+style = "icon"
+# Icon orientation: "horizontal" | "vertical"
+orientation = "horizontal"
+# Animate the icon's fill while charging (redraws at ~20 fps while charging)
+charge_animation = false
+
+[hooks]
+# Shell commands (run with sh -c) on clock events; empty = off.
+# Output goes to the log; a hook still running is not started again
+# on_hour = "paplay /usr/share/sounds/freedesktop/stereo/bell.oga"
+# on_alarm = "notify-send clockie Alarm"
 
 # Up to 2 timezone sub-clocks. Uncomment to enable.
 
 # [[timezone]]
 # label = "London"
 # tz    = "Europe/London"
+# Optional colours for this sub-clock's time and label
+# color       = "7FB8E0"
+# label_color = "7FB8E0AA"
+# Draw this one larger and first (one entry at most)
+# primary = true
 
 # [[timezone]]
 # label = "New York"
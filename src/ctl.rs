@@ -2,19 +2,44 @@ use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::Shell;
 use serde_json::json;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, IsTerminal, Write};
 use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 
 use crate::ipc;
 
+/// Exit status for invalid arguments. clap already uses 2 for the usage
+/// errors it catches, so the values we reject ourselves share it.
+const EXIT_USAGE: i32 = 2;
+/// Exit status when the daemon can't be reached or its reply can't be read.
+const EXIT_CONNECT: i32 = 3;
+/// Exit status when the daemon answered with `"ok": false`.
+const EXIT_DAEMON: i32 = 4;
+
 #[derive(Parser, Debug)]
-#[command(name = "ctl", about = "Control a running clockie instance")]
+#[command(
+    name = "ctl",
+    about = "Control a running clockie instance",
+    after_help = "Exit status:\n  0  success\n  1  other failure\n  2  invalid arguments\n  3  could not reach the daemon (not running, wrong socket, bad reply)\n  4  the daemon rejected the command",
+)]
 pub struct CtlArgs {
     /// Override socket path
     #[arg(long)]
     socket: Option<PathBuf>,
 
+    /// Connect to an abstract socket (daemon started with --abstract-socket)
+    #[arg(long)]
+    abstract_socket: bool,
+
+    /// Print the daemon's raw JSON response for every command, including errors
+    #[arg(long)]
+    json: bool,
+
+    /// Frame the request and response with a length prefix instead of a
+    /// newline (falls back to lines if the daemon doesn't support it)
+    #[arg(long)]
+    length_prefixed: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -31,6 +56,16 @@ enum Commands {
         /// on, off, or toggle
         mode: String,
     },
+    /// Switch between 12- and 24-hour time
+    Hour {
+        /// 12, 24, or toggle
+        mode: String,
+    },
+    /// Set the date line's strftime format
+    DateFormat {
+        /// e.g. "%a %d %b"
+        format: String,
+    },
     /// Set font size (digital) or diameter (analogue), or scale by +/-N
     Size {
         args: Vec<String>,
@@ -44,6 +79,17 @@ enum Commands {
         /// on, off, or toggle
         mode: String,
     },
+    /// Show or hide the clock
+    Visible {
+        /// on, off, or toggle
+        mode: String,
+    },
+    /// Report whether a fullscreen window is active (hides the clock when
+    /// `hide_when_fullscreen` is set)
+    Fullscreen {
+        /// on or off
+        mode: String,
+    },
     /// Move clock to a specific output (monitor name, "next", or "prev")
     Output {
         /// Output name (e.g. HDMI-A-1), or "next"/"prev" to cycle
@@ -54,8 +100,19 @@ enum Commands {
         #[command(subcommand)]
         action: GalleryAction,
     },
+    /// Set a one-shot alarm, or clear alarms
+    Alarm {
+        /// Time as 24-hour HH:MM, or "clear"
+        time: String,
+        /// With "clear", the alarm to remove (all alarms when omitted)
+        at: Option<String>,
+    },
     /// Shut down clockie
     Quit,
+    /// Print client and daemon versions
+    Version,
+    /// Check the daemon is responding (prints PID and uptime)
+    Ping,
     /// Generate shell completions for the ctl subcommand
     Completions {
         /// Shell to generate completions for
@@ -89,23 +146,71 @@ enum GalleryAction {
     },
 }
 
-fn send_command(socket: &PathBuf, cmd: serde_json::Value) -> Result<serde_json::Value> {
-    let mut stream = UnixStream::connect(socket)
-        .with_context(|| format!("Failed to connect to clockie at {}", socket.display()))?;
+fn send_command(socket: &ipc::Endpoint, mut cmd: serde_json::Value, framing: ipc::Framing) -> Result<serde_json::Value> {
+    cmd["proto"] = json!(ipc::PROTOCOL_VERSION);
+    let connect = || ipc::connect(socket)
+        .with_context(|| format!("Failed to connect to clockie at {}", socket));
+    let mut stream = connect()?;
 
-    let msg = serde_json::to_string(&cmd)? + "\n";
-    stream.write_all(msg.as_bytes())?;
-    stream.flush()?;
+    let mut framing = framing;
+    if framing != ipc::Framing::Line && !negotiate_framing(&stream, framing)? {
+        print_warning("clockie daemon doesn't support length-prefixed framing, using lines");
+        stream = connect()?;
+        framing = ipc::Framing::Line;
+    }
 
-    let mut reader = BufReader::new(&stream);
-    let mut response = String::new();
-    reader.read_line(&mut response)?;
+    let msg = serde_json::to_string(&cmd)?;
+    let response = match framing {
+        ipc::Framing::Line => {
+            stream.write_all((msg + "\n").as_bytes())?;
+            stream.flush()?;
+            read_response_line(&stream)?
+        }
+        ipc::Framing::LengthPrefixed => {
+            ipc::write_frame(&mut stream, msg.as_bytes())?;
+            let frame = ipc::read_frame(&mut stream, u32::MAX)
+                .context("Failed to read response frame from clockie")?;
+            String::from_utf8(frame).context("Response from clockie is not valid UTF-8")?
+        }
+    };
 
     let resp: serde_json::Value = serde_json::from_str(&response)
         .context("Failed to parse response from clockie")?;
+    match resp.get("proto").and_then(|v| v.as_u64()) {
+        Some(p) if p == ipc::PROTOCOL_VERSION as u64 => {}
+        Some(p) => print_warning(format_args!(
+            "clockie daemon speaks IPC protocol {}, this client {}; restart clockie after upgrading",
+            p,
+            ipc::PROTOCOL_VERSION,
+        )),
+        None => print_warning("clockie daemon predates IPC versioning; restart clockie after upgrading"),
+    }
     Ok(resp)
 }
 
+/// Ask the daemon to switch `stream` to `framing`. False means it answered
+/// but refused, which daemons from before framing was negotiable do.
+fn negotiate_framing(mut stream: &UnixStream, framing: ipc::Framing) -> Result<bool> {
+    let hello = json!({"cmd": "hello", "proto": ipc::PROTOCOL_VERSION, "framing": framing});
+    stream.write_all((hello.to_string() + "\n").as_bytes())?;
+    stream.flush()?;
+    let resp: serde_json::Value = serde_json::from_str(&read_response_line(stream)?)
+        .context("Failed to parse response from clockie")?;
+    Ok(resp.get("ok").and_then(|v| v.as_bool()) == Some(true)
+        && resp.get("framing") == Some(&json!(framing)))
+}
+
+fn read_response_line(stream: &UnixStream) -> Result<String> {
+    // Buffering can't swallow anything past the line: the daemon sends
+    // nothing more until the client writes again
+    let mut reader = BufReader::new(stream);
+    let mut response = String::new();
+    if reader.read_line(&mut response)? == 0 {
+        anyhow::bail!("clockie closed the connection without replying");
+    }
+    Ok(response)
+}
+
 pub fn run(args: CtlArgs) -> Result<()> {
     // Handle completions before connecting to socket
     if let Commands::Completions { shell } = &args.command {
@@ -114,9 +219,149 @@ pub fn run(args: CtlArgs) -> Result<()> {
         return Ok(());
     }
 
-    let sock = ipc::socket_path(args.socket.as_ref());
+    let sock = ipc::endpoint(args.socket.as_ref(), args.abstract_socket);
+    let cmd = build_command(&args.command).unwrap_or_else(|e| fail(EXIT_USAGE, e));
+    let framing = if args.length_prefixed { ipc::Framing::LengthPrefixed } else { ipc::Framing::Line };
+    let resp = send_command(&sock, cmd, framing).unwrap_or_else(|e| fail(EXIT_CONNECT, e));
+    let ok = resp.get("ok").and_then(|v| v.as_bool()) == Some(true);
 
-    let cmd = match &args.command {
+    if args.json {
+        // One line per response so pipelines can read it with jq or similar
+        print_json(&resp, false)?;
+        if !ok {
+            std::process::exit(EXIT_DAEMON);
+        }
+        return Ok(());
+    }
+
+    if ok {
+        match &args.command {
+            Commands::State => print_json(&resp, true)?,
+            Commands::Version => {
+                let daemon = resp.get("version").and_then(|v| v.as_str()).unwrap_or("unknown");
+                println!("client {} (protocol {})", env!("CARGO_PKG_VERSION"), ipc::PROTOCOL_VERSION);
+                println!("daemon {} (protocol {})", daemon, resp["proto"]);
+            }
+            Commands::Ping => println!("ok pid {} up {}s", resp["pid"], resp["uptime_secs"]),
+            _ => {}
+        }
+    } else {
+        let err = resp.get("error").and_then(|v| v.as_str()).unwrap_or("Unknown error");
+        print_error(err);
+        std::process::exit(EXIT_DAEMON);
+    }
+
+    Ok(())
+}
+
+/// Print `err` with its causes and exit with `code`.
+fn fail(code: i32, err: anyhow::Error) -> ! {
+    print_error(format_args!("{:#}", err));
+    std::process::exit(code)
+}
+
+// SGR codes for terminal output
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const GREEN: &str = "32";
+const CYAN: &str = "36";
+const BLUE: &str = "34";
+const MAGENTA: &str = "35";
+
+/// Colour output only on a terminal, and never when `NO_COLOR` is set to a
+/// non-empty value (https://no-color.org).
+fn use_colour(stream: &impl IsTerminal) -> bool {
+    stream.is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+fn paint(text: &str, sgr: &str, on: bool) -> String {
+    if on {
+        format!("\x1b[{}m{}\x1b[0m", sgr, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn print_error(msg: impl std::fmt::Display) {
+    let on = use_colour(&std::io::stderr());
+    eprintln!("{}", paint(&format!("Error: {}", msg), RED, on));
+}
+
+fn print_warning(msg: impl std::fmt::Display) {
+    let on = use_colour(&std::io::stderr());
+    eprintln!("{}", paint(&format!("Warning: {}", msg), YELLOW, on));
+}
+
+/// Print a response as JSON, on one line or pretty-printed, highlighted when
+/// stdout is a terminal.
+fn print_json(value: &serde_json::Value, pretty: bool) -> Result<()> {
+    if !use_colour(&std::io::stdout()) {
+        let text = if pretty {
+            serde_json::to_string_pretty(value)?
+        } else {
+            serde_json::to_string(value)?
+        };
+        println!("{}", text);
+        return Ok(());
+    }
+    let mut out = String::new();
+    write_json(&mut out, value, pretty.then_some(0));
+    println!("{}", out);
+    Ok(())
+}
+
+/// Append `value` to `out` with ANSI colours, laid out like serde_json's
+/// compact output, or its pretty output at `indent` levels deep.
+fn write_json(out: &mut String, value: &serde_json::Value, indent: Option<usize>) {
+    use serde_json::Value;
+
+    // Line break and indent before each element and the closing bracket, when pretty
+    let newline = |out: &mut String, level: usize| {
+        if indent.is_some() {
+            out.push('\n');
+            out.push_str(&"  ".repeat(level));
+        }
+    };
+    let level = indent.unwrap_or(0);
+    match value {
+        Value::Null => out.push_str(&paint("null", MAGENTA, true)),
+        Value::Bool(b) => out.push_str(&paint(&b.to_string(), YELLOW, true)),
+        Value::Number(n) => out.push_str(&paint(&n.to_string(), CYAN, true)),
+        Value::String(_) => out.push_str(&paint(&value.to_string(), GREEN, true)),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, level + 1);
+                write_json(out, item, indent.map(|l| l + 1));
+            }
+            newline(out, level);
+            out.push(']');
+        }
+        Value::Object(map) => {
+            out.push('{');
+            for (i, (key, item)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, level + 1);
+                out.push_str(&paint(&Value::from(key.as_str()).to_string(), BLUE, true));
+                out.push_str(if indent.is_some() { ": " } else { ":" });
+                write_json(out, item, indent.map(|l| l + 1));
+            }
+            newline(out, level);
+            out.push('}');
+        }
+    }
+}
+
+/// Translate a subcommand into its IPC request, rejecting bad values.
+fn build_command(command: &Commands) -> Result<serde_json::Value> {
+    let cmd = match command {
         Commands::Face { mode } => match mode.as_str() {
             "digital" => json!({"cmd": "set-face", "face": "digital"}),
             "analogue" => json!({"cmd": "set-face", "face": "analogue"}),
@@ -129,6 +374,13 @@ pub fn run(args: CtlArgs) -> Result<()> {
             "toggle" => json!({"cmd": "toggle-compact"}),
             other => anyhow::bail!("Unknown compact mode: {}. Use on, off, or toggle", other),
         },
+        Commands::Hour { mode } => match mode.as_str() {
+            "12" => json!({"cmd": "set-hour-format", "format": 12}),
+            "24" => json!({"cmd": "set-hour-format", "format": 24}),
+            "toggle" => json!({"cmd": "toggle-hour-format"}),
+            other => anyhow::bail!("Unknown hour format: {}. Use 12, 24, or toggle", other),
+        },
+        Commands::DateFormat { format } => json!({"cmd": "set-date-format", "format": format}),
         Commands::Size { args } => {
             if args.len() == 1 {
                 let s = &args[0];
@@ -164,6 +416,17 @@ pub fn run(args: CtlArgs) -> Result<()> {
             "toggle" => json!({"cmd": "toggle-locked"}),
             other => anyhow::bail!("Unknown lock mode: {}. Use on, off, or toggle", other),
         },
+        Commands::Visible { mode } => match mode.as_str() {
+            "on" => json!({"cmd": "set-visible", "visible": true}),
+            "off" => json!({"cmd": "set-visible", "visible": false}),
+            "toggle" => json!({"cmd": "toggle-visible"}),
+            other => anyhow::bail!("Unknown visible mode: {}. Use on, off, or toggle", other),
+        },
+        Commands::Fullscreen { mode } => match mode.as_str() {
+            "on" => json!({"cmd": "set-fullscreen", "fullscreen": true}),
+            "off" => json!({"cmd": "set-fullscreen", "fullscreen": false}),
+            other => anyhow::bail!("Unknown fullscreen mode: {}. Use on or off", other),
+        },
         Commands::Gallery { action } => match action {
             GalleryAction::Next => json!({"cmd": "gallery-next"}),
             GalleryAction::Prev => json!({"cmd": "gallery-prev"}),
@@ -178,24 +441,20 @@ pub fn run(args: CtlArgs) -> Result<()> {
             GalleryAction::Stop => json!({"cmd": "gallery-rotate-stop"}),
             GalleryAction::Interval { seconds } => json!({"cmd": "gallery-rotate-interval", "seconds": seconds}),
         },
+        Commands::Alarm { time, at } => match (time.as_str(), at) {
+            ("clear", Some(at)) => json!({"cmd": "clear-alarm", "at": at}),
+            ("clear", None) => json!({"cmd": "clear-alarm"}),
+            (_, Some(_)) => anyhow::bail!("Alarm takes one time, e.g. alarm 07:30"),
+            (time, None) => json!({"cmd": "set-alarm", "at": time}),
+        },
         Commands::Output { name } => json!({"cmd": "move-to-output", "name": name}),
         Commands::Reload => json!({"cmd": "reload-config"}),
         Commands::State => json!({"cmd": "get-state"}),
         Commands::Quit => json!({"cmd": "quit"}),
+        Commands::Version => json!({"cmd": "version"}),
+        Commands::Ping => json!({"cmd": "ping"}),
         Commands::Completions { .. } => unreachable!("handled above"),
     };
 
-    let resp = send_command(&sock, cmd)?;
-
-    if let Some(true) = resp.get("ok").and_then(|v| v.as_bool()) {
-        if matches!(&args.command, Commands::State) {
-            println!("{}", serde_json::to_string_pretty(&resp)?);
-        }
-    } else {
-        let err = resp.get("error").and_then(|v| v.as_str()).unwrap_or("Unknown error");
-        eprintln!("Error: {}", err);
-        std::process::exit(1);
-    }
-
-    Ok(())
+    Ok(cmd)
 }
@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::canvas::{Canvas, FontState};
+use crate::config::ClockConfig;
+use crate::renderer::{self, ClockState, ContrastInfo, ElementContrast};
+use crate::time_utils;
+use crate::wayland::GalleryState;
+
+#[derive(Parser, Debug)]
+#[command(about = "Render one frame to a PNG without a Wayland connection")]
+pub struct RenderArgs {
+    /// Output PNG path
+    #[arg(short, long)]
+    pub out: PathBuf,
+
+    /// Canvas width in logical pixels (default: the size the daemon would use)
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// Canvas height in logical pixels (default: the size the daemon would use)
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// Device scale, e.g. 2 for a HiDPI preview
+    #[arg(long, default_value_t = 1.0)]
+    pub scale: f32,
+
+    /// Path to config file (defaults to the top-level --config)
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Render this moment instead of the current time, e.g. "2024-01-02T03:04:05"
+    #[arg(long, hide = true, value_parser = parse_now)]
+    pub now: Option<DateTime<Utc>>,
+}
+
+fn parse_now(s: &str) -> Result<DateTime<Utc>, String> {
+    time_utils::parse_instant(s)
+        .ok_or_else(|| format!("expected a date-time like 2024-01-02T03:04:05, got {s:?}"))
+}
+
+/// Render a single frame of `config` and write it to `args.out`. Follows the
+/// same background → contrast → foreground → opacity sequence as the daemon.
+pub fn run(config: ClockConfig, args: &RenderArgs) -> Result<()> {
+    anyhow::ensure!(args.scale > 0.0, "Scale must be positive, got {}", args.scale);
+
+    let font = FontState::new(&config.clock.font)?;
+    let compact = config.window.compact;
+    // There is no output here; in output size mode the canvas stands in for it
+    let (natural_w, natural_h) = renderer::compute_size(&config, &font, compact, None);
+    let width = args.width.unwrap_or(natural_w);
+    let height = args.height.unwrap_or(natural_h);
+    anyhow::ensure!(width > 0 && height > 0, "Canvas size must be non-zero, got {}x{}", width, height);
+
+    let mut canvas = Canvas::with_scale(width, height, args.scale);
+    canvas.set_antialias(config.window.antialias);
+    let now = args.now.unwrap_or_else(Utc::now);
+    let time = time_utils::clock_time_at(
+        now,
+        &config.clock.date_format,
+        config.clock.effective_time_format(),
+        config.clock.effective_secondary_line(),
+        config.clock.locale(),
+    );
+    let battery = if config.battery.enabled { crate::battery::read_battery() } else { None };
+
+    // Use the gallery images the daemon would restore on startup, then apply the schedule
+    let mut gallery = GalleryState::from_config(&config);
+    gallery.apply_schedule(now.with_timezone(&chrono::Local));
+    let mut render_config = config.clone();
+    render_config.background.digital_image = gallery.current_digital_image().to_string();
+    render_config.background.analogue_face_image = gallery.current_analogue_image().to_string();
+    let scale = renderer::render_scale(&config, &font, compact, width, height);
+    if scale != 1.0 {
+        renderer::scale_content(&mut render_config, &config, scale);
+    }
+    let auto_contrast_active = renderer::auto_contrast_active(&render_config.theme, gallery.has_images());
+
+    let bg_contrast = ContrastInfo::resolve(&render_config.theme, None);
+    let mut state = ClockState {
+        config: render_config,
+        time,
+        compact,
+        battery,
+        contrast: bg_contrast,
+        elements: ElementContrast::uniform(bg_contrast),
+        background_frame: None,
+        alarm_pending: false,
+        lock_glyph: None,
+    };
+    renderer::render_background(&mut canvas, &state, &font);
+
+    if auto_contrast_active {
+        let (global, elements) = renderer::sample_element_contrast(&canvas, &state, &font, width, height);
+        state.contrast = global;
+        state.elements = elements;
+    }
+    renderer::render_foreground(&mut canvas, &state, &font);
+
+    canvas.apply_opacity(config.window.opacity);
+
+    canvas.pixmap.save_png(&args.out)
+        .with_context(|| format!("Failed to write {}", args.out.display()))?;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Runs `[hooks]` commands in the background, at most one at a time per hook.
+#[derive(Default)]
+pub struct HookRunner {
+    /// Set while a hook's command is still running
+    busy: HashMap<&'static str, Arc<AtomicBool>>,
+}
+
+impl HookRunner {
+    /// Start `command` with `sh -c` for the hook `name`, unless it is empty or
+    /// the previous run of the same hook hasn't finished. The command's output
+    /// is logged once it exits; a reaper thread waits for it so the event loop
+    /// never blocks.
+    pub fn run(&mut self, name: &'static str, command: &str) {
+        if command.trim().is_empty() {
+            return;
+        }
+        let busy = self.busy.entry(name).or_default().clone();
+        if busy.swap(true, Ordering::SeqCst) {
+            log::warn!("Hook {} is still running, not starting it again", name);
+            return;
+        }
+
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("CLOCKIE_HOOK", name)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                log::warn!("Failed to run hook {}: {}", name, e);
+                busy.store(false, Ordering::SeqCst);
+                return;
+            }
+        };
+        log::info!("Running hook {}: {}", name, command);
+
+        std::thread::spawn(move || {
+            match child.wait_with_output() {
+                Ok(output) => {
+                    for line in String::from_utf8_lossy(&output.stdout).lines() {
+                        log::info!("[{}] {}", name, line);
+                    }
+                    for line in String::from_utf8_lossy(&output.stderr).lines() {
+                        log::warn!("[{}] {}", name, line);
+                    }
+                    if !output.status.success() {
+                        log::warn!("Hook {} exited with {}", name, output.status);
+                    }
+                }
+                Err(e) => log::warn!("Failed to wait for hook {}: {}", name, e),
+            }
+            busy.store(false, Ordering::SeqCst);
+        });
+    }
+}
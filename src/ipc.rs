@@ -1,8 +1,40 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Bumped whenever commands or response fields change incompatibly. Clients
+/// send it as `"proto"` with each command and every response carries it back.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Longest request line the daemon accepts. Longer ones are rejected instead
+/// of buffered, so a client can't grow the daemon's memory without bound.
+const MAX_REQUEST_BYTES: u32 = 64 * 1024;
+
+/// How long a client gets to send its whole request, or read the response,
+/// before the daemon gives up on it. Connections are served on the event
+/// loop, so a stalled client must not hold it up for long.
+const CLIENT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How a connection's request and response are delimited. Connections start
+/// out line-delimited; a client switches with a `hello` request first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Framing {
+    /// One JSON object per line
+    #[default]
+    Line,
+    /// A 4-byte little-endian byte count, then that many bytes of JSON
+    LengthPrefixed,
+}
+
+/// The `hello` request that negotiates framing for the rest of a connection.
+#[derive(Debug, Deserialize)]
+struct Hello {
+    framing: Framing,
+}
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "cmd", rename_all = "kebab-case")]
@@ -14,8 +46,14 @@ pub enum IpcCommand {
     SetFontSize { size: f32 },
     SetDiameter { diameter: u32 },
     ScaleBy { delta: i32 },
+    SetHourFormat { format: u8 },
+    ToggleHourFormat,
+    SetDateFormat { format: String },
     SetLocked { locked: bool },
     ToggleLocked,
+    SetVisible { visible: bool },
+    ToggleVisible,
+    SetFullscreen { fullscreen: bool },
     MoveToOutput { name: String },
     ReloadConfig,
     GetState,
@@ -26,13 +64,29 @@ pub enum IpcCommand {
     GalleryRotateStart { interval: Option<u64> },
     GalleryRotateStop,
     GalleryRotateInterval { seconds: u64 },
+    SetAlarm { at: String },
+    /// Clear the alarm at `at`, or every alarm when it is omitted
+    ClearAlarm { at: Option<String> },
+    Version,
+    Ping,
+    /// Any `cmd` this daemon doesn't know (e.g. from a newer client)
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Serialize)]
 pub struct IpcResponse {
     pub ok: bool,
+    pub proto: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    // Health fields (only for ping)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
     // State fields (only for get-state)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub face: Option<String>,
@@ -48,9 +102,14 @@ pub struct IpcResponse {
     pub diameter: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config_path: Option<String>,
+    /// File the font was loaded from, or "(embedded)" for the built-in fallback.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub locked: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gallery_digital_index: Option<usize>,
@@ -64,35 +123,48 @@ pub struct IpcResponse {
     pub gallery_rotate_active: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gallery_rotate_interval: Option<u64>,
+    /// Pending alarms as `HH:MM`, soonest first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alarms: Option<Vec<String>>,
+    /// Framing agreed by a `hello` request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub framing: Option<Framing>,
 }
 
 impl IpcResponse {
     pub fn ok() -> Self {
         Self {
-            ok: true, error: None, face: None, compact: None, width: None,
+            ok: true, proto: PROTOCOL_VERSION, error: None, version: None,
+            uptime_secs: None, pid: None, face: None, compact: None, width: None,
             height: None, font_size: None, diameter: None, config_path: None,
-            locked: None, output: None, gallery_digital_index: None,
+            font_path: None, locked: None, visible: None, output: None, gallery_digital_index: None,
             gallery_analogue_index: None, gallery_digital_count: None,
             gallery_analogue_count: None, gallery_rotate_active: None,
-            gallery_rotate_interval: None,
+            gallery_rotate_interval: None, alarms: None, framing: None,
         }
     }
 
     pub fn err(msg: impl Into<String>) -> Self {
         Self {
-            ok: false, error: Some(msg.into()), face: None, compact: None,
+            ok: false, proto: PROTOCOL_VERSION, error: Some(msg.into()),
+            version: None, uptime_secs: None, pid: None, face: None, compact: None,
             width: None, height: None, font_size: None, diameter: None,
-            config_path: None, locked: None, output: None,
+            config_path: None, font_path: None, locked: None, visible: None, output: None,
             gallery_digital_index: None, gallery_analogue_index: None,
             gallery_digital_count: None, gallery_analogue_count: None,
-            gallery_rotate_active: None, gallery_rotate_interval: None,
+            gallery_rotate_active: None, gallery_rotate_interval: None, alarms: None, framing: None,
         }
     }
 
-    pub fn state(face: &str, compact: bool, width: u32, height: u32, font_size: f32, diameter: u32, config_path: &str, locked: bool, output: Option<&str>) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn state(face: &str, compact: bool, width: u32, height: u32, font_size: f32, diameter: u32, config_path: &str, locked: bool, visible: bool, output: Option<&str>) -> Self {
         Self {
             ok: true,
+            proto: PROTOCOL_VERSION,
             error: None,
+            version: None,
+            uptime_secs: None,
+            pid: None,
             face: Some(face.into()),
             compact: Some(compact),
             width: Some(width),
@@ -100,7 +172,9 @@ impl IpcResponse {
             font_size: Some(font_size),
             diameter: Some(diameter),
             config_path: Some(config_path.into()),
+            font_path: None,
             locked: Some(locked),
+            visible: Some(visible),
             output: output.map(|s| s.into()),
             gallery_digital_index: None,
             gallery_analogue_index: None,
@@ -108,9 +182,24 @@ impl IpcResponse {
             gallery_analogue_count: None,
             gallery_rotate_active: None,
             gallery_rotate_interval: None,
+            alarms: None,
+            framing: None,
         }
     }
 
+    pub fn version() -> Self {
+        Self { version: Some(env!("CARGO_PKG_VERSION").into()), ..Self::ok() }
+    }
+
+    pub fn ping(uptime_secs: u64) -> Self {
+        Self { uptime_secs: Some(uptime_secs), pid: Some(std::process::id()), ..Self::ok() }
+    }
+
+    pub fn with_font_path(mut self, path: Option<&std::path::Path>) -> Self {
+        self.font_path = Some(path.map_or_else(|| "(embedded)".into(), |p| p.display().to_string()));
+        self
+    }
+
     pub fn with_gallery(mut self, digital_index: usize, analogue_index: usize, digital_count: usize, analogue_count: usize, rotate_active: bool, rotate_interval: u64) -> Self {
         self.gallery_digital_index = Some(digital_index);
         self.gallery_analogue_index = Some(analogue_index);
@@ -120,6 +209,16 @@ impl IpcResponse {
         self.gallery_rotate_interval = Some(rotate_interval);
         self
     }
+
+    pub fn with_alarms(mut self, alarms: impl IntoIterator<Item = String>) -> Self {
+        self.alarms = Some(alarms.into_iter().collect());
+        self
+    }
+
+    fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = Some(framing);
+        self
+    }
 }
 
 pub fn socket_path(override_path: Option<&PathBuf>) -> PathBuf {
@@ -134,42 +233,395 @@ pub fn socket_path(override_path: Option<&PathBuf>) -> PathBuf {
     }
 }
 
-pub fn create_listener(path: &PathBuf) -> Result<UnixListener> {
-    // Remove stale socket
-    if path.exists() {
-        // Check if another instance is running
-        if UnixStream::connect(path).is_ok() {
-            anyhow::bail!("Another clockie instance is already running (socket {} is active)", path.display());
+/// Where the IPC socket lives: a filesystem path, or (on Linux) a name in the
+/// abstract namespace, which the kernel removes when the daemon exits.
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    Path(PathBuf),
+    Abstract(String),
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Path(path) => write!(f, "{}", path.display()),
+            Endpoint::Abstract(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
+/// Resolve the endpoint from `--socket` and `--abstract-socket`. An abstract
+/// socket uses the override as its name, else `clockie-<uid>`.
+pub fn endpoint(override_path: Option<&PathBuf>, abstract_socket: bool) -> Endpoint {
+    if !abstract_socket {
+        return Endpoint::Path(socket_path(override_path));
+    }
+    match override_path {
+        Some(p) => Endpoint::Abstract(p.display().to_string()),
+        None => {
+            let uid = unsafe { libc::getuid() };
+            Endpoint::Abstract(format!("clockie-{}", uid))
         }
-        std::fs::remove_file(path)?;
     }
+}
+
+#[cfg(target_os = "linux")]
+fn abstract_addr(name: &str) -> Result<std::os::unix::net::SocketAddr> {
+    use std::os::linux::net::SocketAddrExt;
+    Ok(std::os::unix::net::SocketAddr::from_abstract_name(name)?)
+}
 
-    let listener = UnixListener::bind(path)?;
+#[cfg(not(target_os = "linux"))]
+fn abstract_addr(_name: &str) -> Result<std::os::unix::net::SocketAddr> {
+    anyhow::bail!("Abstract sockets are only supported on Linux")
+}
+
+/// Connect to a running daemon.
+pub fn connect(endpoint: &Endpoint) -> std::io::Result<UnixStream> {
+    match endpoint {
+        Endpoint::Path(path) => UnixStream::connect(path),
+        Endpoint::Abstract(name) => {
+            let addr = abstract_addr(name).map_err(std::io::Error::other)?;
+            UnixStream::connect_addr(&addr)
+        }
+    }
+}
+
+pub fn create_listener(endpoint: &Endpoint) -> Result<UnixListener> {
+    let listener = match endpoint {
+        Endpoint::Path(path) => {
+            // Remove stale socket
+            if path.exists() {
+                // Check if another instance is running
+                if UnixStream::connect(path).is_ok() {
+                    anyhow::bail!("Another clockie instance is already running (socket {} is active)", path.display());
+                }
+                std::fs::remove_file(path)?;
+            }
+            UnixListener::bind(path)?
+        }
+        Endpoint::Abstract(name) => {
+            // The kernel frees abstract names on exit, so a bind failure means a live instance
+            UnixListener::bind_addr(&abstract_addr(name)?).map_err(|e| match e.kind() {
+                std::io::ErrorKind::AddrInUse => anyhow::anyhow!("Another clockie instance is already running (socket {} is active)", endpoint),
+                _ => e.into(),
+            })?
+        }
+    };
     listener.set_nonblocking(true)?;
-    log::info!("IPC listening on {}", path.display());
+    log::info!("IPC listening on {}", endpoint);
     Ok(listener)
 }
 
-pub fn cleanup_socket(path: &PathBuf) {
-    if path.exists() {
-        let _ = std::fs::remove_file(path);
-        log::info!("Removed socket {}", path.display());
+pub fn cleanup_socket(endpoint: &Endpoint) {
+    if let Endpoint::Path(path) = endpoint {
+        if path.exists() {
+            let _ = std::fs::remove_file(path);
+            log::info!("Removed socket {}", path.display());
+        }
     }
 }
 
-pub fn read_command(stream: &UnixStream) -> Result<IpcCommand> {
-    let reader = BufReader::new(stream);
-    let mut line = String::new();
-    let mut reader = reader;
-    reader.read_line(&mut line)?;
-    let cmd: IpcCommand = serde_json::from_str(line.trim())?;
-    Ok(cmd)
+/// Read one command. `None` means the client hung up without sending
+/// anything. The outer error is an I/O failure; the inner one is a request
+/// the daemon can't act on, to be reported back to the client. A leading
+/// `hello` request is answered here and switches `framing` to the one it
+/// asked for, which the response must then be written with.
+pub fn read_command(
+    stream: &UnixStream,
+    framing: &mut Framing,
+) -> Result<Option<std::result::Result<IpcCommand, String>>> {
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut reader = BufReader::new(DeadlineReader { stream, deadline: Instant::now() + CLIENT_TIMEOUT });
+    let Some(mut request) = read_request(&mut reader, *framing)? else {
+        return Ok(None);
+    };
+    if let Ok(value) = &request {
+        if value.get("cmd").and_then(|v| v.as_str()) == Some("hello") {
+            let hello = match serde_json::from_value::<Hello>(value.clone()) {
+                Ok(hello) => hello,
+                Err(e) => return Ok(Some(Err(format!("invalid arguments for hello: {}", e)))),
+            };
+            write_response(stream, &IpcResponse::ok().with_framing(hello.framing), *framing)?;
+            *framing = hello.framing;
+            request = match read_request(&mut reader, *framing)? {
+                Some(request) => request,
+                None => return Ok(None),
+            };
+        }
+    }
+    Ok(Some(request.and_then(parse_command)))
+}
+
+/// Reads from a client until a fixed deadline. Each read's timeout is set to
+/// the time left, so a client trickling bytes can't keep a request going.
+struct DeadlineReader<'a> {
+    stream: &'a UnixStream,
+    deadline: Instant,
 }
 
-pub fn write_response(stream: &mut UnixStream, response: &IpcResponse) -> Result<()> {
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::Error::new(ErrorKind::TimedOut, "client request took too long"));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        let mut stream = self.stream;
+        stream.read(buf)
+    }
+}
+
+/// Read one line or frame and parse it as JSON. `None` means the client
+/// hung up at a request boundary.
+fn read_request(
+    reader: &mut BufReader<DeadlineReader>,
+    framing: Framing,
+) -> Result<Option<std::result::Result<serde_json::Value, String>>> {
+    let timed_out = |e: &std::io::Error| matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut);
+    let bytes = match framing {
+        Framing::Line => {
+            let mut line = Vec::new();
+            match reader.by_ref().take(MAX_REQUEST_BYTES as u64 + 1).read_until(b'\n', &mut line) {
+                Ok(0) => return Ok(None),
+                Ok(_) if line.len() > MAX_REQUEST_BYTES as usize => {
+                    return Ok(Some(Err(format!("request longer than {} bytes", MAX_REQUEST_BYTES))));
+                }
+                Ok(_) => line,
+                Err(e) if timed_out(&e) => {
+                    return Ok(Some(Err("timed out waiting for a newline-terminated request".into())));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Framing::LengthPrefixed => match read_frame(reader, MAX_REQUEST_BYTES) {
+            Ok(frame) => frame,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                return Ok(Some(Err("connection closed in the middle of a frame".into())));
+            }
+            Err(e) if e.kind() == ErrorKind::InvalidData => {
+                return Ok(Some(Err(format!("request longer than {} bytes", MAX_REQUEST_BYTES))));
+            }
+            Err(e) if timed_out(&e) => {
+                return Ok(Some(Err("timed out waiting for a complete frame".into())));
+            }
+            Err(e) => return Err(e.into()),
+        },
+    };
+
+    let Ok(text) = std::str::from_utf8(&bytes) else {
+        return Ok(Some(Err("request is not valid UTF-8".into())));
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(Some(Err("empty request".into())));
+    }
+    Ok(Some(serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))))
+}
+
+/// Read one length-prefixed frame, refusing (`InvalidData`) any longer than
+/// `limit` bytes before reading its body.
+pub fn read_frame(reader: &mut impl Read, limit: u32) -> std::io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len);
+    if len > limit {
+        return Err(std::io::Error::new(ErrorKind::InvalidData, format!("frame of {} bytes is too long", len)));
+    }
+    let mut frame = vec![0u8; len as usize];
+    reader.read_exact(&mut frame)?;
+    Ok(frame)
+}
+
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| std::io::Error::new(ErrorKind::InvalidInput, "frame longer than 4 GiB"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+fn parse_command(value: serde_json::Value) -> std::result::Result<IpcCommand, String> {
+    let name = value.get("cmd").and_then(|v| v.as_str())
+        .ok_or("missing \"cmd\" field")?
+        .to_string();
+    let proto = value.get("proto").and_then(|v| v.as_u64());
+    match serde_json::from_value(value) {
+        Ok(IpcCommand::Unknown) => Err(match proto {
+            Some(p) if p > PROTOCOL_VERSION as u64 => format!(
+                "unknown command: {} (client speaks protocol {}, daemon {}; restart clockie after upgrading)",
+                name, p, PROTOCOL_VERSION,
+            ),
+            _ => format!("unknown command: {}", name),
+        }),
+        Ok(cmd) => Ok(cmd),
+        Err(e) => Err(format!("invalid arguments for {}: {}", name, e)),
+    }
+}
+
+pub fn write_response(mut stream: &UnixStream, response: &IpcResponse, framing: Framing) -> Result<()> {
     let json = serde_json::to_string(response)?;
-    stream.write_all(json.as_bytes())?;
-    stream.write_all(b"\n")?;
-    stream.flush()?;
+    match framing {
+        Framing::Line => {
+            stream.write_all(json.as_bytes())?;
+            stream.write_all(b"\n")?;
+            stream.flush()?;
+        }
+        Framing::LengthPrefixed => write_frame(&mut stream, json.as_bytes())?,
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// How a test client leaves the connection once it has written its input.
+    #[derive(Clone, Copy)]
+    enum Then {
+        Close,
+        HoldOpen,
+    }
+
+    /// Feed `input` to `read_command` over a socket pair and return its result
+    /// and how long it took.
+    fn feed(input: Vec<u8>, framing: Framing, then: Then) -> (Result<Option<std::result::Result<IpcCommand, String>>>, Duration) {
+        feed_with(framing, move |mut writer| {
+            let _ = writer.write_all(&input);
+            if let Then::Close = then {
+                let _ = writer.shutdown(std::net::Shutdown::Write);
+            }
+        })
+    }
+
+    /// Like `feed`, with `write` doing the client's writing.
+    fn feed_with(framing: Framing, write: impl FnOnce(UnixStream) + Send + 'static) -> (Result<Option<std::result::Result<IpcCommand, String>>>, Duration) {
+        let (client, server) = UnixStream::pair().expect("socket pair");
+        let writer = client.try_clone().expect("clone client");
+        // Written from another thread so oversized input can't block the test
+        let handle = std::thread::spawn(move || write(writer));
+        let mut framing = framing;
+        let start = Instant::now();
+        let result = read_command(&server, &mut framing);
+        let elapsed = start.elapsed();
+        drop(server);
+        drop(client);
+        handle.join().expect("writer thread");
+        (result, elapsed)
+    }
+
+    fn assert_rejected(input: Vec<u8>, framing: Framing, then: Then, label: &str) {
+        assert_result_rejected(feed(input, framing, then), label);
+    }
+
+    fn assert_result_rejected((result, elapsed): (Result<Option<std::result::Result<IpcCommand, String>>>, Duration), label: &str) {
+        match result {
+            Ok(None) | Ok(Some(Err(_))) => {}
+            Ok(Some(Ok(cmd))) => panic!("{label}: accepted as {cmd:?}"),
+            Err(e) => panic!("{label}: failed with {e}"),
+        }
+        // One client timeout, plus some slack for a loaded machine
+        assert!(elapsed < CLIENT_TIMEOUT + Duration::from_millis(250), "{label}: took {elapsed:?}");
+    }
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut bytes = (payload.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    /// xorshift64, so the random cases are the same on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+            let len = (self.next() % max_len as u64) as usize;
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    #[test]
+    fn random_input_is_rejected() {
+        let mut rng = Rng(0x5eed_c10c_1e00_0001);
+        for i in 0..200 {
+            let bytes = rng.bytes(2048);
+            assert_rejected(bytes.clone(), Framing::Line, Then::Close, &format!("line case {i}"));
+            assert_rejected(frame(&bytes), Framing::LengthPrefixed, Then::Close, &format!("frame case {i}"));
+            assert_rejected(bytes, Framing::LengthPrefixed, Then::Close, &format!("raw frame case {i}"));
+        }
+    }
+
+    #[test]
+    fn empty_input_is_rejected() {
+        assert_rejected(Vec::new(), Framing::Line, Then::Close, "nothing");
+        assert_rejected(b"\n".to_vec(), Framing::Line, Then::Close, "blank line");
+        assert_rejected(b"   \r\n".to_vec(), Framing::Line, Then::Close, "whitespace line");
+        assert_rejected(Vec::new(), Framing::LengthPrefixed, Then::Close, "no frame");
+        assert_rejected(frame(b""), Framing::LengthPrefixed, Then::Close, "empty frame");
+    }
+
+    #[test]
+    fn non_utf8_input_is_rejected() {
+        let bytes = b"{\"cmd\":\"\xff\xfe\"}".to_vec();
+        let mut line = bytes.clone();
+        line.push(b'\n');
+        assert_rejected(line, Framing::Line, Then::Close, "line");
+        assert_rejected(frame(&bytes), Framing::LengthPrefixed, Then::Close, "frame");
+    }
+
+    #[test]
+    fn oversized_input_is_rejected() {
+        let long = vec![b'a'; MAX_REQUEST_BYTES as usize + 16];
+        assert_rejected(long.clone(), Framing::Line, Then::Close, "long line");
+        assert_rejected(long.clone(), Framing::Line, Then::HoldOpen, "long line held open");
+        assert_rejected(frame(&long), Framing::LengthPrefixed, Then::Close, "long frame");
+        assert_rejected(u32::MAX.to_le_bytes().to_vec(), Framing::LengthPrefixed, Then::HoldOpen, "huge frame header");
+    }
+
+    #[test]
+    fn half_written_input_is_rejected() {
+        let partial = br#"{"cmd":"sta"#.to_vec();
+        assert_rejected(partial.clone(), Framing::Line, Then::Close, "line then close");
+        assert_rejected(partial.clone(), Framing::Line, Then::HoldOpen, "line held open");
+
+        let mut short_frame = 100u32.to_le_bytes().to_vec();
+        short_frame.extend_from_slice(&partial);
+        assert_rejected(short_frame.clone(), Framing::LengthPrefixed, Then::Close, "frame then close");
+        assert_rejected(short_frame, Framing::LengthPrefixed, Then::HoldOpen, "frame held open");
+        assert_rejected(vec![0x10, 0x00], Framing::LengthPrefixed, Then::HoldOpen, "half a frame header");
+
+        // A hello that switches framing, then a half-written frame
+        let mut after_hello = b"{\"cmd\":\"hello\",\"framing\":\"length-prefixed\"}\n".to_vec();
+        after_hello.extend_from_slice(&100u32.to_le_bytes());
+        after_hello.extend_from_slice(&partial);
+        assert_rejected(after_hello.clone(), Framing::Line, Then::Close, "hello then close");
+        assert_rejected(after_hello, Framing::Line, Then::HoldOpen, "hello then held open");
+    }
+
+    #[test]
+    fn trickled_input_is_rejected() {
+        // One byte at a time, each well inside the timeout, for far longer than it
+        let trickle = |start: Vec<u8>| move |mut writer: UnixStream| {
+            let stop = Instant::now() + CLIENT_TIMEOUT * 4;
+            if writer.write_all(&start).is_err() { return; }
+            while Instant::now() < stop && writer.write_all(b"a").is_ok() {
+                std::thread::sleep(CLIENT_TIMEOUT / 5);
+            }
+        };
+        assert_result_rejected(feed_with(Framing::Line, trickle(br#"{"cmd":""#.to_vec())), "line");
+        assert_result_rejected(feed_with(Framing::LengthPrefixed, trickle(100u32.to_le_bytes().to_vec())), "frame");
+
+        let mut after_hello = b"{\"cmd\":\"hello\",\"framing\":\"length-prefixed\"}\n".to_vec();
+        after_hello.extend_from_slice(&100u32.to_le_bytes());
+        assert_result_rejected(feed_with(Framing::Line, trickle(after_hello)), "hello then frame");
+    }
+}
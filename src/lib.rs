@@ -0,0 +1,9 @@
+//! Config loading, rendering and window sizing, shared by the `clockie`
+//! binary. None of it needs a compositor, so layout can be checked from
+//! integration tests with [`canvas::FontState::embedded`].
+
+pub mod battery;
+pub mod canvas;
+pub mod config;
+pub mod renderer;
+pub mod time_utils;
@@ -1,12 +1,11 @@
-mod battery;
-mod canvas;
-mod config;
 mod ctl;
+mod headless;
+mod hooks;
 mod ipc;
-mod renderer;
-mod time_utils;
 mod wayland;
 
+use clockie::{battery, canvas, config, renderer, time_utils};
+
 use anyhow::Result;
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::Shell;
@@ -27,6 +26,30 @@ pub struct Cli {
     #[arg(long)]
     compact: bool,
 
+    /// Override layer: background | bottom | top | overlay
+    #[arg(long)]
+    layer: Option<String>,
+
+    /// Override anchor edges, e.g. "top right"
+    #[arg(long)]
+    anchor: Option<String>,
+
+    /// Override window opacity (0.0–1.0)
+    #[arg(long)]
+    opacity: Option<f32>,
+
+    /// Override output to display on (e.g. HDMI-A-1)
+    #[arg(long)]
+    output: Option<String>,
+
+    /// Override digital font size in pixels
+    #[arg(long)]
+    font_size: Option<f32>,
+
+    /// Override analogue face diameter in pixels
+    #[arg(long)]
+    diameter: Option<u32>,
+
     /// Override first extra timezone
     #[arg(long)]
     tz1: Option<String>,
@@ -43,10 +66,30 @@ pub struct Cli {
     #[arg(long)]
     socket: Option<PathBuf>,
 
+    /// Listen on an abstract socket (Linux) instead of a socket file
+    #[arg(long)]
+    abstract_socket: bool,
+
     /// Generate shell completions and exit
     #[arg(long, value_name = "SHELL")]
     completions: Option<Shell>,
 
+    /// Validate the config file, report every problem, and exit (0 = valid)
+    #[arg(long)]
+    check: bool,
+
+    /// Print the effective config (after CLI overrides) as TOML and exit
+    #[arg(long)]
+    dump_config: bool,
+
+    /// List connected outputs (name, description, position, size) and exit
+    #[arg(long)]
+    list_outputs: bool,
+
+    /// Print --list-outputs as JSON
+    #[arg(long, requires = "list_outputs")]
+    json: bool,
+
     #[command(subcommand)]
     command: Option<CliCommand>,
 }
@@ -55,18 +98,48 @@ pub struct Cli {
 enum CliCommand {
     /// Control a running clockie instance
     Ctl(ctl::CtlArgs),
+    /// Render one frame to a PNG file and exit
+    Render(headless::RenderArgs),
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
-    match cli.command {
+    match cli.command.take() {
         Some(CliCommand::Ctl(args)) => ctl::run(args),
+        Some(CliCommand::Render(args)) => run_render(&cli, &args),
         None => run_daemon(cli),
     }
 }
 
+fn run_render(cli: &Cli, args: &headless::RenderArgs) -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    // Like --dump-config, a missing config file renders the system config or
+    // the defaults instead of creating one
+    let config_path = args.config.clone()
+        .or_else(|| cli.config.clone())
+        .unwrap_or_else(config::default_config_path);
+    let mut config = if config_path.exists() {
+        config::load_config(&config_path)?
+    } else {
+        config::fallback_config()?
+    };
+    apply_cli_overrides(cli, &mut config)?;
+
+    headless::run(config, args)
+}
+
 fn run_daemon(args: Cli) -> Result<()> {
+    if args.check {
+        let config_path = args.config.unwrap_or_else(config::default_config_path);
+        std::process::exit(run_check(&config_path));
+    }
+
+    if args.list_outputs {
+        return wayland::list_outputs(args.json);
+    }
+
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
     if let Some(shell) = args.completions {
@@ -75,10 +148,34 @@ fn run_daemon(args: Cli) -> Result<()> {
         return Ok(());
     }
 
-    let config_path = args.config.unwrap_or_else(config::default_config_path);
+    let config_path = args.config.clone().unwrap_or_else(config::default_config_path);
+
+    if args.dump_config {
+        // Don't generate a default config file just to print it
+        let mut config = if config_path.exists() {
+            config::load_config(&config_path)?
+        } else {
+            config::fallback_config()?
+        };
+        apply_cli_overrides(&args, &mut config)?;
+        print!("{}", config::to_toml_string(&config)?);
+        return Ok(());
+    }
+
     let mut config = config::load_config(&config_path)?;
+    apply_cli_overrides(&args, &mut config)?;
+
+    log::info!("Starting clockie with face={:?}, compact={}", config.clock.face, config.window.compact);
+    log::info!("Content sizing: font_size={}, diameter={}", config.clock.font_size, config.clock.diameter);
+
+    let endpoint = ipc::endpoint(args.socket.as_ref(), args.abstract_socket);
+    wayland::run(config, config_path, endpoint)?;
 
-    // Apply CLI overrides
+    Ok(())
+}
+
+/// Apply command-line overrides on top of the loaded config.
+fn apply_cli_overrides(args: &Cli, config: &mut config::ClockConfig) -> Result<()> {
     if let Some(face) = &args.face {
         match face.as_str() {
             "digital" => config.clock.face = config::FaceMode::Digital,
@@ -89,21 +186,55 @@ fn run_daemon(args: Cli) -> Result<()> {
     if args.compact {
         config.window.compact = true;
     }
+    if let Some(layer) = &args.layer {
+        match layer.as_str() {
+            "background" | "bottom" | "top" | "overlay" => config.window.layer = layer.clone(),
+            other => anyhow::bail!("Unknown layer: {}. Use background, bottom, top, or overlay", other),
+        }
+    }
+    if let Some(anchor) = &args.anchor {
+        if let Some(bad) = anchor.split_whitespace()
+            .find(|edge| !["top", "bottom", "left", "right"].contains(&edge.to_lowercase().as_str()))
+        {
+            anyhow::bail!("Unknown anchor edge: {}. Use top, bottom, left, or right", bad);
+        }
+        config.window.anchor = anchor.clone();
+    }
+    if let Some(opacity) = args.opacity {
+        anyhow::ensure!((0.0..=1.0).contains(&opacity), "Opacity must be between 0.0 and 1.0, got {}", opacity);
+        config.window.opacity = opacity;
+    }
+    if let Some(output) = &args.output {
+        config.window.output = Some(output.clone());
+    }
+    if let Some(size) = args.font_size {
+        let (min, max) = (config.clock.min_font_size, config.clock.max_font_size);
+        anyhow::ensure!(size >= min && size <= max, "Font size must be between {} and {}, got {}", min, max, size);
+        config.clock.font_size = size;
+    }
+    if let Some(diameter) = args.diameter {
+        let (min, max) = (config.clock.min_diameter, config.clock.max_diameter);
+        anyhow::ensure!(diameter >= min && diameter <= max, "Diameter must be between {} and {}, got {}", min, max, diameter);
+        config.clock.diameter = diameter;
+    }
     if args.no_tz {
         config.timezone.clear();
     } else {
+        for tz in args.tz1.iter().chain(&args.tz2) {
+            anyhow::ensure!(tz.parse::<chrono_tz::Tz>().is_ok(), "Unknown timezone: {} (expected an IANA name like Europe/London)", tz);
+        }
         if let Some(tz1) = &args.tz1 {
             if config.timezone.is_empty() {
-                config.timezone.push(config::TimezoneEntry { label: tz1.clone(), tz: tz1.clone() });
+                config.timezone.push(config::TimezoneEntry::from_tz(tz1));
             } else {
-                config.timezone[0] = config::TimezoneEntry { label: tz1.clone(), tz: tz1.clone() };
+                config.timezone[0] = config::TimezoneEntry::from_tz(tz1);
             }
         }
         if let Some(tz2) = &args.tz2 {
             if config.timezone.len() < 2 {
-                config.timezone.push(config::TimezoneEntry { label: tz2.clone(), tz: tz2.clone() });
+                config.timezone.push(config::TimezoneEntry::from_tz(tz2));
             } else {
-                config.timezone[1] = config::TimezoneEntry { label: tz2.clone(), tz: tz2.clone() };
+                config.timezone[1] = config::TimezoneEntry::from_tz(tz2);
             }
         }
     }
@@ -111,10 +242,31 @@ fn run_daemon(args: Cli) -> Result<()> {
     // Truncate to max 2 timezone entries
     config.timezone.truncate(2);
 
-    log::info!("Starting clockie with face={:?}, compact={}", config.clock.face, config.window.compact);
-    log::info!("Content sizing: font_size={}, diameter={}", config.clock.font_size, config.clock.diameter);
+    Ok(())
+}
 
-    wayland::run(config, config_path, args.socket)?;
+/// Validate a config file and print a report. Runs before logging is set up so
+/// the report isn't interleaved with log lines. Returns the process exit code.
+fn run_check(config_path: &std::path::Path) -> i32 {
+    let (config, mut problems) = config::validate_config(config_path);
 
-    Ok(())
+    // A font that looks like a path must load; names always fall back to a
+    // system or embedded font, so there is nothing to check for them
+    if let Some(config) = &config {
+        let font = &config.clock.font;
+        if canvas::FontState::is_path(font) && canvas::FontState::from_path(font).is_none() {
+            problems.push(format!("[clock] font: cannot load {}", font));
+        }
+    }
+
+    if problems.is_empty() {
+        println!("{}: OK", config_path.display());
+        0
+    } else {
+        println!("{}: {} problem(s)", config_path.display(), problems.len());
+        for problem in &problems {
+            println!("  - {}", problem);
+        }
+        1
+    }
 }
@@ -0,0 +1,28 @@
+use crate::canvas::Canvas;
+use crate::renderer::{ClockState, CornerGlyph};
+
+/// Bell outline in a unit square: a dome widening into a flared rim.
+const BELL: [(f32, f32); 8] = [
+    (0.38, 0.16), (0.62, 0.16), (0.75, 0.36), (0.78, 0.64),
+    (0.92, 0.78), (0.08, 0.78), (0.22, 0.64), (0.25, 0.36),
+];
+
+/// Draw a small bell in the top-left corner while an alarm is pending, the
+/// corner opposite the battery icon.
+pub fn render(canvas: &mut Canvas, state: &ClockState) {
+    let glyph = CornerGlyph::new(canvas, state, false);
+    let at = |p| glyph.at(p);
+
+    let color = state.contrast.text_color;
+    let bell: Vec<(f32, f32)> = BELL.iter().copied().map(at).collect();
+    if let Some(edge) = glyph.edge {
+        canvas.stroke_polygon(&bell, edge, glyph.edge_width());
+    }
+    canvas.fill_polygon(&bell, color);
+
+    // Knob on top and clapper under the rim
+    let (kx, ky) = at((0.5, 0.12));
+    canvas.draw_circle(kx, ky, glyph.size * 0.07, color, true, 0.0);
+    let (cx, cy) = at((0.5, 0.86));
+    canvas.draw_circle(cx, cy, glyph.size * 0.09, color, true, 0.0);
+}
@@ -1,114 +1,194 @@
 use crate::canvas::{self, Canvas, FontState};
-use crate::config::{AnalogueConfig, HandCap, NumeralStyle, TickStyle, TickVisibility};
-use crate::renderer::{draw_contrast_text, ClockState, ContrastInfo, SubclockSizing};
+use crate::config::{AnalogueConfig, HandCap, NumeralStyle, SweepEasing, TickStyle, TickVisibility};
+use crate::renderer::{battery, compact_factor, draw_contrast_text, outline_color_for, place_content, ClockState, ContrastInfo, Region, SubclockSizing};
+
+/// How long a `bounce` second hand takes to settle after each tick.
+const BOUNCE_MS: u32 = 300;
+
+/// Outer end of the ticks, as a fraction of the dial radius.
+pub const TICK_OUTER: f32 = 0.98;
+
+/// Second hand angle in degrees for `second` and `millis` into it.
+fn second_hand_angle(easing: SweepEasing, second: u32, millis: u32) -> f32 {
+    let base = second as f32 * 6.0;
+    match easing {
+        SweepEasing::Tick => base,
+        SweepEasing::Linear => base + millis.min(999) as f32 * 0.006,
+        // Swing up to about a degree past the mark, easing back as it settles
+        SweepEasing::Bounce if millis < BOUNCE_MS => {
+            let t = millis as f32 / BOUNCE_MS as f32;
+            base + 1.5 * (t * std::f32::consts::PI).sin() * (1.0 - t)
+        }
+        SweepEasing::Bounce => base,
+    }
+}
+
+/// Whether the second hand is moving between ticks `millis` into a second,
+/// which needs redraws more often than once a second.
+pub fn second_hand_moving(easing: SweepEasing, millis: u32) -> bool {
+    match easing {
+        SweepEasing::Tick => false,
+        SweepEasing::Linear => true,
+        SweepEasing::Bounce => millis < BOUNCE_MS,
+    }
+}
+
+/// Centre and radius of the dial within a `w`x`h` window.
+pub fn face_geometry(state: &ClockState, w: f32, h: f32) -> (f32, f32, f32) {
+    let config = &state.config;
+    let diameter = config.clock.diameter as f32;
+    let effective = diameter * compact_factor(config, state.compact);
+    let radius = effective / 2.0;
+
+    // Subclock area height (hidden in compact mode)
+    let subclock_h = SubclockSizing::reserved_height(config, state.compact);
+
+    let pad = config.analogue.padding.max(0.0);
+    let dial = place_content(w, h, subclock_h, effective, effective, (pad, pad), config.window.content_gravity());
+    (dial.x + radius, dial.y + radius, radius)
+}
+
+/// Radius of the ticks' outer ends: just inside the bezel, or inside the
+/// battery ring with a small gap when one is drawn around the dial.
+fn tick_outer(state: &ClockState, radius: f32) -> f32 {
+    match battery::ring_geometry(state, radius) {
+        Some((ring_r, ring_w)) => ring_r - ring_w,
+        None => radius * TICK_OUTER,
+    }
+}
 
 /// Render the analogue clock background: clear + face image or procedural face.
 pub fn render_background(canvas: &mut Canvas, state: &ClockState, font: &FontState) {
-    let w = canvas.width() as f32;
-    let h = canvas.height() as f32;
     let config = &state.config;
     let theme = &config.theme;
 
     // Clear background
     canvas.clear(theme.bg_color);
 
-    let diameter = config.clock.diameter as f32;
-    let effective = if state.compact { diameter * 0.75 } else { diameter };
-    let radius = effective / 2.0;
-
-    // Subclock area height (hidden in compact mode)
-    let subclock_h = if !state.compact && !config.timezone.is_empty() {
-        let tz_count = config.timezone.len().min(2);
-        let sz = SubclockSizing::from_base(diameter * 0.25);
-        sz.area_h * tz_count as f32
-    } else {
-        0.0
-    };
-
-    // Clock area is total height minus subclock area
-    let clock_area_h = h - subclock_h;
-    let cx = w / 2.0;
-    let cy = clock_area_h / 2.0;
+    let (cx, cy, radius) = face_geometry(state, canvas.width() as f32, canvas.height() as f32);
+    let tick_outer = tick_outer(state, radius);
 
     // Draw face image or procedural face
     if !config.background.analogue_face_image.is_empty() {
         let path = &config.background.analogue_face_image;
-        let size = (radius * 2.0) as u32;
+        // Rasterize the face at device resolution, then map it onto the logical face area
+        let size = radius * 2.0;
+        let dev_size = (size * canvas.scale()).round() as u32;
         let face = if canvas::is_svg(path) {
-            canvas::load_svg(path, size, size)
+            canvas::load_svg(path, dev_size, dev_size)
         } else {
-            canvas::load_image(path).map(|img| canvas::scale_image(&img, size, size, &config.background.image_scale))
+            let align = config.background.image_alignment(canvas.scale());
+            canvas::load_image(path).map(|img| canvas::scale_image(&img, dev_size, dev_size, &config.background.image_scale, align))
         };
         if let Some(img) = face {
-            canvas.draw_image(&img, (cx - radius) as i32, (cy - radius) as i32);
+            let target = dev_size as f32 / canvas.scale();
+            let (x, y) = ((cx - radius).floor(), (cy - radius).floor());
+            canvas.draw_scaled_image(&img, x, y, target, target);
+            // Face images are only dimmed when a scrim is asked for explicitly,
+            // and only within the dial so the corners stay untouched
+            if config.background.scrim_opacity.is_some() {
+                canvas.draw_circle(cx, cy, radius, config.background.scrim_color(theme.bg_color), true, 0.0);
+            }
+            if config.analogue.ticks_over_image {
+                draw_face_marks(canvas, font, cx, cy, radius, tick_outer, &config.analogue, theme, &state.contrast);
+            }
         } else {
-            draw_procedural_face(canvas, font, cx, cy, radius, &config.analogue, &config.theme, &state.contrast);
+            draw_procedural_face(canvas, font, cx, cy, radius, tick_outer, &config.analogue, &config.theme, &state.contrast);
         }
     } else {
-        draw_procedural_face(canvas, font, cx, cy, radius, &config.analogue, &config.theme, &state.contrast);
+        draw_procedural_face(canvas, font, cx, cy, radius, tick_outer, &config.analogue, &config.theme, &state.contrast);
     }
 }
 
-/// Render the analogue clock foreground: hands and centre boss.
-pub fn render_foreground(canvas: &mut Canvas, state: &ClockState, _font: &FontState) {
-    let w = canvas.width() as f32;
-    let h = canvas.height() as f32;
+/// The digital readout's text, size and position, when enabled and not compact.
+pub fn readout_layout(state: &ClockState, font: &FontState, w: f32, h: f32) -> Option<(String, f32, Region)> {
+    let config = &state.config;
+    if !config.analogue.digital_readout || state.compact {
+        return None;
+    }
+    let (cx, cy, radius) = face_geometry(state, w, h);
+    let text = format!(
+        "{}{}",
+        state.time.format_time(config.clock.hour_format, false),
+        state.time.format_time_suffix(config.clock.hour_format),
+    );
+    let size = radius * 0.14;
+    let (tw, _) = font.measure_text(&text, size);
+
+    // Centre the text between the boss and the top of the 6 o'clock numeral
+    let boss_r = radius * config.analogue.boss_radius;
+    let six = radius * (1.0 - config.analogue.numeral_inset) - radius * config.analogue.numeral_size / 2.0;
+    let mid = cy + (boss_r + six) / 2.0;
+    Some((text, size, Region { x: cx - tw / 2.0, y: mid - size / 2.0, w: tw, h: size }))
+}
+
+/// Render the analogue clock foreground: digital readout, hands and centre boss.
+pub fn render_foreground(canvas: &mut Canvas, state: &ClockState, font: &FontState) {
     let config = &state.config;
     let theme = &config.theme;
     let acfg = &config.analogue;
 
-    let diameter = config.clock.diameter as f32;
-    let effective = if state.compact { diameter * 0.75 } else { diameter };
-    let radius = effective / 2.0;
+    let (cx, cy, radius) = face_geometry(state, canvas.width() as f32, canvas.height() as f32);
 
-    // Subclock area height (hidden in compact mode)
-    let subclock_h = if !state.compact && !config.timezone.is_empty() {
-        let tz_count = config.timezone.len().min(2);
-        let sz = SubclockSizing::from_base(diameter * 0.25);
-        sz.area_h * tz_count as f32
-    } else {
-        0.0
-    };
-
-    let clock_area_h = h - subclock_h;
-    let cx = w / 2.0;
-    let cy = clock_area_h / 2.0;
+    // Readout first so the hands sweep over it
+    if let Some((text, size, region)) = readout_layout(state, font, canvas.width() as f32, canvas.height() as f32) {
+        let contrast = &state.elements.time;
+        draw_contrast_text(font, canvas, &text, region.x, region.y, size, contrast.text_color, contrast);
+    }
 
     // Draw hands
     let sec = state.time.second as f32;
     let min = state.time.minute as f32 + sec / 60.0;
     let hr = (state.time.hour % 12) as f32 + min / 60.0;
 
-    let sec_angle = sec * 6.0;
+    let sec_angle = second_hand_angle(acfg.sweep_easing, state.time.second, state.time.millis);
     let min_angle = min * 6.0;
     let hr_angle = hr * 30.0;
 
-    let hand_scale = if state.compact { 0.8 } else { 1.0 };
+    let hand_scale = if state.compact { state.config.window.compact_hand_scale.clamp(0.1, 1.0) } else { 1.0 };
+    // Hands stop at the inner edge of the bezel however long they are configured
+    let max_len = usable_radius(radius, acfg);
+    let hand_len = |length: f32| (radius * length * hand_scale).min(max_len);
 
     // Hour hand
     draw_hand(canvas, cx, cy, hr_angle,
-        radius * acfg.hour_hand_length * hand_scale,
+        hand_len(acfg.hour_hand_length),
         radius * acfg.hour_hand_width,
         theme.hour_hand_color, acfg);
     // Minute hand
     draw_hand(canvas, cx, cy, min_angle,
-        radius * acfg.minute_hand_length * hand_scale,
+        hand_len(acfg.minute_hand_length),
         radius * acfg.minute_hand_width,
         theme.minute_hand_color, acfg);
     // Second hand
     draw_hand(canvas, cx, cy, sec_angle,
-        radius * acfg.second_hand_length * hand_scale,
+        hand_len(acfg.second_hand_length),
         radius * acfg.second_hand_width,
         theme.second_hand_color, acfg);
 
     // Centre boss
-    canvas.draw_circle(cx, cy, radius * 0.05, state.contrast.text_color, true, 0.0);
+    let boss_r = radius * acfg.boss_radius;
+    if boss_r > 0.0 {
+        let boss_color = acfg.boss_color.unwrap_or(state.contrast.text_color);
+        canvas.draw_circle(cx, cy, boss_r, boss_color, true, 0.0);
+        if let Some(inner) = acfg.boss_inner_color {
+            canvas.draw_circle(cx, cy, boss_r * 0.5, inner, true, 0.0);
+        }
+    }
+}
+
+/// Radius inside the bezel stroke (or the default 2px ring).
+fn usable_radius(radius: f32, acfg: &AnalogueConfig) -> f32 {
+    let stroke_w = if acfg.bezel_width > 0.0 { radius * acfg.bezel_width } else { 2.0 };
+    (radius - stroke_w / 2.0).max(0.0)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn draw_procedural_face(
     canvas: &mut Canvas,
     font: &FontState,
     cx: f32, cy: f32, radius: f32,
+    tick_outer: f32,
     acfg: &AnalogueConfig,
     theme: &crate::config::ThemeConfig,
     contrast: &ContrastInfo,
@@ -119,13 +199,8 @@ fn draw_procedural_face(
     }
 
     // 2. Bezel
-    if acfg.bezel_width > 0.0 {
-        let stroke_w = radius * acfg.bezel_width;
-        canvas.draw_circle(cx, cy, radius, acfg.bezel_color, false, stroke_w);
-    } else {
-        // Default thin 2px stroke (current behavior)
-        canvas.draw_circle(cx, cy, radius, theme.tick_color, false, 2.0);
-    }
+    let stroke_w = if acfg.bezel_width > 0.0 { radius * acfg.bezel_width } else { 2.0 };
+    canvas.draw_circle(cx, cy, radius, acfg.bezel_color(), false, stroke_w);
 
     // 3. Minute track
     if acfg.minute_track_width > 0.0 {
@@ -134,22 +209,41 @@ fn draw_procedural_face(
         canvas.draw_circle(cx, cy, track_r, acfg.minute_track_color, false, stroke_w);
     }
 
-    // 4. Ticks
-    draw_ticks(canvas, cx, cy, radius, acfg, theme);
+    // 4. Ticks and 5. numerals
+    draw_face_marks(canvas, font, cx, cy, radius, tick_outer, acfg, theme, contrast);
+}
 
-    // 5. Numerals
+/// Ticks and numerals, drawn by the procedural face and, with
+/// `ticks_over_image`, over a face image.
+#[allow(clippy::too_many_arguments)]
+fn draw_face_marks(
+    canvas: &mut Canvas,
+    font: &FontState,
+    cx: f32, cy: f32, radius: f32,
+    tick_outer: f32,
+    acfg: &AnalogueConfig,
+    theme: &crate::config::ThemeConfig,
+    contrast: &ContrastInfo,
+) {
+    draw_ticks(canvas, cx, cy, radius, tick_outer, acfg, theme);
     draw_numerals(canvas, font, cx, cy, radius, acfg, contrast);
+    if acfg.minute_numbers {
+        draw_minute_numbers(canvas, font, cx, cy, radius, tick_outer, acfg, contrast);
+    }
 }
 
 fn draw_ticks(
     canvas: &mut Canvas,
     cx: f32, cy: f32, radius: f32,
+    tick_outer: f32,
     acfg: &AnalogueConfig,
     theme: &crate::config::ThemeConfig,
 ) {
     if acfg.show_ticks == TickVisibility::None {
         return;
     }
+    // Ticks pulled in from the rim (inside a battery ring) keep their proportions
+    let shrink = tick_outer / (radius * TICK_OUTER);
 
     for i in 0..60 {
         let is_hour = i % 5 == 0;
@@ -164,12 +258,15 @@ fn draw_ticks(
         if !should_draw { continue; }
 
         let angle = (i as f32 * 6.0 - 90.0).to_radians();
-        let inner = if is_hour { radius * 0.85 } else { radius * 0.92 };
-        let outer = radius * 0.98;
+        let outer = tick_outer;
+        let length = if is_hour { acfg.hour_tick_length } else { acfg.minute_tick_length };
+        let inner = outer - radius * length * shrink;
+        let tick_width = if is_hour { acfg.hour_tick_width } else { acfg.minute_tick_width };
+        // Dots and diamonds scale relative to the default widths
+        let size_scale = tick_width / if is_hour { 2.5 } else { 1.0 };
 
         match acfg.tick_style {
             TickStyle::Line => {
-                let tick_width = if is_hour { 2.5 } else { 1.0 };
                 let x1 = cx + inner * angle.cos();
                 let y1 = cy + inner * angle.sin();
                 let x2 = cx + outer * angle.cos();
@@ -180,13 +277,13 @@ fn draw_ticks(
                 let mid = (inner + outer) / 2.0;
                 let dot_cx = cx + mid * angle.cos();
                 let dot_cy = cy + mid * angle.sin();
-                let dot_r = if is_hour { 3.0 } else { 1.5 };
+                let dot_r = (if is_hour { 3.0 } else { 1.5 }) * size_scale;
                 canvas.draw_circle(dot_cx, dot_cy, dot_r, theme.tick_color, true, 0.0);
             }
             TickStyle::Diamond => {
                 let mid = (inner + outer) / 2.0;
                 let half_len = (outer - inner) / 2.0;
-                let half_w = if is_hour { 2.5 } else { 1.2 };
+                let half_w = (if is_hour { 2.5 } else { 1.2 }) * size_scale;
                 let cos_a = angle.cos();
                 let sin_a = angle.sin();
                 // Diamond: 4 points along and perpendicular to the radial axis
@@ -209,16 +306,19 @@ fn draw_numerals(
     acfg: &AnalogueConfig,
     contrast: &ContrastInfo,
 ) {
-    let labels: &[&str] = match acfg.numerals {
+    let labels: Vec<&str> = match acfg.numerals {
         NumeralStyle::None => return,
-        NumeralStyle::Arabic => &["12", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11"],
-        NumeralStyle::Roman => &["XII", "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI"],
+        NumeralStyle::Arabic => vec!["12", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11"],
+        NumeralStyle::Roman => vec!["XII", "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI"],
+        NumeralStyle::Quarters => vec!["12", "", "", "3", "", "", "6", "", "", "9", "", ""],
+        NumeralStyle::Custom => acfg.numeral_labels.iter().take(12).map(String::as_str).collect(),
     };
 
     let text_size = radius * acfg.numeral_size;
     let dist = radius * (1.0 - acfg.numeral_inset);
 
     for (i, label) in labels.iter().enumerate() {
+        if label.is_empty() { continue; }
         let angle = (i as f32 * 30.0 - 90.0).to_radians();
         let nx = cx + dist * angle.cos();
         let ny = cy + dist * angle.sin();
@@ -231,6 +331,48 @@ fn draw_numerals(
     }
 }
 
+/// Radius of the innermost tick end, or `tick_outer` when no ticks are drawn.
+fn tick_inner(tick_outer: f32, acfg: &AnalogueConfig) -> f32 {
+    let longest = match acfg.show_ticks {
+        TickVisibility::None => 0.0,
+        TickVisibility::HoursOnly | TickVisibility::QuartersOnly => acfg.hour_tick_length,
+        TickVisibility::All60 => acfg.hour_tick_length.max(acfg.minute_tick_length),
+    };
+    // Tick lengths scale with `tick_outer`, as in `draw_ticks`
+    tick_outer * (1.0 - longest / TICK_OUTER)
+}
+
+/// Small 05..60 labels at 5-minute intervals, just inside the minute track
+/// and the inner end of the hour ticks.
+#[allow(clippy::too_many_arguments)]
+fn draw_minute_numbers(
+    canvas: &mut Canvas,
+    font: &FontState,
+    cx: f32, cy: f32, radius: f32,
+    tick_outer: f32,
+    acfg: &AnalogueConfig,
+    contrast: &ContrastInfo,
+) {
+    let text_size = radius * 0.07;
+    // Inside the innermost tick end and, when shown, the hour numerals
+    let mut edge = tick_inner(tick_outer, acfg);
+    if acfg.numerals != NumeralStyle::None {
+        edge = edge.min(radius * (1.0 - acfg.numeral_inset) - radius * acfg.numeral_size * 0.75);
+    }
+    let dist = edge - text_size * 0.75;
+
+    for i in 1..=12 {
+        let label = format!("{:02}", i * 5);
+        let angle = (i as f32 * 30.0 - 90.0).to_radians();
+        let nx = cx + dist * angle.cos();
+        let ny = cy + dist * angle.sin();
+
+        let (tw, th) = font.measure_text(&label, text_size);
+        draw_contrast_text(font, canvas, &label, nx - tw / 2.0, ny - th / 2.0, text_size, contrast.text_color, contrast);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn draw_hand(
     canvas: &mut Canvas,
     cx: f32, cy: f32,
@@ -247,13 +389,31 @@ fn draw_hand(
         let shadow_color = [0x00, 0x00, 0x00, 0x60];
         let sx = 2.0;
         let sy = 2.0;
-        draw_hand_shape(canvas, cx + sx, cy + sy, cos_a, sin_a, length, width, shadow_color, acfg);
+        draw_hand_shape(canvas, cx + sx, cy + sy, cos_a, sin_a, length, width, shadow_color, acfg, None);
+    }
+
+    // Outline pass: stroke the same shape, then fill over it so only the outer half shows
+    if acfg.hand_outline {
+        let outline_color = acfg.hand_outline_color.unwrap_or_else(|| outline_color_for(color));
+        draw_hand_shape(canvas, cx, cy, cos_a, sin_a, length, width, outline_color, acfg, Some(HAND_OUTLINE_WIDTH * 2.0));
     }
 
     // Main hand
-    draw_hand_shape(canvas, cx, cy, cos_a, sin_a, length, width, color, acfg);
+    draw_hand_shape(canvas, cx, cy, cos_a, sin_a, length, width, color, acfg, None);
+}
+
+/// Visible thickness of the hand outline, in logical pixels.
+const HAND_OUTLINE_WIDTH: f32 = 1.5;
+
+/// Fill a hand polygon, or stroke its edge when `stroke` is set.
+fn hand_polygon(canvas: &mut Canvas, points: &[(f32, f32)], color: [u8; 4], stroke: Option<f32>) {
+    match stroke {
+        Some(w) => canvas.stroke_polygon(points, color, w),
+        None => canvas.fill_polygon(points, color),
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn draw_hand_shape(
     canvas: &mut Canvas,
     cx: f32, cy: f32,
@@ -261,6 +421,7 @@ fn draw_hand_shape(
     length: f32, width: f32,
     color: [u8; 4],
     acfg: &AnalogueConfig,
+    stroke: Option<f32>,
 ) {
     let half_w = width / 2.0;
 
@@ -279,7 +440,7 @@ fn draw_hand_shape(
                 (sx + shaft_half_w * sin_a, sy - shaft_half_w * cos_a),
                 (sx - shaft_half_w * sin_a, sy + shaft_half_w * cos_a),
             ];
-            canvas.fill_polygon(&shaft, color);
+            hand_polygon(canvas, &shaft, color, stroke);
 
             // Arrowhead triangle
             let tip_x = cx + length * cos_a;
@@ -289,7 +450,7 @@ fn draw_hand_shape(
                 (sx - half_w * sin_a, sy + half_w * cos_a),
                 (sx + half_w * sin_a, sy - half_w * cos_a),
             ];
-            canvas.fill_polygon(&arrow, color);
+            hand_polygon(canvas, &arrow, color, stroke);
         }
         HandCap::Round | HandCap::Flat => {
             if acfg.hand_taper > 0.0 {
@@ -303,12 +464,16 @@ fn draw_hand_shape(
                     (tip_x + tip_half_w * sin_a, tip_y - tip_half_w * cos_a),
                     (tip_x - tip_half_w * sin_a, tip_y + tip_half_w * cos_a),
                 ];
-                canvas.fill_polygon(&points, color);
+                hand_polygon(canvas, &points, color, stroke);
             } else {
-                // Simple line (unchanged visual — current default)
-                let x2 = cx + length * cos_a;
-                let y2 = cy + length * sin_a;
-                canvas.draw_line(cx, cy, x2, y2, color, width);
+                // Simple line (unchanged visual — current default).
+                // The outline pass extends past both ends so the tip is edged too.
+                let ext = stroke.map_or(0.0, |w| w / 2.0);
+                let x1 = cx - ext * cos_a;
+                let y1 = cy - ext * sin_a;
+                let x2 = cx + (length + ext) * cos_a;
+                let y2 = cy + (length + ext) * sin_a;
+                canvas.draw_line(x1, y1, x2, y2, color, width + ext * 2.0);
             }
         }
     }
@@ -1,38 +1,154 @@
 use crate::battery::BatteryInfo;
 use crate::canvas::{Canvas, FontState};
-use crate::config::FaceMode;
-use crate::renderer::{ClockState, draw_contrast_text};
+use crate::config::{BatteryOrientation, BatteryStyle, ClockConfig, FaceMode};
+use crate::renderer::{analogue, ClockState, Region, draw_contrast_text};
 
-pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState, battery: &BatteryInfo) {
-    let w = canvas.width() as f32;
-    let config = &state.config;
+/// Geometry of the battery icon in the top-right corner. Both orientations
+/// fit the same `slot_h` row; `x`/`y`/`body_w`/`body_h` are the body outline,
+/// and the nub never extends left of `x`.
+struct BatteryLayout {
+    x: f32,
+    y: f32,
+    body_w: f32,
+    body_h: f32,
+    /// Terminal nub rectangle: x, y, w, h
+    nub: (f32, f32, f32, f32),
+    /// Width of body plus nub
+    total_w: f32,
+    slot_h: f32,
+    border: f32,
+    margin: f32,
+    vertical: bool,
+}
 
-    // Derive icon size from face mode
-    let base = match config.clock.face {
-        FaceMode::Digital => config.clock.font_size,
-        FaceMode::Analogue => config.clock.diameter as f32 * 0.25,
-    };
-    let icon_h = (base * 0.3).max(12.0);
-    let icon_w = icon_h * 1.8;
-    let cap_w = icon_w * 0.08; // battery terminal nub width
-    let cap_h = icon_h * 0.35;
-    let border = (icon_h * 0.08).max(1.5);
-    let margin = base * 0.2;
+impl BatteryLayout {
+    fn new(state: &ClockState, w: f32) -> Self {
+        let config = &state.config;
 
-    // Position: top-right corner
-    let x = w - icon_w - cap_w - margin;
-    let y = margin;
+        // Derive icon size from face mode
+        let base = match config.clock.face {
+            FaceMode::Digital => config.clock.font_size,
+            FaceMode::Analogue => config.clock.diameter as f32 * 0.25,
+        };
+        let slot_h = (base * 0.3).max(12.0);
+        let border = (slot_h * 0.08).max(1.5);
+        let margin = base * 0.2;
+        let y = margin;
 
-    // Color based on charge level
-    let fill_color: [u8; 4] = if battery.percent > 50 {
+        match config.battery.orientation {
+            BatteryOrientation::Horizontal => {
+                let body_w = slot_h * 1.8;
+                let cap_w = body_w * 0.08; // battery terminal nub width
+                let cap_h = slot_h * 0.35;
+                // Position: top-right corner
+                let x = w - body_w - cap_w - margin;
+                Self {
+                    x, y, body_w, body_h: slot_h,
+                    nub: (x + body_w, y + (slot_h - cap_h) / 2.0, cap_w, cap_h),
+                    total_w: body_w + cap_w,
+                    slot_h, border, margin,
+                    vertical: false,
+                }
+            }
+            BatteryOrientation::Vertical => {
+                // The horizontal proportions turned on end, nub included in the slot height
+                let cap_h = slot_h * 0.08;
+                let body_h = slot_h - cap_h;
+                let body_w = body_h / 1.8;
+                let cap_w = body_w * 0.35;
+                let x = w - body_w - margin;
+                Self {
+                    x, y: y + cap_h, body_w, body_h,
+                    nub: (x + (body_w - cap_w) / 2.0, y, cap_w, cap_h),
+                    total_w: body_w,
+                    slot_h, border, margin,
+                    vertical: true,
+                }
+            }
+        }
+    }
+
+    fn text_size(&self) -> f32 {
+        self.slot_h * 0.75
+    }
+}
+
+/// Whether the battery is drawn as a ring around the analogue dial.
+fn uses_ring(state: &ClockState) -> bool {
+    state.config.battery.style == BatteryStyle::Ring
+        && state.config.clock.face == FaceMode::Analogue
+}
+
+/// Centre radius and stroke width of the charge ring on a dial of `radius`,
+/// or `None` when no ring is drawn. The ring takes the place of the ticks'
+/// outer ends, kept inside the bezel; the ticks shrink to fit within it.
+pub fn ring_geometry(state: &ClockState, radius: f32) -> Option<(f32, f32)> {
+    if !uses_ring(state) || !state.config.battery.enabled || state.battery.is_none() {
+        return None;
+    }
+    let acfg = &state.config.analogue;
+    // Inner edge of the bezel stroke (the default bezel is a 2px line)
+    let bezel_half = if acfg.bezel_width > 0.0 { radius * acfg.bezel_width / 2.0 } else { 1.0 };
+    let outer = (radius * analogue::TICK_OUTER).min(radius - bezel_half);
+    let ring_w = (radius * 0.03).max(2.0);
+    Some((outer - ring_w / 2.0, ring_w))
+}
+
+/// Whether `charge_animation` applies: only the icon's fill is animated.
+pub fn animates_charge(config: &ClockConfig) -> bool {
+    config.battery.charge_animation
+        && !(config.battery.style == BatteryStyle::Ring && config.clock.face == FaceMode::Analogue)
+}
+
+/// Fill colour for a charge level.
+fn charge_color(percent: u8) -> [u8; 4] {
+    if percent > 50 {
         [0x4A, 0xDE, 0x80, 0xFF] // green
-    } else if battery.percent > 20 {
+    } else if percent > 20 {
         [0xFB, 0xBF, 0x24, 0xFF] // yellow
     } else {
         [0xEF, 0x44, 0x44, 0xFF] // red
+    }
+}
+
+/// Region covered by the battery icon and its percentage text, or by the
+/// dial in ring mode.
+pub fn region(state: &ClockState, font: &FontState, battery: &BatteryInfo, w: f32, h: f32) -> Region {
+    if uses_ring(state) {
+        let (cx, cy, radius) = analogue::face_geometry(state, w, h);
+        return Region { x: cx - radius, y: cy - radius, w: radius * 2.0, h: radius * 2.0 };
+    }
+
+    let l = BatteryLayout::new(state, w);
+    let text_w = if state.config.battery.show_percentage {
+        let text = format!("{}%", battery.percent);
+        font.measure_text(&text, l.text_size()).0 + l.margin * 0.4
+    } else {
+        0.0
     };
+    Region {
+        x: l.x - text_w,
+        y: l.margin,
+        w: text_w + l.total_w,
+        h: l.slot_h,
+    }
+}
+
+pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState, battery: &BatteryInfo) {
+    if uses_ring(state) {
+        render_ring(canvas, state, battery);
+        return;
+    }
+
+    let w = canvas.width() as f32;
+    let l = BatteryLayout::new(state, w);
+    let (x, y, icon_w, icon_h, border) = (l.x, l.y, l.body_w, l.body_h, l.border);
+
+    // Color based on charge level
+    let fill_color = charge_color(battery.percent);
 
-    let tc = state.contrast.text_color;
+    let contrast = &state.elements.battery;
+    let tc = contrast.text_color;
     let outline_color: [u8; 4] = [tc[0], tc[1], tc[2], 0xCC];
 
     // Draw battery outline
@@ -41,9 +157,9 @@ pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState, battery
     canvas.draw_line(x, y, x, y + icon_h, outline_color, border);
     canvas.draw_line(x + icon_w, y, x + icon_w, y + icon_h, outline_color, border);
 
-    // Terminal nub on right
-    let nub_y = y + (icon_h - cap_h) / 2.0;
-    canvas.fill_rect(x + icon_w, nub_y, cap_w, cap_h, outline_color);
+    // Terminal nub (right, or top when vertical)
+    let (nub_x, nub_y, cap_w, cap_h) = l.nub;
+    canvas.fill_rect(nub_x, nub_y, cap_w, cap_h, outline_color);
 
     // Fill interior based on percentage
     let inner_margin = border + 1.0;
@@ -51,10 +167,35 @@ pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState, battery
     let inner_y = y + inner_margin;
     let inner_w = icon_w - inner_margin * 2.0;
     let inner_h = icon_h - inner_margin * 2.0;
-    let fill_w = inner_w * (battery.percent as f32 / 100.0);
+    let level = battery.percent as f32 / 100.0;
 
-    if fill_w > 0.0 {
-        canvas.fill_rect(inner_x, inner_y, fill_w, inner_h, fill_color);
+    if level > 0.0 {
+        if l.vertical {
+            // Fill from the bottom up
+            let fill_h = inner_h * level;
+            canvas.fill_rect(inner_x, inner_y + inner_h - fill_h, inner_w, fill_h, fill_color);
+        } else {
+            canvas.fill_rect(inner_x, inner_y, inner_w * level, inner_h, fill_color);
+        }
+
+        if battery.charging && animates_charge(&state.config) {
+            // A soft band sweeps along the fill every two seconds
+            let inner_len = if l.vertical { inner_h } else { inner_w };
+            let fill_len = inner_len * level;
+            let phase = ((state.time.second % 2) * 1000 + state.time.millis) as f32 / 2000.0;
+            let band = inner_len * 0.3;
+            let start = (phase * (fill_len + band) - band).max(0.0);
+            let end = (phase * (fill_len + band)).min(fill_len);
+            if end > start {
+                let highlight = [0xFF, 0xFF, 0xFF, 0x55];
+                if l.vertical {
+                    let bottom = inner_y + inner_h;
+                    canvas.fill_rect(inner_x, bottom - end, inner_w, end - start, highlight);
+                } else {
+                    canvas.fill_rect(inner_x + start, inner_y, end - start, inner_h, highlight);
+                }
+            }
+        }
     }
 
     // Lightning bolt if charging
@@ -62,8 +203,11 @@ pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState, battery
         let bolt_color: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
         let cx = x + icon_w / 2.0;
         let cy = y + icon_h / 2.0;
-        let bh = icon_h * 0.35;
-        let bw = icon_w * 0.12;
+        let (bh, bw) = if l.vertical {
+            (icon_h * 0.3, icon_w * 0.3)
+        } else {
+            (icon_h * 0.35, icon_w * 0.12)
+        };
         let stroke = (border * 0.8).max(1.0);
 
         canvas.draw_line(cx + bw * 0.3, cy - bh, cx - bw * 0.5, cy + bh * 0.1, bolt_color, stroke);
@@ -71,14 +215,35 @@ pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState, battery
         canvas.draw_line(cx + bw * 0.5, cy - bh * 0.1, cx - bw * 0.3, cy + bh, bolt_color, stroke);
     }
 
-    // Percentage text to the left of icon
+    // Percentage text to the left of icon, centred on the slot
     if state.config.battery.show_percentage {
         let text = format!("{}%", battery.percent);
-        let font_size = icon_h * 0.75;
+        let font_size = l.text_size();
         let (tw, _th) = font.measure_text(&text, font_size);
-        let text_x = x - tw - margin * 0.4;
-        let text_y = y + (icon_h - font_size) / 2.0;
-        let text_color = state.contrast.text_color;
-        draw_contrast_text(font, canvas, &text, text_x, text_y, font_size, text_color, &state.contrast);
+        let text_x = l.x - tw - l.margin * 0.4;
+        let text_y = l.margin + (l.slot_h - font_size) / 2.0;
+        draw_contrast_text(font, canvas, &text, text_x, text_y, font_size, contrast.text_color, contrast);
+    }
+}
+
+/// Draw the charge as an arc from 12 o'clock just inside the bezel, over a
+/// faint full-circle track.
+fn render_ring(canvas: &mut Canvas, state: &ClockState, battery: &BatteryInfo) {
+    let (cx, cy, radius) = analogue::face_geometry(state, canvas.width() as f32, canvas.height() as f32);
+    let Some((ring_r, ring_w)) = ring_geometry(state, radius) else { return };
+
+    let tc = state.elements.battery.text_color;
+    canvas.draw_circle(cx, cy, ring_r, [tc[0], tc[1], tc[2], 0x33], false, ring_w);
+
+    let fill_color = charge_color(battery.percent);
+    let sweep = 360.0 * battery.percent.min(100) as f32 / 100.0;
+    canvas.draw_arc(cx, cy, ring_r, -90.0, sweep, fill_color, ring_w);
+
+    // While charging, pulse a dot on the arc endpoint every other second
+    if battery.charging && state.time.second.is_multiple_of(2) {
+        let end = (sweep - 90.0).to_radians();
+        let ex = cx + ring_r * end.cos();
+        let ey = cy + ring_r * end.sin();
+        canvas.draw_circle(ex, ey, ring_w * 0.9, [0xFF, 0xFF, 0xFF, 0xDD], true, 0.0);
     }
 }
@@ -1,5 +1,6 @@
 use crate::canvas::{self, Canvas, FontState};
-use crate::renderer::{ClockState, SubclockSizing, draw_contrast_text};
+use crate::config::{AmPmStyle, ClockConfig, DatePosition};
+use crate::renderer::{ClockState, Region, SubclockSizing, compact_factor, draw_contrast_text, place_content};
 
 /// Render the digital clock background: image+scrim or solid fill.
 pub fn render_background(canvas: &mut Canvas, state: &ClockState, _font: &FontState) {
@@ -8,69 +9,227 @@ pub fn render_background(canvas: &mut Canvas, state: &ClockState, _font: &FontSt
     let config = &state.config;
     let theme = &config.theme;
 
-    if !config.background.digital_image.is_empty() {
-        if let Some(img) = canvas::load_image(&config.background.digital_image) {
-            let scaled = canvas::scale_image(&img, canvas.width(), canvas.height(), &config.background.image_scale);
-            canvas.draw_image(&scaled, 0, 0);
-            // Apply scrim
-            canvas.fill_rect(0.0, 0.0, w, h, theme.bg_color);
+    // An animated background supplies its current frame; an SVG is rasterized
+    // at the canvas size; otherwise load the still image and scale it
+    let path = &config.background.digital_image;
+    let mode = &config.background.image_scale;
+    let align = config.background.image_alignment(canvas.scale());
+    let (dev_w, dev_h) = canvas.device_size();
+    let scaled = match &state.background_frame {
+        Some(frame) => Some(canvas::scale_image(frame, dev_w, dev_h, mode, align)),
+        None if canvas::is_svg(path) => canvas::load_svg_scaled(path, dev_w, dev_h, mode, align),
+        None => canvas::load_image(path).map(|img| canvas::scale_image(&img, dev_w, dev_h, mode, align)),
+    };
+    if let Some(scaled) = scaled {
+        canvas.draw_device_image(&scaled, 0, 0);
+        // Apply scrim, optionally fading in from nothing at the top
+        let scrim = config.background.scrim_color(theme.bg_color);
+        if config.background.scrim_gradient {
+            let clear = [scrim[0], scrim[1], scrim[2], 0];
+            canvas.fill_rect_vertical_gradient(0.0, 0.0, w, h, clear, scrim);
         } else {
-            canvas.clear(theme.bg_color);
+            canvas.fill_rect(0.0, 0.0, w, h, scrim);
         }
     } else {
         canvas.clear(theme.bg_color);
     }
 }
 
-/// Render the digital clock foreground: time text, date text.
-pub fn render_foreground(canvas: &mut Canvas, state: &ClockState, font: &FontState) {
-    let w = canvas.width() as f32;
-    let h = canvas.height() as f32;
+/// Positions of the digital time and date text within the canvas.
+pub struct DigitalLayout {
+    pub full_time: String,
+    pub time_size: f32,
+    pub time: Region,
+    pub date_size: f32,
+    pub date: Option<Region>,
+    pub week_size: f32,
+    /// ISO week line ("Wk 32") and where it goes.
+    pub week: Option<(String, Region)>,
+    pub secondary_size: f32,
+    pub secondary: Option<Region>,
+    pub ampm_size: f32,
+    /// AM/PM suffix drawn apart from the time ("small"/"stacked" styles).
+    pub ampm: Option<(&'static str, Region)>,
+}
+
+/// Text size and gap of a separately drawn AM/PM suffix, or `None` when the
+/// suffix is inline (the default style, 24-hour mode, or a custom `time_format`).
+pub fn ampm_sizing(config: &ClockConfig, time_size: f32) -> Option<(f32, f32)> {
+    if config.clock.hour_format != 12 || config.clock.effective_time_format().is_some() {
+        return None;
+    }
+    match config.clock.ampm_style {
+        AmPmStyle::Inline => None,
+        AmPmStyle::Small => Some((time_size * 0.4, time_size * 0.1)),
+        AmPmStyle::Stacked => Some((time_size * 0.3, time_size * 0.05)),
+    }
+}
+
+/// Extra room above and below the text block so the font's ascent over its
+/// first line and descent under its last stay within `top_pad`/`bottom_pad`.
+/// `lines` are the line sizes from top to bottom, zero for hidden lines.
+pub fn text_overflow(font: &FontState, lines: &[f32], top_pad: f32, bottom_pad: f32) -> (f32, f32) {
+    let mut shown = lines.iter().copied().filter(|size| *size > 0.0);
+    let Some(first) = shown.next() else { return (0.0, 0.0) };
+    let last = shown.next_back().unwrap_or(first);
+    let above = font.line_metrics(first).0 - first;
+    let below = font.line_metrics(last).1;
+    ((above - top_pad).max(0.0), (below - bottom_pad).max(0.0))
+}
+
+/// Lay out the time and date text for a canvas of `w`×`h` pixels.
+pub fn layout(state: &ClockState, font: &FontState, w: f32, h: f32) -> DigitalLayout {
     let config = &state.config;
 
     let compact = state.compact;
     let font_size = config.clock.font_size;
-    let time_size = if compact { font_size * 0.7 } else { font_size };
+    let time_size = font_size * compact_factor(config, compact);
+    let pad_x = time_size * 0.4;
     let pad_y = time_size * 0.25;
 
-    // Time text
-    let time_str = state.time.format_time(config.clock.hour_format, config.clock.show_seconds);
-    let suffix = state.time.format_time_suffix(config.clock.hour_format);
-    let full_time = format!("{}{}", time_str, suffix);
+    // Time text; a separately styled AM/PM suffix is left off and drawn on its own
+    let ampm_sizing = ampm_sizing(config, time_size);
+    let full_time = match ampm_sizing {
+        Some(_) => state.time.format_time(config.clock.hour_format, config.clock.show_seconds),
+        None => state.time.display_time(config.clock.hour_format, config.clock.show_seconds),
+    };
+    let (ampm_size, ampm_gap) = ampm_sizing.unwrap_or((0.0, 0.0));
+    let stacked = ampm_sizing.is_some() && config.clock.ampm_style == AmPmStyle::Stacked;
+    let ampm_text = state.time.format_time_suffix(config.clock.hour_format).trim_start();
+    let ampm_w = if ampm_sizing.is_some() { font.measure_text(ampm_text, ampm_size).0 } else { 0.0 };
 
-    // Measure and centre time text
+    // Measure and centre time text, together with a small suffix beside it
     let (tw, _) = font.measure_text(&full_time, time_size);
-    let time_x = (w - tw) / 2.0;
+    let block_w = if ampm_sizing.is_some() && !stacked { tw + ampm_gap + ampm_w } else { tw };
+    let stacked_h = if stacked { ampm_size + ampm_gap } else { 0.0 };
 
     // Date sizing
     let date_size = if config.clock.show_date && !compact { time_size * 0.25 } else { 0.0 };
     let date_gap = if date_size > 0.0 { time_size * 0.15 } else { 0.0 };
+    let date_above = config.clock.date_position == DatePosition::Above;
+    // Space the date takes over the time, and under it before the week line
+    let (date_above_h, date_below_h) = if date_above { (date_size + date_gap, 0.0) } else { (0.0, date_gap + date_size) };
+
+    // Week number line, below the time and date
+    let week_size = if config.clock.show_week && !compact { time_size * 0.2 } else { 0.0 };
+    let week_gap = if week_size > 0.0 { time_size * 0.08 } else { 0.0 };
+
+    // Secondary line, below the week
+    let secondary_text = state.time.secondary.as_deref().filter(|_| !compact);
+    let secondary_size = if secondary_text.is_some() { time_size * 0.22 } else { 0.0 };
+    let secondary_gap = if secondary_size > 0.0 { time_size * 0.08 } else { 0.0 };
 
     // Battery offset
     let battery_h = if config.battery.enabled { time_size * 0.35 } else { 0.0 };
     let battery_gap = if battery_h > 0.0 { pad_y * 0.5 } else { 0.0 };
 
-    // Subclock area height
-    let subclock_h = if !config.timezone.is_empty() {
-        SubclockSizing::from_base(time_size).area_h
-    } else {
-        0.0
-    };
+    // Subclock area height (hidden in compact mode)
+    let subclock_h = SubclockSizing::reserved_height(config, compact);
 
-    // Clock area is total height minus subclock area
-    let clock_area_h = h - subclock_h;
+    // Room for a font whose ascent or descent runs past the padding
+    let lines = [
+        if date_above { date_size } else { 0.0 },
+        if stacked { ampm_size } else { 0.0 },
+        time_size,
+        if date_above { 0.0 } else { date_size },
+        week_size,
+        secondary_size,
+    ];
+    let top_pad = if battery_h > 0.0 { battery_gap } else { pad_y };
+    let (over_top, over_bottom) = text_overflow(font, &lines, top_pad, pad_y);
 
-    // Content height within clock area
-    let content_h = battery_h + battery_gap + time_size + date_gap + date_size;
-    let time_y = (clock_area_h - content_h) / 2.0 + battery_h + battery_gap;
+    // Content block above the subclock area
+    let content_h = battery_h + battery_gap + over_top + stacked_h + time_size + date_gap + date_size
+        + week_gap + week_size + secondary_gap + secondary_size + over_bottom;
+    // The lines share the gravity's horizontal edge (or centre) within the block
+    let date_text = &state.time.date_string;
+    let week_text = (week_size > 0.0).then(|| format!("Wk {}", state.time.iso_week));
+    let date_w = if date_size > 0.0 { font.measure_text(date_text, date_size).0 } else { 0.0 };
+    let week_w = week_text.as_ref().map_or(0.0, |text| font.measure_text(text, week_size).0);
+    let secondary_w = secondary_text.map_or(0.0, |text| font.measure_text(text, secondary_size).0);
+    let content_w = block_w.max(date_w).max(week_w).max(secondary_w);
+    let gravity = config.window.content_gravity();
+    let content = place_content(w, h, subclock_h, content_w, content_h, (pad_x, pad_y), gravity);
+    let line_x = |line_w: f32| content.x + (content.w - line_w) * gravity.0;
+    let text_top = content.y + battery_h + battery_gap + over_top;
+    let time_x = line_x(block_w);
+    let time_y = text_top + date_above_h + stacked_h;
 
-    draw_contrast_text(font, canvas, &full_time, time_x, time_y, time_size, state.contrast.text_color, &state.contrast);
+    // A small suffix shares the time's baseline; a stacked one sits centred above it
+    let ampm = ampm_sizing.map(|_| {
+        let (x, y) = if stacked {
+            (time_x + (tw - ampm_w) / 2.0, time_y - stacked_h)
+        } else {
+            (time_x + tw + ampm_gap, time_y + time_size - ampm_size)
+        };
+        (ampm_text, Region { x, y, w: ampm_w, h: ampm_size })
+    });
 
     // Date string
-    if config.clock.show_date && !compact {
-        let (dw, _) = font.measure_text(&state.time.date_string, date_size);
-        let date_x = (w - dw) / 2.0;
-        let date_y = time_y + time_size + date_gap;
-        draw_contrast_text(font, canvas, &state.time.date_string, date_x, date_y, date_size, state.contrast.text_color, &state.contrast);
+    let date = (date_size > 0.0).then(|| Region {
+        x: line_x(date_w),
+        y: if date_above { text_top } else { time_y + time_size + date_gap },
+        w: date_w,
+        h: date_size,
+    });
+
+    let week = week_text.map(|text| {
+        let region = Region {
+            x: line_x(week_w),
+            y: time_y + time_size + date_below_h + week_gap,
+            w: week_w,
+            h: week_size,
+        };
+        (text, region)
+    });
+
+    let secondary = secondary_text.map(|_| Region {
+        x: line_x(secondary_w),
+        y: time_y + time_size + date_below_h + week_gap + week_size + secondary_gap,
+        w: secondary_w,
+        h: secondary_size,
+    });
+
+    DigitalLayout {
+        full_time,
+        time_size,
+        time: Region { x: time_x, y: time_y, w: tw, h: time_size },
+        date_size,
+        date,
+        week_size,
+        week,
+        secondary_size,
+        secondary,
+        ampm_size,
+        ampm,
+    }
+}
+
+/// Render the digital clock foreground: time, date, week and secondary lines.
+pub fn render_foreground(canvas: &mut Canvas, state: &ClockState, font: &FontState) {
+    let w = canvas.width() as f32;
+    let h = canvas.height() as f32;
+    let layout = layout(state, font, w, h);
+
+    let time_contrast = &state.elements.time;
+    draw_contrast_text(font, canvas, &layout.full_time, layout.time.x, layout.time.y, layout.time_size, time_contrast.text_color, time_contrast);
+
+    if let Some((text, ampm)) = &layout.ampm {
+        draw_contrast_text(font, canvas, text, ampm.x, ampm.y, layout.ampm_size, time_contrast.text_color, time_contrast);
+    }
+
+    if let Some(date) = layout.date {
+        let date_contrast = &state.elements.date;
+        draw_contrast_text(font, canvas, &state.time.date_string, date.x, date.y, layout.date_size, date_contrast.text_color, date_contrast);
+    }
+
+    if let Some((text, week)) = &layout.week {
+        let week_contrast = &state.elements.week;
+        draw_contrast_text(font, canvas, text, week.x, week.y, layout.week_size, week_contrast.text_color, week_contrast);
+    }
+
+    if let (Some(text), Some(line)) = (&state.time.secondary, layout.secondary) {
+        let secondary_contrast = &state.elements.secondary;
+        draw_contrast_text(font, canvas, text, line.x, line.y, layout.secondary_size, secondary_contrast.text_color, secondary_contrast);
     }
 }
@@ -0,0 +1,60 @@
+use crate::canvas::Canvas;
+use crate::renderer::{ClockState, CornerGlyph};
+
+/// How long the padlock stays up after the lock is toggled.
+const FLASH_SECS: f32 = 1.0;
+
+/// Opacity of the padlock `show_lock_indicator` keeps up while locked.
+pub const INDICATOR_ALPHA: f32 = 0.35;
+
+/// A padlock in the bottom-left corner: closed or open, and how opaque.
+#[derive(Debug, Clone, Copy)]
+pub struct LockGlyph {
+    pub locked: bool,
+    pub alpha: f32,
+}
+
+/// Opacity of the padlock `elapsed` after a lock toggle: fading from full
+/// to nothing over a second, then `None`.
+pub fn flash_alpha(elapsed: std::time::Duration) -> Option<f32> {
+    let t = elapsed.as_secs_f32() / FLASH_SECS;
+    (t < 1.0).then_some(1.0 - t)
+}
+
+/// Draw `glyph` in the bottom-left corner, sized like the alarm bell.
+pub fn render(canvas: &mut Canvas, state: &ClockState, glyph: LockGlyph) {
+    let corner = CornerGlyph::new(canvas, state, true);
+    let at = |p| corner.at(p);
+    let size = corner.size;
+
+    let fade = |c: [u8; 4]| [c[0], c[1], c[2], (c[3] as f32 * glyph.alpha.clamp(0.0, 1.0)).round() as u8];
+    let color = fade(state.contrast.text_color);
+    let edge = corner.edge.map(fade);
+
+    // Shackle: an arch over the body, lifted and open on the right when unlocked
+    let lift = if glyph.locked { 0.0 } else { 0.14 };
+    let (cx, cy) = at((0.5, 0.38 - lift));
+    let r = size * 0.2;
+    let (lx, ly) = at((0.3, 0.38 - lift));
+    let (_, body_top) = at((0.0, 0.48));
+    let (rx, _) = at((0.7, 0.0));
+    let shackle_w = (size * 0.1).max(1.0);
+    let draw_shackle = |canvas: &mut Canvas, color: [u8; 4], width: f32| {
+        canvas.draw_arc(cx, cy, r, 180.0, 180.0, color, width);
+        canvas.draw_line(lx, ly, lx, body_top, color, width);
+        if glyph.locked {
+            canvas.draw_line(rx, ly, rx, body_top, color, width);
+        }
+    };
+
+    let body: Vec<(f32, f32)> = [(0.18, 0.48), (0.82, 0.48), (0.82, 0.92), (0.18, 0.92)]
+        .into_iter()
+        .map(at)
+        .collect();
+    if let Some(edge) = edge {
+        draw_shackle(canvas, edge, shackle_w + corner.edge_width());
+        canvas.stroke_polygon(&body, edge, corner.edge_width());
+    }
+    draw_shackle(canvas, color, shackle_w);
+    canvas.fill_polygon(&body, color);
+}
@@ -1,19 +1,184 @@
+pub mod alarm;
 pub mod analogue;
 pub mod battery;
 pub mod digital;
+pub mod lock;
 pub mod subclock;
 
+use std::rc::Rc;
+
+use tiny_skia::Pixmap;
+
 use crate::battery::BatteryInfo;
 use crate::canvas::{Canvas, FontState};
-use crate::config::{ClockConfig, FaceMode};
-use crate::time_utils::ClockTime;
+use crate::config::{AmPmStyle, ClockConfig, DatePosition, FaceMode, SizeMode, SubclockStyle, TextEffect, ThemeConfig, TimezoneEntry};
+use crate::time_utils::{self, ClockTime};
 
 /// Resolved contrast information for text rendering.
+#[derive(Debug, Clone, Copy)]
 pub struct ContrastInfo {
     /// The color to use for text (may differ from theme.fg_color when auto-contrast is active).
     pub text_color: [u8; 4],
-    /// Whether to draw a contrasting outline around text.
-    pub use_outline: bool,
+    /// What to draw behind text: nothing, an outline or a drop shadow.
+    pub effect: TextEffect,
+    /// Shadow offset and colour from the theme, for `TextEffect::Shadow`.
+    pub shadow_offset: (f32, f32),
+    pub shadow_color: [u8; 4],
+    /// Outline radius from `[theme] outline_width`; `None` scales with the text size.
+    pub outline_width: Option<f32>,
+    /// Outline colour from `[theme] outline_color`; `None` contrasts with the text.
+    pub outline_color: Option<[u8; 4]>,
+}
+
+impl ContrastInfo {
+    /// Derive text contrast from the theme and, when auto-contrast sampled it,
+    /// the luminance (0–255) of the background beneath the text.
+    ///
+    /// Light backgrounds (luminance > 140) get dark text. The theme's text effect
+    /// is used, upgraded from none to an outline when the text was darkened for
+    /// a light background (the sample is an average, so darker patches beneath
+    /// it would swallow dark text) or its colour is still too close to the
+    /// background to read on its own.
+    pub fn resolve(theme: &ThemeConfig, background_luminance: Option<f32>) -> Self {
+        let Some(lum) = background_luminance else {
+            return Self {
+                text_color: theme.fg_color,
+                effect: theme.effective_text_effect(),
+                shadow_offset: (theme.text_shadow_dx, theme.text_shadow_dy),
+                shadow_color: theme.text_shadow_color,
+                outline_width: theme.outline_width,
+                outline_color: theme.outline_color,
+            };
+        };
+        let light = lum > 140.0;
+        let text_color = if light {
+            [0x1a, 0x1a, 0x1a, 0xFF] // dark text for light backgrounds
+        } else {
+            theme.fg_color
+        };
+        Self {
+            text_color,
+            effect: match theme.effective_text_effect() {
+                TextEffect::None if light || (color_luminance(text_color) - lum).abs() < 96.0 => TextEffect::Outline,
+                effect => effect,
+            },
+            shadow_offset: (theme.text_shadow_dx, theme.text_shadow_dy),
+            shadow_color: theme.text_shadow_color,
+            outline_width: theme.outline_width,
+            outline_color: theme.outline_color,
+        }
+    }
+}
+
+/// Whether auto-contrast sampling applies for the given theme.
+/// `"auto"` only samples when a gallery is configured.
+pub fn auto_contrast_active(theme: &ThemeConfig, has_gallery: bool) -> bool {
+    match theme.auto_contrast.as_str() {
+        "always" => true,
+        "never" => false,
+        _ /* "auto" */ => has_gallery,
+    }
+}
+
+/// Sample the background under `region` and resolve contrast for text drawn there.
+pub fn sample_contrast(canvas: &Canvas, region: Region, theme: &ThemeConfig) -> ContrastInfo {
+    let lum = crate::canvas::sample_region_luminance(
+        canvas,
+        region.x.max(0.0) as u32,
+        region.y.max(0.0) as u32,
+        region.w.max(1.0).ceil() as u32,
+        region.h.max(1.0).ceil() as u32,
+    );
+    ContrastInfo::resolve(theme, Some(lum))
+}
+
+/// Sample the background under every text element: returns the whole-canvas
+/// contrast and the per-element contrast for the foreground pass.
+pub fn sample_element_contrast(canvas: &Canvas, state: &ClockState, font: &FontState, width: u32, height: u32) -> (ContrastInfo, ElementContrast) {
+    let theme = &state.config.theme;
+    let whole = Region { x: 0.0, y: 0.0, w: width as f32, h: height as f32 };
+    let global = sample_contrast(canvas, whole, theme);
+    let regions = text_regions(state, font, width, height);
+    let sample = |region: Option<Region>| {
+        region.map_or(global, |r| sample_contrast(canvas, r, theme))
+    };
+    let elements = ElementContrast {
+        time: sample(regions.time),
+        date: sample(regions.date),
+        week: sample(regions.week),
+        secondary: sample(regions.secondary),
+        subclocks: regions.subclocks.into_iter().map(|r| sample(Some(r))).collect(),
+        battery: sample(regions.battery),
+    };
+    (global, elements)
+}
+
+/// Contrast resolved separately for each text element, so text over a busy
+/// background adapts to the pixels directly beneath it.
+#[derive(Debug, Clone)]
+pub struct ElementContrast {
+    pub time: ContrastInfo,
+    pub date: ContrastInfo,
+    pub week: ContrastInfo,
+    pub secondary: ContrastInfo,
+    pub subclocks: Vec<ContrastInfo>,
+    pub battery: ContrastInfo,
+}
+
+impl ElementContrast {
+    /// Use the same contrast for every element.
+    pub fn uniform(contrast: ContrastInfo) -> Self {
+        Self {
+            time: contrast,
+            date: contrast,
+            week: contrast,
+            secondary: contrast,
+            subclocks: Vec::new(),
+            battery: contrast,
+        }
+    }
+
+    /// Contrast for the sub-clock at `index`, falling back to the time contrast.
+    pub fn subclock(&self, index: usize) -> &ContrastInfo {
+        self.subclocks.get(index).unwrap_or(&self.time)
+    }
+}
+
+/// An axis-aligned rectangle in canvas pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+/// Place a face's main content (`content_w`×`content_h`) in a `w`×`h` window
+/// whose bottom `reserved_h` pixels hold the sub-clocks. `gravity` (see
+/// `WindowConfig::content_gravity`) shares out the space the content and its
+/// `pad` (x, y) leave free, centring it by default. Vertically that is the
+/// space in the window as a whole, but the content stays at least `pad` above
+/// the sub-clocks; in a window too short for that it is placed in the space
+/// above them, which is where it sits at its natural size.
+pub fn place_content(w: f32, h: f32, reserved_h: f32, content_w: f32, content_h: f32, pad: (f32, f32), gravity: (f32, f32)) -> Region {
+    let (pad_x, pad_y) = pad;
+    let (gx, gy) = gravity;
+    let area_h = h - reserved_h;
+    let in_area = pad_y + (area_h - content_h - pad_y * 2.0) * gy;
+    let lowest = area_h - content_h - pad_y;
+    let y = (pad_y + (h - content_h - pad_y * 2.0) * gy).min(lowest).max(in_area);
+    let x = pad_x + (w - content_w - pad_x * 2.0) * gx;
+    Region { x, y, w: content_w, h: content_h }
+}
+
+/// Canvas regions occupied by each text element, used for luminance sampling.
+pub struct TextRegions {
+    pub time: Option<Region>,
+    pub date: Option<Region>,
+    pub week: Option<Region>,
+    pub secondary: Option<Region>,
+    pub subclocks: Vec<Region>,
+    pub battery: Option<Region>,
 }
 
 pub struct ClockState {
@@ -22,28 +187,84 @@ pub struct ClockState {
     pub compact: bool,
     pub battery: Option<BatteryInfo>,
     pub contrast: ContrastInfo,
+    pub elements: ElementContrast,
+    /// Current frame of an animated digital background, if one is playing.
+    pub background_frame: Option<Rc<Pixmap>>,
+    /// Whether an alarm is set, shown as a bell in the corner.
+    pub alarm_pending: bool,
+    /// Padlock to show in the bottom-left corner, e.g. confirming a lock toggle.
+    pub lock_glyph: Option<lock::LockGlyph>,
 }
 
 /// Draw text, optionally with a contrasting outline based on ContrastInfo.
+#[allow(clippy::too_many_arguments)]
 pub fn draw_contrast_text(font: &FontState, canvas: &mut Canvas, text: &str, x: f32, y: f32, size: f32, color: [u8; 4], contrast: &ContrastInfo) {
-    if contrast.use_outline {
-        let outline = outline_color_for(color);
-        font.draw_text_outlined(canvas, text, x, y, size, color, outline);
-    } else {
-        font.draw_text(canvas, text, x, y, size, color);
+    match contrast.effect {
+        TextEffect::Outline => {
+            let outline = contrast.outline_color.unwrap_or_else(|| outline_color_for(color));
+            font.draw_text_outlined(canvas, text, x, y, size, color, outline, contrast.outline_width);
+        }
+        TextEffect::Shadow => {
+            font.draw_text_shadowed(canvas, text, x, y, size, color, contrast.shadow_color, contrast.shadow_offset);
+        }
+        TextEffect::None => font.draw_text(canvas, text, x, y, size, color),
     }
 }
 
+/// Perceptual luminance (0–255) of an RGB colour.
+fn color_luminance(color: [u8; 4]) -> f32 {
+    crate::canvas::perceived_luminance(color)
+}
+
 /// Pick a contrasting outline color: dark outline for light text, light for dark.
-fn outline_color_for(color: [u8; 4]) -> [u8; 4] {
-    let lum = 0.2126 * color[0] as f32 + 0.7152 * color[1] as f32 + 0.0722 * color[2] as f32;
-    if lum > 128.0 {
+pub fn outline_color_for(color: [u8; 4]) -> [u8; 4] {
+    if color_luminance(color) > 128.0 {
         [0x00, 0x00, 0x00, color[3]]
     } else {
         [0xFF, 0xFF, 0xFF, color[3]]
     }
 }
 
+/// Placement of a small status glyph (the alarm bell or padlock) in a left
+/// corner. It scales with the time text or dial, compact mode included, and
+/// stays inside the padding left of the content.
+pub struct CornerGlyph {
+    /// Side of the glyph's square
+    pub size: f32,
+    /// Top-left of the glyph's square
+    pub origin: (f32, f32),
+    /// Outline colour while the theme outlines or shadows text, keeping the
+    /// glyph readable the same way the text is
+    pub edge: Option<[u8; 4]>,
+}
+
+impl CornerGlyph {
+    /// The glyph's square in the top-left corner, or the bottom-left one.
+    pub fn new(canvas: &Canvas, state: &ClockState, bottom: bool) -> Self {
+        let config = &state.config;
+        let base = match config.clock.face {
+            FaceMode::Digital => config.clock.font_size,
+            FaceMode::Analogue => config.clock.diameter as f32 * 0.25,
+        } * compact_factor(config, state.compact);
+        let size = (base * 0.2).max(8.0);
+        let y = if bottom { canvas.height() as f32 - base * 0.1 - size } else { base * 0.1 };
+        let contrast = &state.contrast;
+        let edge = (contrast.effect != TextEffect::None)
+            .then(|| contrast.outline_color.unwrap_or_else(|| outline_color_for(contrast.text_color)));
+        Self { size, origin: (base * 0.1, y), edge }
+    }
+
+    /// A point in the glyph's unit square, in canvas coordinates.
+    pub fn at(&self, (px, py): (f32, f32)) -> (f32, f32) {
+        (self.origin.0 + px * self.size, self.origin.1 + py * self.size)
+    }
+
+    /// Stroke width of the edge.
+    pub fn edge_width(&self) -> f32 {
+        (self.size * 0.1).max(1.0)
+    }
+}
+
 /// Shared sizing constants for subclock text, eliminating duplication across renderers.
 #[allow(dead_code)]
 pub struct SubclockSizing {
@@ -52,6 +273,10 @@ pub struct SubclockSizing {
     pub row_h: f32,
     pub sep_gap: f32,
     pub area_h: f32,
+    /// Diameter of a mini analogue dial (zero for digital sub-clocks).
+    pub face_size: f32,
+    /// Size of the date line under the sub-clock (zero when it is off).
+    pub date_size: f32,
 }
 
 impl SubclockSizing {
@@ -63,101 +288,324 @@ impl SubclockSizing {
         let row_h = label_size + time_size + label_size * 0.1;
         let sep_gap = pad_y * 0.5;
         let area_h = sep_gap + row_h + sep_gap;
-        Self { label_size, time_size, row_h, sep_gap, area_h }
+        Self { label_size, time_size, row_h, sep_gap, area_h, face_size: 0.0, date_size: 0.0 }
+    }
+
+    /// Sizing for the configured sub-clock style. Analogue sub-clocks reserve a
+    /// square cell for the dial with the label below it. The date line, when
+    /// shown, closes the row either way.
+    pub fn for_config(config: &ClockConfig, base: f32) -> Self {
+        let sz = Self::from_base(base);
+        let sz = match config.clock.subclock_style {
+            SubclockStyle::Digital => sz,
+            SubclockStyle::Analogue => {
+                let face_size = sz.time_size * 2.0;
+                let row_h = face_size + sz.label_size * 1.3;
+                Self { row_h, area_h: sz.sep_gap * 2.0 + row_h, face_size, ..sz }
+            }
+        };
+        if !config.clock.subclock_show_date {
+            return sz;
+        }
+        let date_size = (sz.label_size * 0.85).max(10.0);
+        let row_h = sz.row_h + date_size * 1.1;
+        Self { row_h, area_h: sz.sep_gap * 2.0 + row_h, date_size, ..sz }
+    }
+
+    /// Sizing for one entry: a `primary` sub-clock is a quarter larger.
+    pub fn for_entry(config: &ClockConfig, base: f32, tz: &TimezoneEntry) -> Self {
+        Self::for_config(config, if tz.primary { base * 1.25 } else { base })
+    }
+
+    /// The base size sub-clocks scale from: the digital time size (shrunk in
+    /// compact mode), or a quarter of the analogue diameter, times `subclock_scale`.
+    pub fn base(config: &ClockConfig, compact: bool) -> f32 {
+        let base = match config.clock.face {
+            FaceMode::Digital => config.clock.font_size * compact_factor(config, compact),
+            FaceMode::Analogue => config.clock.diameter as f32 * 0.25,
+        };
+        base * config.clock.subclock_scale.max(0.01)
+    }
+
+    /// Height reserved at the bottom of the window for sub-clocks: one row side
+    /// by side on the digital face (as tall as its tallest entry), a row each on
+    /// the analogue face, nothing in compact mode. Sizing, layout and drawing
+    /// all use this.
+    pub fn reserved_height(config: &ClockConfig, compact: bool) -> f32 {
+        let shown = &config.timezone[..config.timezone.len().min(2)];
+        if compact || shown.is_empty() {
+            return 0.0;
+        }
+        let base = Self::base(config, compact);
+        let areas = shown.iter().map(|tz| Self::for_entry(config, base, tz).area_h);
+        match config.clock.face {
+            FaceMode::Digital => areas.fold(0.0, f32::max),
+            FaceMode::Analogue => areas.sum(),
+        }
+    }
+}
+
+/// Compute the window dimensions. In content size mode this is the measured
+/// size of the clock; in output mode it is `output_fraction` of `output` (the
+/// output's logical size), falling back to the content size while unknown.
+/// Either way it stays within `max_width`/`max_height` and the output: a clock
+/// too large for them is measured again at the scale that fits (see
+/// `render_scale`).
+pub fn compute_size(config: &ClockConfig, font: &FontState, compact: bool, output: Option<(u32, u32)>) -> (u32, u32) {
+    let (max_w, max_h) = size_limit(config, output);
+    match (config.window.size_mode, output) {
+        (SizeMode::Output, Some((out_w, out_h))) if out_w > 0 && out_h > 0 => {
+            let fraction = config.window.output_fraction.clamp(0.01, 1.0);
+            (
+                ((out_w as f32 * fraction).round() as u32).clamp(1, max_w),
+                ((out_h as f32 * fraction).round() as u32).clamp(1, max_h),
+            )
+        }
+        _ => {
+            let (w, h) = content_size(config, font, compact);
+            if w <= max_w && h <= max_h {
+                return (w, h);
+            }
+            // Shrink the clock into the limit, then fit the window to what is left
+            let scale = content_scale(config, font, compact, w.min(max_w), h.min(max_h));
+            let mut scaled = config.clone();
+            scale_content(&mut scaled, config, scale);
+            let (w, h) = content_size(&scaled, font, compact);
+            (w.min(max_w), h.min(max_h))
+        }
+    }
+}
+
+/// The largest window `[window] max_width`/`max_height` and the output allow,
+/// `u32::MAX` on an axis without a limit.
+fn size_limit(config: &ClockConfig, output: Option<(u32, u32)>) -> (u32, u32) {
+    let limit = |max: u32, out: Option<u32>| {
+        let max = if max > 0 { max } else { u32::MAX };
+        out.filter(|o| *o > 0).map_or(max, |o| max.min(o))
+    };
+    (
+        limit(config.window.max_width, output.map(|(w, _)| w)),
+        limit(config.window.max_height, output.map(|(_, h)| h)),
+    )
+}
+
+/// Factor the renderers scale `font_size` and `diameter` by in a `width`×`height`
+/// window: the one that fills it in output size mode. In content mode the clock
+/// keeps its size (1.0) unless the window is too small for it, as when
+/// `compute_size` limited it, and then it shrinks to fit.
+pub fn render_scale(config: &ClockConfig, font: &FontState, compact: bool, width: u32, height: u32) -> f32 {
+    match config.window.size_mode {
+        SizeMode::Output => content_scale(config, font, compact, width, height),
+        SizeMode::Content => {
+            let (w, h) = content_size(config, font, compact);
+            if w <= width && h <= height {
+                1.0
+            } else {
+                content_scale(config, font, compact, width, height).min(1.0)
+            }
+        }
     }
 }
 
-/// Compute the required window dimensions based on config, font, and compact state.
-pub fn compute_size(config: &ClockConfig, font: &FontState, compact: bool) -> (u32, u32) {
+/// Size the clock needs at its configured `font_size` and `diameter`.
+fn content_size(config: &ClockConfig, font: &FontState, compact: bool) -> (u32, u32) {
     match config.clock.face {
         FaceMode::Digital => compute_digital_size(config, font, compact),
         FaceMode::Analogue => compute_analogue_size(config, font, compact),
     }
 }
 
-fn compute_digital_size(config: &ClockConfig, font: &FontState, compact: bool) -> (u32, u32) {
+/// Factor for `font_size` and `diameter` that makes the content fill a
+/// `width`×`height` window without overflowing it. Padding and minimum sizes
+/// don't scale linearly, so the estimate is refined a few times.
+pub fn content_scale(config: &ClockConfig, font: &FontState, compact: bool, width: u32, height: u32) -> f32 {
+    let mut scale = 1.0;
+    let mut scaled = config.clone();
+    for _ in 0..4 {
+        let (w, h) = content_size(&scaled, font, compact);
+        let step = (width as f32 / w.max(1) as f32).min(height as f32 / h.max(1) as f32);
+        scale *= step;
+        scale_content(&mut scaled, config, scale);
+        if (step - 1.0).abs() < 0.01 {
+            break;
+        }
+    }
+    scale
+}
+
+/// Set `config`'s font size and diameter to those of `base` times `scale`.
+pub fn scale_content(config: &mut ClockConfig, base: &ClockConfig, scale: f32) {
+    config.clock.font_size = (base.clock.font_size * scale).max(1.0);
+    config.clock.diameter = ((base.clock.diameter as f32 * scale).round() as u32).max(1);
+}
+
+/// How much the main clock (digital time or analogue dial) shrinks: the
+/// configured compact scale in compact mode, otherwise 1.
+pub fn compact_factor(config: &ClockConfig, compact: bool) -> f32 {
+    if compact { config.window.compact_scale(config.clock.face) } else { 1.0 }
+}
+
+pub fn compute_digital_size(config: &ClockConfig, font: &FontState, compact: bool) -> (u32, u32) {
     let font_size = config.clock.font_size;
-    let time_size = if compact { font_size * 0.7 } else { font_size };
+    let time_size = font_size * compact_factor(config, compact);
     let pad_x = time_size * 0.4;
     let pad_y = time_size * 0.25;
 
     // Measure widest possible time string to avoid width jitter
-    let widest_time = widest_time_string(config);
-    let (time_w, _) = font.measure_text(&widest_time, time_size);
+    // A custom format is measured on a worst-case sample (long names, two-digit fields)
+    let ampm = digital::ampm_sizing(config, time_size);
+    let widest_time = match config.clock.effective_time_format() {
+        Some(fmt) => time_utils::sample_wide_time(fmt, config.clock.locale()),
+        None => widest_time_string(config, config.clock.show_seconds, ampm.is_none()),
+    };
+    let (mut time_w, _) = font.measure_text(&widest_time, time_size);
+
+    // A separately drawn AM/PM widens the line (small) or adds a row above it (stacked)
+    let mut ampm_h = 0.0;
+    if let Some((size, gap)) = ampm {
+        let ampm_w = font.measure_text("AM", size).0.max(font.measure_text("PM", size).0);
+        if config.clock.ampm_style == AmPmStyle::Stacked {
+            time_w = time_w.max(ampm_w);
+            ampm_h = size + gap;
+        } else {
+            time_w += gap + ampm_w;
+        }
+    }
 
     // Date
     let date_size = if config.clock.show_date && !compact { time_size * 0.25 } else { 0.0 };
     let date_w = if date_size > 0.0 {
-        let sample = chrono::Local::now().format(&config.clock.date_format).to_string();
+        let sample = chrono::Local::now().format_localized(&config.clock.date_format, config.clock.locale()).to_string();
         font.measure_text(&sample, date_size).0
     } else {
         0.0
     };
+    // The date takes the same height whether it goes above or below the time
     let date_gap = if date_size > 0.0 { time_size * 0.15 } else { 0.0 };
 
+    // Week number line
+    let week_size = if config.clock.show_week && !compact { time_size * 0.2 } else { 0.0 };
+    let week_w = if week_size > 0.0 { font.measure_text("Wk 53", week_size).0 } else { 0.0 };
+    let week_gap = if week_size > 0.0 { time_size * 0.08 } else { 0.0 };
+
+    // Secondary line
+    let secondary_fmt = config.clock.effective_secondary_line().filter(|_| !compact);
+    let secondary_size = if secondary_fmt.is_some() { time_size * 0.22 } else { 0.0 };
+    let secondary_w = match secondary_fmt {
+        Some(fmt) => font.measure_text(&time_utils::sample_wide_time(fmt, config.clock.locale()), secondary_size).0,
+        None => 0.0,
+    };
+    let secondary_gap = if secondary_size > 0.0 { time_size * 0.08 } else { 0.0 };
+
     // Battery
     let battery_h = if config.battery.enabled { time_size * 0.35 } else { 0.0 };
     let battery_gap = if battery_h > 0.0 { pad_y * 0.5 } else { 0.0 };
 
     // Subclocks
-    let (subclock_w, subclock_h) = compute_subclock_size(config, font, time_size, pad_y, compact);
+    let (subclock_w, subclock_h) = compute_subclock_size(config, font, compact);
 
-    let width = time_w.max(date_w).max(subclock_w) + pad_x * 2.0;
-    let height = pad_y + battery_h + battery_gap + time_size + date_gap + date_size + subclock_h + pad_y;
+    // Fonts with tall ascenders or deep descenders get extra room, as in the layout
+    let date_above = config.clock.date_position == DatePosition::Above;
+    let lines = [
+        if date_above { date_size } else { 0.0 },
+        if ampm_h > 0.0 { ampm.map_or(0.0, |(size, _)| size) } else { 0.0 },
+        time_size,
+        if date_above { 0.0 } else { date_size },
+        week_size,
+        secondary_size,
+    ];
+    let top_pad = if battery_h > 0.0 { battery_gap } else { pad_y };
+    let (over_top, over_bottom) = digital::text_overflow(font, &lines, top_pad, pad_y);
+
+    let width = time_w.max(date_w).max(week_w).max(secondary_w).max(subclock_w) + pad_x * 2.0;
+    let height = pad_y + battery_h + battery_gap + over_top + ampm_h + time_size + date_gap + date_size
+        + week_gap + week_size + secondary_gap + secondary_size + over_bottom + subclock_h + pad_y;
 
     (width.ceil() as u32, height.ceil() as u32)
 }
 
 fn compute_analogue_size(config: &ClockConfig, font: &FontState, compact: bool) -> (u32, u32) {
     let diameter = config.clock.diameter as f32;
-    let effective = if compact { diameter * 0.75 } else { diameter };
-    let pad = 12.0;
-
-    let base = diameter * 0.25;
-    let pad_y = base * 0.25;
-    let (subclock_w, subclock_h) = compute_subclock_size(config, font, base, pad_y, compact);
+    let effective = diameter * compact_factor(config, compact);
+    let (subclock_w, subclock_h) = compute_subclock_size(config, font, compact);
 
+    let pad = config.analogue.padding.max(0.0);
     let width = effective.max(subclock_w) + pad * 2.0;
     let height = effective + subclock_h + pad * 2.0;
 
     (width.ceil() as u32, height.ceil() as u32)
 }
 
-fn compute_subclock_size(config: &ClockConfig, font: &FontState, base: f32, _pad_y: f32, compact: bool) -> (f32, f32) {
-    if compact { return (0.0, 0.0); }
-    let tz_count = config.timezone.len().min(2);
-    if tz_count == 0 {
+pub fn compute_subclock_size(config: &ClockConfig, font: &FontState, compact: bool) -> (f32, f32) {
+    let subclock_h = SubclockSizing::reserved_height(config, compact);
+    if subclock_h == 0.0 {
         return (0.0, 0.0);
     }
 
-    let sz = SubclockSizing::from_base(base);
+    let tz_count = config.timezone.len().min(2);
+    let base = SubclockSizing::base(config, compact);
+    let widest_time = widest_time_string(config, config.clock.subclock_seconds(), true);
+    let widest_date = config.clock.subclock_show_date
+        .then(|| time_utils::sample_wide_time(subclock::DATE_FORMAT, config.clock.locale()));
 
-    // Measure widest subclock column
-    let widest_sc_time = widest_time_string(config);
-    let (sc_time_w, _) = font.measure_text(&widest_sc_time, sz.time_size);
-    // Also consider label widths
-    let max_label_w = config.timezone.iter().take(2)
-        .map(|tz| font.measure_text(&tz.label, sz.label_size).0)
-        .fold(0.0f32, f32::max);
-    let sc_col_w = sc_time_w.max(max_label_w) + base * 0.2;
+    // Widest subclock column, including its label (a mini dial needs a square
+    // cell); columns share the width equally, so each gets the widest one's
+    let sc_col_w = config.timezone.iter().take(2)
+        .map(|tz| {
+            let sz = SubclockSizing::for_entry(config, base, tz);
+            let time_w = match config.clock.subclock_style {
+                SubclockStyle::Digital => font.measure_text(&widest_time, sz.time_size).0,
+                SubclockStyle::Analogue => sz.face_size,
+            };
+            let date_w = widest_date.as_ref().map_or(0.0, |date| font.measure_text(date, sz.date_size).0);
+            time_w.max(date_w).max(font.measure_text(&tz.label, sz.label_size).0)
+        })
+        .fold(0.0f32, f32::max) + base * 0.2;
 
     // Analogue full mode: stack vertically (one per row)
     let stacked = matches!(config.clock.face, FaceMode::Analogue);
     if stacked {
-        let subclock_h = sz.area_h * tz_count as f32;
         (sc_col_w, subclock_h)
     } else {
-        let subclock_w = sc_col_w * tz_count as f32;
-        (subclock_w, sz.area_h)
+        (sc_col_w * tz_count as f32, subclock_h)
     }
 }
 
-fn widest_time_string(config: &ClockConfig) -> String {
-    let time_part = if config.clock.show_seconds { "00:00:00" } else { "00:00" };
-    let suffix = if config.clock.hour_format == 12 { " PM" } else { "" };
+/// The widest default time line; `inline_suffix` adds the 12-hour " PM".
+fn widest_time_string(config: &ClockConfig, show_seconds: bool, inline_suffix: bool) -> String {
+    let time_part = if show_seconds { "00:00:00" } else { "00:00" };
+    let suffix = if config.clock.hour_format == 12 && inline_suffix { " PM" } else { "" };
     format!("{}{}", time_part, suffix)
 }
 
+/// Compute where each text element will be drawn on a canvas of the given size.
+pub fn text_regions(state: &ClockState, font: &FontState, width: u32, height: u32) -> TextRegions {
+    let w = width as f32;
+    let h = height as f32;
+    let (time, date, week, secondary) = match state.config.clock.face {
+        FaceMode::Digital => {
+            let layout = digital::layout(state, font, w, h);
+            (
+                Some(layout.time),
+                layout.date,
+                layout.week.map(|(_, region)| region),
+                layout.secondary,
+            )
+        }
+        FaceMode::Analogue => {
+            let readout = analogue::readout_layout(state, font, w, h).map(|(_, _, region)| region);
+            (readout, None, None, None)
+        }
+    };
+    let subclocks = subclock::cells(state, w, h);
+    let battery = if state.config.battery.enabled {
+        state.battery.as_ref().map(|info| battery::region(state, font, info, w, h))
+    } else {
+        None
+    };
+    TextRegions { time, date, week, secondary, subclocks, battery }
+}
+
 /// Render just the background layer (image/solid fill, face).
 pub fn render_background(canvas: &mut Canvas, state: &ClockState, font: &FontState) {
     match state.config.clock.face {
@@ -184,4 +632,61 @@ pub fn render_foreground(canvas: &mut Canvas, state: &ClockState, font: &FontSta
     if !state.config.timezone.is_empty() {
         subclock::render(canvas, state, font);
     }
+
+    if state.alarm_pending {
+        alarm::render(canvas, state);
+    }
+    if let Some(glyph) = state.lock_glyph {
+        lock::render(canvas, state, glyph);
+    }
+
+    // Hour flash over everything drawn so far
+    if let Some(strength) = hour_flash_strength(&state.config, &state.time) {
+        canvas.brighten(strength);
+    }
+}
+
+/// How strongly an alarm that went off `elapsed` ago brightens the clock:
+/// three one-second pulses, like the hour flash, then `None`.
+pub fn alarm_flash_strength(elapsed: std::time::Duration) -> Option<f32> {
+    let t = elapsed.as_secs_f32();
+    (t < 3.0).then(|| 0.6 * (1.0 - t.fract()))
+}
+
+/// How strongly the hour flash brightens the clock at `time`: fading from
+/// 60% to nothing over the first second of the hour, `None` at other times.
+pub fn hour_flash_strength(config: &ClockConfig, time: &ClockTime) -> Option<f32> {
+    if !config.clock.hour_flash || time.minute != 0 || time.second != 0 {
+        return None;
+    }
+    Some(0.6 * (1.0 - time.millis.min(999) as f32 / 1000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain_theme(fg_color: [u8; 4]) -> ThemeConfig {
+        ThemeConfig { fg_color, text_outline: false, ..ThemeConfig::default() }
+    }
+
+    #[test]
+    fn dark_text_on_light_background_is_outlined() {
+        let contrast = ContrastInfo::resolve(&plain_theme([0x20, 0x20, 0x20, 0xFF]), Some(200.0));
+        assert_eq!(contrast.text_color, [0x1a, 0x1a, 0x1a, 0xFF]);
+        assert_eq!(contrast.effect, TextEffect::Outline);
+    }
+
+    #[test]
+    fn light_text_on_dark_background_keeps_theme() {
+        let contrast = ContrastInfo::resolve(&plain_theme([0xFF, 0xFF, 0xFF, 0xFF]), Some(20.0));
+        assert_eq!(contrast.text_color, [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(contrast.effect, TextEffect::None);
+    }
+
+    #[test]
+    fn unsampled_uses_theme_effect() {
+        let contrast = ContrastInfo::resolve(&plain_theme([0x20, 0x20, 0x20, 0xFF]), None);
+        assert_eq!(contrast.effect, TextEffect::None);
+    }
 }
@@ -1,68 +1,287 @@
 use crate::canvas::{Canvas, FontState};
-use crate::config::FaceMode;
-use crate::renderer::{ClockState, SubclockSizing, draw_contrast_text};
+use crate::config::{FaceMode, SubclockSeparator, SubclockStyle, TimezoneEntry};
+use crate::renderer::{ClockState, ContrastInfo, Region, SubclockSizing, draw_contrast_text};
 use crate::time_utils;
 
-pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState) {
-    if state.compact { return; }
+/// Muted red for the label and time of a sub-clock whose timezone doesn't parse.
+const ERROR_COLOR: [u8; 4] = [0xE0, 0x6C, 0x6C, 0xCC];
+
+/// Format of the date line under each sub-clock (`subclock_show_date`).
+pub const DATE_FORMAT: &str = "%a %d %b";
+
+/// Compute the cell occupied by each subclock (empty in compact mode).
+pub fn cells(state: &ClockState, w: f32, h: f32) -> Vec<Region> {
+    let total_area_h = SubclockSizing::reserved_height(&state.config, state.compact);
+    if total_area_h == 0.0 { return Vec::new(); }
+
+    let config = &state.config;
+    let tz_count = config.timezone.len().min(2);
+    let base = SubclockSizing::base(config, state.compact);
+    let stacked = matches!(config.clock.face, FaceMode::Analogue);
+    let tz_y_start = h - total_area_h;
 
+    let mut row_y = tz_y_start;
+    config.timezone.iter().take(tz_count).enumerate()
+        .map(|(i, tz)| {
+            if stacked {
+                // Stacked: each subclock gets its own full-width row
+                let area_h = SubclockSizing::for_entry(config, base, tz).area_h;
+                let cell = Region { x: 0.0, y: row_y, w, h: area_h };
+                row_y += area_h;
+                cell
+            } else {
+                // Side-by-side columns, all as tall as the area
+                let col_w = w / tz_count as f32;
+                Region { x: col_w * i as f32, y: tz_y_start, w: col_w, h: total_area_h }
+            }
+        })
+        .collect()
+}
+
+pub fn render(canvas: &mut Canvas, state: &ClockState, font: &FontState) {
     let w = canvas.width() as f32;
-    let h = canvas.height() as f32;
     let config = &state.config;
 
-    let timezones: Vec<_> = config.timezone.iter().take(2).collect();
-    if timezones.is_empty() { return; }
+    let cells = cells(state, w, canvas.height() as f32);
+    let Some(first) = cells.first() else { return };
+
+    let base = SubclockSizing::base(config, state.compact);
+
+    // Draw separator rule
+    let tc = state.contrast.text_color;
+    let sep_color = config.clock.subclock_separator_color.unwrap_or([tc[0], tc[1], tc[2], 0x66]);
+    let (x1, x2) = (w * 0.05, w * 0.95);
+    match config.clock.subclock_separator {
+        SubclockSeparator::Line => canvas.draw_line(x1, first.y, x2, first.y, sep_color, 1.0),
+        SubclockSeparator::Dots => {
+            let step = 4.0;
+            let count = ((x2 - x1) / step).floor() as usize;
+            // Centre the run of dots on the span
+            let start = (w - count as f32 * step) / 2.0;
+            for i in 0..=count {
+                canvas.draw_circle(start + i as f32 * step, first.y, 0.8, sep_color, true, 0.0);
+            }
+        }
+        SubclockSeparator::None => {}
+    }
 
-    // Derive base size from face mode
-    let base = match config.clock.face {
-        FaceMode::Digital => {
-            let font_size = config.clock.font_size;
-            if state.compact { font_size * 0.7 } else { font_size }
+    for (i, (tz, cell)) in config.timezone.iter().zip(&cells).enumerate() {
+        let contrast = state.elements.subclock(i);
+        let sz = SubclockSizing::for_entry(config, base, tz);
+        match config.clock.subclock_style {
+            SubclockStyle::Digital => render_digital(canvas, state, font, &sz, tz, cell, contrast),
+            SubclockStyle::Analogue => render_analogue(canvas, state, font, &sz, tz, cell, contrast),
         }
-        FaceMode::Analogue => config.clock.diameter as f32 * 0.25,
+    }
+}
+
+/// The entry's `label_color`, or the text colour muted to two-thirds alpha.
+fn label_color(tz: &TimezoneEntry, contrast: &ContrastInfo) -> [u8; 4] {
+    let tc = contrast.text_color;
+    tz.label_color.unwrap_or([tc[0], tc[1], tc[2], 0xAA])
+}
+
+/// Label above the zone's time as text.
+fn render_digital(
+    canvas: &mut Canvas,
+    state: &ClockState,
+    font: &FontState,
+    sz: &SubclockSizing,
+    tz: &TimezoneEntry,
+    cell: &Region,
+    contrast: &ContrastInfo,
+) {
+    let config = &state.config;
+    let time_str = time_utils::timezone_time(
+        &tz.tz,
+        state.time.instant,
+        config.clock.hour_format,
+        config.clock.subclock_seconds(),
+    );
+
+    let label_color = if time_str.is_some() { label_color(tz, contrast) } else { ERROR_COLOR };
+    let (time_str, time_color) = match time_str {
+        Some(t) => (t, tz.color.unwrap_or(contrast.text_color)),
+        None => ("??:??".to_string(), ERROR_COLOR),
     };
 
-    let sz = SubclockSizing::from_base(base);
-    let stacked = matches!(config.clock.face, FaceMode::Analogue);
-    let total_area_h = if stacked { sz.area_h * timezones.len() as f32 } else { sz.area_h };
-    let tz_y_start = h - total_area_h;
+    let col_cx = cell.x + cell.w / 2.0;
+    let y_offset = cell.y + (cell.h - sz.row_h) / 2.0;
 
-    // Draw separator line
-    let tc = state.contrast.text_color;
-    let sep_color = [tc[0], tc[1], tc[2], 0x66];
-    canvas.draw_line(w * 0.05, tz_y_start, w * 0.95, tz_y_start, sep_color, 1.0);
-
-    let content_h = sz.label_size + sz.time_size;
-    let cx = w / 2.0;
-
-    for (i, tz) in timezones.iter().enumerate() {
-        let time_str = time_utils::timezone_time(
-            &tz.tz,
-            config.clock.hour_format,
-            config.clock.show_seconds,
-        ).unwrap_or_else(|| "??:??".into());
-
-        let label_color = [tc[0], tc[1], tc[2], 0xAA];
-
-        let (col_cx, y_offset) = if stacked {
-            // Stacked: each subclock gets its own row, centred horizontally
-            let row_y = tz_y_start + sz.area_h * i as f32;
-            (cx, row_y + (sz.area_h - content_h) / 2.0)
-        } else {
-            // Side-by-side columns
-            let tz_count = timezones.len();
-            let col_w = w / tz_count as f32;
-            let col_cx = col_w * i as f32 + col_w / 2.0;
-            (col_cx, tz_y_start + (sz.area_h - content_h) / 2.0)
+    let (lw, _) = font.measure_text(&tz.label, sz.label_size);
+    let label_x = col_cx - lw / 2.0;
+    draw_contrast_text(font, canvas, &tz.label, label_x, y_offset, sz.label_size, label_color, contrast);
+
+    let (tw, _) = font.measure_text(&time_str, sz.time_size);
+    let time_x = col_cx - tw / 2.0;
+    let time_y = y_offset + sz.label_size * 1.1;
+    draw_contrast_text(font, canvas, &time_str, time_x, time_y, sz.time_size, time_color, contrast);
+
+    render_date(canvas, state, font, sz, tz, col_cx, time_y + sz.time_size * 1.05, label_color, contrast);
+}
+
+/// The zone's short date centred on `cx` below `y`, when `subclock_show_date` is on.
+#[allow(clippy::too_many_arguments)]
+fn render_date(
+    canvas: &mut Canvas,
+    state: &ClockState,
+    font: &FontState,
+    sz: &SubclockSizing,
+    tz: &TimezoneEntry,
+    cx: f32,
+    y: f32,
+    color: [u8; 4],
+    contrast: &ContrastInfo,
+) {
+    if sz.date_size <= 0.0 {
+        return;
+    }
+    let locale = state.config.clock.locale();
+    let Some(date) = time_utils::timezone_date(&tz.tz, state.time.instant, DATE_FORMAT, locale) else { return };
+    let (dw, _) = font.measure_text(&date, sz.date_size);
+    draw_contrast_text(font, canvas, &date, cx - dw / 2.0, y, sz.date_size, color, contrast);
+}
+
+/// A mini dial with hour, minute and (with `subclock_show_seconds`) second hands, label below.
+fn render_analogue(
+    canvas: &mut Canvas,
+    state: &ClockState,
+    font: &FontState,
+    sz: &SubclockSizing,
+    tz: &TimezoneEntry,
+    cell: &Region,
+    contrast: &ContrastInfo,
+) {
+    let config = &state.config;
+    let tc = tz.color.unwrap_or(contrast.text_color);
+    let components = time_utils::timezone_components_at(&tz.tz, state.time.instant);
+    let label_color = if components.is_some() { label_color(tz, contrast) } else { ERROR_COLOR };
+
+    let r = sz.face_size / 2.0;
+    let cx = cell.x + cell.w / 2.0;
+    let y_offset = cell.y + (cell.h - sz.row_h) / 2.0;
+    let cy = y_offset + r;
+
+    let ring_w = (r * 0.06).max(1.0);
+    canvas.draw_circle(cx, cy, r - ring_w / 2.0, tc, false, ring_w);
+
+    // An unknown zone keeps its empty dial
+    if let Some(t) = components {
+        let hand = |canvas: &mut Canvas, deg: f32, len: f32, color: [u8; 4], width: f32| {
+            let rad = deg.to_radians();
+            canvas.draw_line(cx, cy, cx + rad.sin() * len, cy - rad.cos() * len, color, width);
         };
+        let hour_deg = (t.hour % 12) as f32 * 30.0 + t.minute as f32 * 0.5;
+        let minute_deg = t.minute as f32 * 6.0 + t.second as f32 * 0.1;
+        hand(canvas, hour_deg, r * 0.5, tc, (r * 0.1).max(1.5));
+        hand(canvas, minute_deg, r * 0.75, tc, (r * 0.07).max(1.0));
+        if config.clock.subclock_seconds() {
+            hand(canvas, t.second as f32 * 6.0, r * 0.85, config.theme.second_hand_color, (r * 0.03).max(1.0));
+        }
+        canvas.draw_circle(cx, cy, (r * 0.08).max(1.0), tc, true, 0.0);
+    }
+
+    let (lw, _) = font.measure_text(&tz.label, sz.label_size);
+    let label_y = y_offset + sz.face_size + sz.label_size * 0.3;
+    draw_contrast_text(font, canvas, &tz.label, cx - lw / 2.0, label_y, sz.label_size, label_color, contrast);
+
+    render_date(canvas, state, font, sz, tz, cx, label_y + sz.label_size * 1.1, label_color, contrast);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClockConfig;
+    use crate::renderer::ElementContrast;
+    use chrono::{TimeZone, Utc};
 
-        let (lw, _) = font.measure_text(&tz.label, sz.label_size);
-        let label_x = col_cx - lw / 2.0;
-        draw_contrast_text(font, canvas, &tz.label, label_x, y_offset, sz.label_size, label_color, &state.contrast);
+    fn entry(label: &str, tz: &str, primary: bool) -> TimezoneEntry {
+        TimezoneEntry { label: label.into(), tz: tz.into(), color: None, label_color: None, primary }
+    }
+
+    fn state(face: FaceMode, style: SubclockStyle, show_date: bool) -> ClockState {
+        let mut config = ClockConfig::default();
+        config.clock.face = face;
+        config.clock.subclock_style = style;
+        config.clock.subclock_show_date = show_date;
+        // The separator sits on the area's top edge; only the entries are measured
+        config.clock.subclock_separator = SubclockSeparator::None;
+        // A primary entry makes the two rows different heights
+        config.timezone = vec![entry("Tokyo", "Asia/Tokyo", true), entry("London", "Europe/London", false)];
+        let instant = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let time = time_utils::clock_time_at(instant, &config.clock.date_format, None, None, chrono::Locale::POSIX);
+        let contrast = ContrastInfo::resolve(&config.theme, None);
+        ClockState {
+            config,
+            time,
+            compact: false,
+            battery: None,
+            contrast,
+            elements: ElementContrast::uniform(contrast),
+            background_frame: None,
+            alarm_pending: false,
+            lock_glyph: None,
+        }
+    }
+
+    const W: u32 = 360;
+    const H: u32 = 480;
+
+    /// First and last rows in `rows` holding drawn pixels within `columns`.
+    fn drawn_rows(canvas: &Canvas, columns: std::ops::Range<u32>, rows: std::ops::Range<u32>) -> Option<(u32, u32)> {
+        let data = canvas.pixmap.data();
+        let covered = |y: u32| columns.clone().any(|x| data[((y * W + x) * 4 + 3) as usize] != 0);
+        let first = rows.clone().find(|&y| covered(y))?;
+        let last = rows.rev().find(|&y| covered(y))?;
+        Some((first, last))
+    }
 
-        let (tw, _) = font.measure_text(&time_str, sz.time_size);
-        let time_x = col_cx - tw / 2.0;
-        let time_y = y_offset + sz.label_size * 1.1;
-        draw_contrast_text(font, canvas, &time_str, time_x, time_y, sz.time_size, state.contrast.text_color, &state.contrast);
+    /// Draw every combination of face, sub-clock style and date line, and check
+    /// that what `render` draws for each entry lies within that entry's share
+    /// of the reserved area and fills most of it.
+    #[test]
+    fn drawn_entries_fit_reserved_height() {
+        let font = FontState::embedded().expect("embedded font");
+        for face in [FaceMode::Digital, FaceMode::Analogue] {
+            for style in [SubclockStyle::Digital, SubclockStyle::Analogue] {
+                for show_date in [false, true] {
+                    let state = state(face, style, show_date);
+                    let label = format!("{face:?} face, {style:?} sub-clocks, show_date={show_date}");
+                    let reserved = SubclockSizing::reserved_height(&state.config, false);
+                    let mut canvas = Canvas::new(W, H);
+                    render(&mut canvas, &state, &font);
+
+                    // Nothing reaches up into the main clock
+                    let above = drawn_rows(&canvas, 0..W, 0..H).expect("something drawn").0;
+                    assert!(above as f32 >= H as f32 - reserved, "{label}: drawn from row {above}, reserved {reserved}");
+
+                    for (i, cell) in cells(&state, W as f32, H as f32).iter().enumerate() {
+                        let columns = cell.x.ceil() as u32..(cell.x + cell.w).floor() as u32;
+                        // Stacked rows share columns, so each is measured within its own rows
+                        let rows = match face {
+                            FaceMode::Analogue => cell.y.floor() as u32..((cell.y + cell.h).ceil() as u32).min(H),
+                            FaceMode::Digital => 0..H,
+                        };
+                        let (y0, y1) = drawn_rows(&canvas, columns, rows).unwrap_or_else(|| panic!("{label}: entry {i} draws nothing"));
+
+                        let (top, bottom) = (cell.y, cell.y + cell.h);
+                        assert!(y0 as f32 >= top.floor() && (y1 + 1) as f32 <= bottom.ceil(),
+                            "{label}: entry {i} drawn on rows {y0}..={y1}, cell {top}..{bottom}");
+                        // The reserved height isn't much more than what is drawn
+                        let drawn = (y1 + 1 - y0) as f32;
+                        assert!(drawn >= cell.h * 0.5, "{label}: entry {i} draws {drawn}px of a {}px cell", cell.h);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dates_reserve_more_height() {
+        for face in [FaceMode::Analogue, FaceMode::Digital] {
+            let height = |show_date| SubclockSizing::reserved_height(&state(face, SubclockStyle::Digital, show_date).config, false);
+            let (without, with) = (height(false), height(true));
+            assert!(with > without, "{face:?}: {with} <= {without}");
+        }
     }
 }
@@ -1,17 +1,40 @@
-use chrono::{Local, Timelike};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local, Locale, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike, Utc, Weekday};
 use chrono_tz::Tz;
 
 #[derive(Debug, Clone)]
 pub struct ClockTime {
+    /// The instant this time was taken at; sub-clocks convert it to their zones.
+    pub instant: DateTime<Utc>,
     pub hour: u32,
     pub minute: u32,
     pub second: u32,
+    /// Milliseconds into the current second, for sub-second animation.
+    pub millis: u32,
     pub hour12: u32,
     pub is_pm: bool,
     pub date_string: String,
+    /// ISO 8601 week number of the local date.
+    pub iso_week: u32,
+    /// The time formatted with `[clock] time_format`, when one is set.
+    pub custom_time: Option<String>,
+    /// The `[clock] secondary_line`, when one is set.
+    pub secondary: Option<String>,
 }
 
 impl ClockTime {
+    /// The main time line: the custom format if set, else `HH:MM[:SS]` plus AM/PM.
+    pub fn display_time(&self, hour_format: u8, show_seconds: bool) -> String {
+        match &self.custom_time {
+            Some(t) => t.clone(),
+            None => format!(
+                "{}{}",
+                self.format_time(hour_format, show_seconds),
+                self.format_time_suffix(hour_format),
+            ),
+        }
+    }
+
     pub fn format_time(&self, hour_format: u8, show_seconds: bool) -> String {
         let h = if hour_format == 12 { self.hour12 } else { self.hour };
         if show_seconds {
@@ -30,37 +53,247 @@ impl ClockTime {
     }
 }
 
-pub fn current_time(date_format: &str) -> ClockTime {
-    let now = Local::now();
+/// Whether `fmt` is a strftime string chrono can format (it panics on bad ones).
+pub fn is_valid_format(fmt: &str) -> bool {
+    !StrftimeItems::new(fmt).any(|item| matches!(item, Item::Error))
+}
+
+/// Parse a locale name such as `de_DE`, `de_DE.UTF-8` or `C`. A codeset is
+/// ignored; a modifier (`@euro`) is kept when the locale has that variant.
+pub fn parse_locale(name: &str) -> Option<Locale> {
+    let (base, modifier) = match name.split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (name, None),
+    };
+    let base = base.split('.').next().unwrap_or(base);
+    if base == "C" || base == "POSIX" {
+        return Some(Locale::POSIX);
+    }
+    modifier
+        .and_then(|m| Locale::try_from(format!("{}@{}", base, m).as_str()).ok())
+        .or_else(|| Locale::try_from(base).ok())
+}
+
+/// The locale for day and month names: `configured` when set, otherwise the
+/// first of `$LC_ALL`, `$LC_TIME` and `$LANG` that is set. Anything unknown
+/// falls back to POSIX (English) names.
+pub fn resolve_locale(configured: &str) -> Locale {
+    if !configured.is_empty() {
+        return parse_locale(configured).unwrap_or(Locale::POSIX);
+    }
+    ["LC_ALL", "LC_TIME", "LANG"].iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .and_then(|name| parse_locale(&name))
+        .unwrap_or(Locale::POSIX)
+}
+
+/// A fixed moment with the locale's longest day and month names and
+/// two-digit fields, used to measure the widest rendering of a custom format.
+pub fn sample_wide_time(fmt: &str, locale: Locale) -> String {
+    let month = longest_name((1..=12).filter_map(|m| NaiveDate::from_ymd_opt(2000, m, 1)), "%B", locale)
+        .map_or(9, |d| d.month());
+    // 3 to 9 January 2000 run Monday to Sunday
+    let weekday = longest_name((3..=9).filter_map(|d| NaiveDate::from_ymd_opt(2000, 1, d)), "%A", locale)
+        .map_or(Weekday::Wed, |d| d.weekday());
+    // Any month has every weekday among its 22nd to 28th
+    let sample = (22..=28)
+        .filter_map(|day| NaiveDate::from_ymd_opt(2000, month, day))
+        .find(|d| d.weekday() == weekday)
+        .and_then(|d| d.and_hms_opt(22, 58, 58))
+        .expect("valid sample date");
+    Utc.from_utc_datetime(&sample).format_localized(fmt, locale).to_string()
+}
+
+/// The first of `dates` whose `spec` (e.g. `%B`) is longest in `locale`.
+fn longest_name(dates: impl Iterator<Item = NaiveDate>, spec: &str, locale: Locale) -> Option<NaiveDate> {
+    dates.fold(None, |best: Option<(NaiveDate, usize)>, date| {
+        let len = date.format_localized(spec, locale).to_string().chars().count();
+        match best {
+            Some((_, best_len)) if best_len >= len => best,
+            _ => Some((date, len)),
+        }
+    }).map(|(date, _)| date)
+}
+
+/// Whether a format changes from one second to the next, i.e. shows seconds.
+pub fn format_shows_seconds(fmt: &str) -> bool {
+    // A zoned time, so `%Z` and `%z` have something to format
+    let naive = NaiveDate::from_ymd_opt(2000, 1, 1).and_then(|d| d.and_hms_opt(0, 0, 0)).expect("valid date");
+    let t = Utc.from_utc_datetime(&naive);
+    t.format(fmt).to_string() != (t + chrono::Duration::seconds(1)).format(fmt).to_string()
+}
+
+pub fn current_time(date_format: &str, time_format: Option<&str>, secondary_format: Option<&str>, locale: Locale) -> ClockTime {
+    clock_time_at(Utc::now(), date_format, time_format, secondary_format, locale)
+}
+
+/// The local clock time at a given UTC instant, for rendering a fixed moment.
+/// Day and month names in the formats are spelled in `locale`.
+pub fn clock_time_at(
+    instant: DateTime<Utc>,
+    date_format: &str,
+    time_format: Option<&str>,
+    secondary_format: Option<&str>,
+    locale: Locale,
+) -> ClockTime {
+    let now = instant.with_timezone(&Local);
     let hour = now.hour();
     let hour12 = if hour == 0 { 12 } else if hour > 12 { hour - 12 } else { hour };
     ClockTime {
+        instant,
         hour,
         minute: now.minute(),
         second: now.second(),
+        millis: now.timestamp_subsec_millis().min(999),
         hour12,
         is_pm: hour >= 12,
-        date_string: now.format(date_format).to_string(),
+        date_string: now.format_localized(date_format, locale).to_string(),
+        iso_week: now.iso_week().week(),
+        custom_time: time_format.map(|fmt| now.format_localized(fmt, locale).to_string()),
+        secondary: secondary_format.map(|fmt| now.format_localized(fmt, locale).to_string()),
+    }
+}
+
+/// Parse a `--now` value: RFC 3339 with an offset (`2024-01-02T03:04:05Z`), or a
+/// local date-time without one (`2024-01-02T03:04:05`, `2024-01-02 03:04`).
+pub fn parse_instant(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())?;
+    // A time skipped by a DST change has no local instant; an ambiguous one takes the first
+    Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc))
+}
+
+/// Parse an alarm time given as 24-hour `HH:MM` (`7:30` is accepted too).
+pub fn parse_alarm_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// Wall-clock time in a timezone, broken into fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeComponents {
+    /// Hour, 0–23.
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub is_pm: bool,
+    /// Offset from UTC in seconds (east positive) in effect at that instant,
+    /// so it includes daylight saving time.
+    pub offset_secs: i32,
+}
+
+impl TimeComponents {
+    /// Hour on a 12-hour dial, 1–12.
+    pub fn hour12(&self) -> u32 {
+        match self.hour {
+            0 => 12,
+            h if h > 12 => h - 12,
+            h => h,
+        }
     }
 }
 
-pub fn timezone_time(tz_str: &str, hour_format: u8, show_seconds: bool) -> Option<String> {
+/// The current time in `tz_str`, or `None` if it isn't a known IANA zone.
+pub fn timezone_components(tz_str: &str) -> Option<TimeComponents> {
+    timezone_components_at(tz_str, chrono::Utc::now())
+}
+
+/// The time in `tz_str` at a given UTC instant, or `None` if it isn't a known IANA zone.
+/// Converting the instant (never a wall time) looks up the zone's rules for
+/// that moment, so the result is exact across DST changes: 06:59 UTC on
+/// 2024-03-10 is 01:59 EST in New York and 07:00 is 03:00 EDT.
+pub fn timezone_components_at(tz_str: &str, instant: DateTime<Utc>) -> Option<TimeComponents> {
     let tz: Tz = tz_str.parse().ok()?;
-    let now = chrono::Utc::now().with_timezone(&tz);
-    let hour = now.hour();
-    let h = if hour_format == 12 {
-        if hour == 0 { 12 } else if hour > 12 { hour - 12 } else { hour }
-    } else {
-        hour
-    };
+    let local = instant.with_timezone(&tz);
+    let hour = local.hour();
+    Some(TimeComponents {
+        hour,
+        minute: local.minute(),
+        second: local.second(),
+        is_pm: hour >= 12,
+        offset_secs: local.offset().fix().local_minus_utc(),
+    })
+}
+
+/// The date in `tz_str` at `instant`, formatted with `fmt` in `locale`.
+pub fn timezone_date(tz_str: &str, instant: DateTime<Utc>, fmt: &str, locale: Locale) -> Option<String> {
+    let tz: Tz = tz_str.parse().ok()?;
+    Some(instant.with_timezone(&tz).format_localized(fmt, locale).to_string())
+}
+
+/// The time in `tz_str` at `instant`, formatted like the main clock's default time line.
+pub fn timezone_time(tz_str: &str, instant: DateTime<Utc>, hour_format: u8, show_seconds: bool) -> Option<String> {
+    let t = timezone_components_at(tz_str, instant)?;
+    let h = if hour_format == 12 { t.hour12() } else { t.hour };
     let suffix = if hour_format == 12 {
-        if hour >= 12 { " PM" } else { " AM" }
+        if t.is_pm { " PM" } else { " AM" }
     } else {
         ""
     };
     if show_seconds {
-        Some(format!("{:02}:{:02}:{:02}{}", h, now.minute(), now.second(), suffix))
+        Some(format!("{:02}:{:02}:{:02}{}", h, t.minute, t.second, suffix))
     } else {
-        Some(format!("{:02}:{:02}{}", h, now.minute(), suffix))
+        Some(format!("{:02}:{:02}{}", h, t.minute, suffix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).expect("valid instant").with_timezone(&Utc)
+    }
+
+    fn wall(tz: &str, instant: &str) -> (u32, u32, i32) {
+        let t = timezone_components_at(tz, at(instant)).expect("known zone");
+        (t.hour, t.minute, t.offset_secs)
+    }
+
+    #[test]
+    fn shows_seconds_with_zone_specifiers() {
+        assert!(!format_shows_seconds("%H:%M %Z"));
+        assert!(!format_shows_seconds("%H:%M %z"));
+        assert!(format_shows_seconds("%H:%M:%S %Z"));
+        assert!(format_shows_seconds("%T %z"));
+    }
+
+    #[test]
+    fn new_york_spring_forward() {
+        assert_eq!(wall("America/New_York", "2024-03-10T06:59:00Z"), (1, 59, -18000));
+        assert_eq!(wall("America/New_York", "2024-03-10T07:00:00Z"), (3, 0, -14400));
+    }
+
+    #[test]
+    fn new_york_fall_back() {
+        assert_eq!(wall("America/New_York", "2024-11-03T05:59:00Z"), (1, 59, -14400));
+        assert_eq!(wall("America/New_York", "2024-11-03T06:00:00Z"), (1, 0, -18000));
+    }
+
+    #[test]
+    fn components_at_fixed_instant() {
+        // 2024-01-15 is winter in Tokyo (no DST, UTC+9)
+        let t = timezone_components_at("Asia/Tokyo", at("2024-01-15T15:30:45Z")).expect("known zone");
+        assert_eq!((t.hour, t.minute, t.second), (0, 30, 45));
+        assert!(!t.is_pm);
+        assert_eq!(t.hour12(), 12);
+        assert_eq!(t.offset_secs, 9 * 3600);
+
+        let t = timezone_components_at("Asia/Tokyo", at("2024-01-15T03:05:00Z")).expect("known zone");
+        assert_eq!((t.hour, t.minute), (12, 5));
+        assert!(t.is_pm);
+        assert_eq!(t.hour12(), 12);
+
+        let t = timezone_components_at("UTC", at("2024-01-15T13:00:00Z")).expect("known zone");
+        assert_eq!((t.hour, t.hour12(), t.is_pm, t.offset_secs), (13, 1, true, 0));
+    }
+
+    #[test]
+    fn unknown_zone_is_none() {
+        assert!(timezone_components_at("Mars/Olympus_Mons", at("2024-01-15T00:00:00Z")).is_none());
+        assert!(timezone_time("Mars/Olympus_Mons", at("2024-01-15T00:00:00Z"), 24, false).is_none());
     }
 }
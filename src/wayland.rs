@@ -1,8 +1,9 @@
 use anyhow::{Context, Result};
+use chrono::{NaiveTime, Timelike};
 use smithay_client_toolkit::{
     compositor::{CompositorHandler, CompositorState},
     delegate_compositor, delegate_layer, delegate_output, delegate_pointer, delegate_registry,
-    delegate_seat, delegate_shm,
+    delegate_seat, delegate_shm, delegate_xdg_shell, delegate_xdg_window,
     output::{OutputHandler, OutputState},
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
@@ -11,26 +12,45 @@ use smithay_client_toolkit::{
         Anchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface,
         LayerSurfaceConfigure,
     },
+    shell::xdg::{
+        window::{Window, WindowConfigure, WindowDecorations, WindowHandler},
+        XdgShell,
+    },
     shell::WaylandSurface,
-    shm::{slot::SlotPool, Shm, ShmHandler},
+    shm::{slot::{Buffer, SlotPool}, Shm, ShmHandler},
+};
+use smithay_client_toolkit::seat::pointer::{
+    cursor_shape::CursorShapeManager, PointerData, PointerEvent, PointerEventKind, PointerHandler,
+};
+use smithay_client_toolkit::reexports::protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::{
+    Shape as CursorShape, WpCursorShapeDeviceV1,
+};
+use smithay_client_toolkit::reexports::protocols::wp::{
+    fractional_scale::v1::client::{wp_fractional_scale_manager_v1, wp_fractional_scale_v1},
+    viewporter::client::{wp_viewport, wp_viewporter},
 };
-use smithay_client_toolkit::seat::pointer::{PointerEvent, PointerEventKind, PointerHandler};
 use wayland_client::{
-    globals::registry_queue_init,
+    globals::{registry_queue_init, GlobalList},
     protocol::{wl_output, wl_pointer, wl_seat, wl_shm, wl_surface},
-    Connection, QueueHandle,
+    Connection, Dispatch, Proxy, QueueHandle,
 };
 
-use std::os::unix::net::UnixListener;
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::canvas::{Canvas, FontState};
-use crate::config::{self, ClockConfig, FaceMode};
+use tiny_skia::Pixmap;
+
+use crate::canvas::{Animation, Canvas, FontState};
+use crate::config::{self, Backend, ClockConfig, DragButton, FaceMode, GallerySchedule};
 use crate::ipc;
-use crate::renderer::{self, ClockState, ContrastInfo};
+use crate::hooks::HookRunner;
+use crate::renderer::{self, lock::LockGlyph, ClockState, ContrastInfo, ElementContrast};
 use crate::time_utils;
 
 pub struct GalleryState {
@@ -41,10 +61,13 @@ pub struct GalleryState {
     rotate_interval: Duration,
     rotate_active: bool,
     last_rotate: Instant,
+    schedule: Vec<GallerySchedule>,
+    /// Index of the schedule slot currently in effect.
+    active_slot: Option<usize>,
 }
 
 impl GalleryState {
-    fn from_config(config: &ClockConfig) -> Self {
+    pub fn from_config(config: &ClockConfig) -> Self {
         let interval_secs = config.background.gallery_interval;
         let rotate_interval = Duration::from_secs(interval_secs);
         let digital_images = config.background.effective_digital_images();
@@ -64,7 +87,54 @@ impl GalleryState {
             rotate_interval,
             rotate_active: interval_secs > 0,
             last_rotate: Instant::now(),
+            schedule: config.background.gallery_schedule.clone(),
+            active_slot: None,
+        }
+    }
+
+    /// When a different schedule slot comes into effect, jump to the first of
+    /// its images (unless the current one is already in its set). Manual
+    /// navigation is left alone until the next slot change. Returns true if an
+    /// index changed.
+    pub fn apply_schedule(&mut self, now: chrono::DateTime<chrono::Local>) -> bool {
+        use chrono::{Datelike, Timelike};
+        let slot = self.schedule.iter().position(|s| s.matches(now.weekday(), now.hour()));
+        if slot == self.active_slot {
+            return false;
         }
+        self.active_slot = slot;
+        let before = (self.digital_index, self.analogue_index);
+        if let Some(&first) = self.scheduled_digital().first() {
+            if !self.scheduled_digital().contains(&self.digital_index) {
+                self.digital_index = first;
+            }
+        }
+        if let Some(&first) = self.scheduled_analogue().first() {
+            if !self.scheduled_analogue().contains(&self.analogue_index) {
+                self.analogue_index = first;
+            }
+        }
+        before != (self.digital_index, self.analogue_index)
+    }
+
+    /// Digital indices of the active schedule slot (empty = no restriction).
+    fn scheduled_digital(&self) -> Vec<usize> {
+        let len = self.digital_images.len();
+        self.active_slot.map_or_else(Vec::new, |i| {
+            self.schedule[i].digital.iter().copied().filter(|&idx| idx < len).collect()
+        })
+    }
+
+    /// Analogue indices of the active schedule slot (empty = no restriction).
+    fn scheduled_analogue(&self) -> Vec<usize> {
+        let len = self.analogue_images.len();
+        self.active_slot.map_or_else(Vec::new, |i| {
+            self.schedule[i].analogue.iter().copied().filter(|&idx| idx < len).collect()
+        })
+    }
+
+    pub fn has_images(&self) -> bool {
+        !self.digital_images.is_empty() || !self.analogue_images.is_empty()
     }
 
     fn next_digital(&mut self) {
@@ -91,13 +161,21 @@ impl GalleryState {
         }
     }
 
+    /// Auto-rotate step: the next image, or the next one in the active
+    /// schedule slot's set.
     fn rotate(&mut self) {
-        self.next_digital();
-        self.next_analogue();
+        match next_in_set(self.digital_index, &self.scheduled_digital()) {
+            Some(idx) => self.digital_index = idx,
+            None => self.next_digital(),
+        }
+        match next_in_set(self.analogue_index, &self.scheduled_analogue()) {
+            Some(idx) => self.analogue_index = idx,
+            None => self.next_analogue(),
+        }
         self.last_rotate = Instant::now();
     }
 
-    fn current_digital_image(&self) -> &str {
+    pub fn current_digital_image(&self) -> &str {
         if self.digital_images.is_empty() {
             ""
         } else {
@@ -105,7 +183,7 @@ impl GalleryState {
         }
     }
 
-    fn current_analogue_image(&self) -> &str {
+    pub fn current_analogue_image(&self) -> &str {
         if self.analogue_images.is_empty() {
             ""
         } else {
@@ -132,6 +210,69 @@ impl GalleryState {
         if interval_secs == 0 {
             self.rotate_active = false;
         }
+        // Re-evaluate the (possibly changed) schedule on the next loop iteration
+        self.schedule = config.background.gallery_schedule.clone();
+        self.active_slot = None;
+    }
+}
+
+/// The entry after `current` in `set`, wrapping; `None` for an empty set.
+fn next_in_set(current: usize, set: &[usize]) -> Option<usize> {
+    let pos = set.iter().position(|&idx| idx == current);
+    let next = pos.map_or(0, |p| (p + 1) % set.len());
+    set.get(next).copied()
+}
+
+/// Playback state for an animated digital background (GIF/APNG).
+/// Still images leave `animation` empty and cost nothing beyond a path comparison.
+struct BackgroundAnimation {
+    path: String,
+    animation: Option<Animation>,
+    frame: usize,
+    frame_started: Instant,
+}
+
+impl BackgroundAnimation {
+    fn new() -> Self {
+        Self {
+            path: String::new(),
+            animation: None,
+            frame: 0,
+            frame_started: Instant::now(),
+        }
+    }
+
+    /// Decode `path` if it differs from the currently loaded image.
+    fn sync(&mut self, path: &str) {
+        if self.path == path {
+            return;
+        }
+        self.path = path.to_string();
+        self.animation = crate::canvas::load_animation(path);
+        self.frame = 0;
+        self.frame_started = Instant::now();
+    }
+
+    /// Advance past any frames whose delay has elapsed. Returns true if the frame changed.
+    fn advance(&mut self) -> bool {
+        let Some(anim) = &self.animation else { return false };
+        let mut changed = false;
+        while self.frame_started.elapsed() >= anim.delays[self.frame] {
+            self.frame_started += anim.delays[self.frame];
+            self.frame = (self.frame + 1) % anim.frames.len();
+            changed = true;
+        }
+        changed
+    }
+
+    /// Time remaining until the next frame is due, if animating.
+    fn time_to_next_frame(&self) -> Option<Duration> {
+        let anim = self.animation.as_ref()?;
+        Some(anim.delays[self.frame].saturating_sub(self.frame_started.elapsed()))
+    }
+
+    fn current_frame(&self) -> Option<Rc<Pixmap>> {
+        self.animation.as_ref().map(|anim| anim.frames[self.frame].clone())
     }
 }
 
@@ -140,12 +281,27 @@ pub struct Clockie {
     seat_state: SeatState,
     output_state: OutputState,
     compositor_state: CompositorState,
-    layer_shell: LayerShell,
+    shell: Shell,
     shm: Shm,
     pool: SlotPool,
+    // Buffers drawn in turn, the last committed first, and that frame's
+    // pixels for partial damage
+    buffers: Vec<Buffer>,
+    prev_frame: Vec<u8>,
+    full_damage: bool,
 
-    layer_surface: LayerSurface,
+    shell_surface: ClockSurface,
     current_output: Option<wl_output::WlOutput>,
+
+    // Fractional scaling (absent when the compositor lacks the protocols)
+    fractional_scaling: Option<FractionalScaling>,
+    surface_scale: Option<SurfaceScale>,
+    /// Preferred scale in 120ths, as sent by wp_fractional_scale_v1 (120 = 1x)
+    scale_120: u32,
+    // Integer buffer scale, used when fractional scaling is unavailable
+    buffer_scale: i32,
+    buffer_transform: wl_output::Transform,
+
     width: u32,
     height: u32,
     configured: bool,
@@ -155,33 +311,213 @@ pub struct Clockie {
     config_path: PathBuf,
     compact: bool,
     font: FontState,
+    /// Factor applied to font_size and diameter when drawing in output size mode.
+    content_scale: f32,
 
     // Pointer / drag-to-move
     pointer: Option<wl_pointer::WlPointer>,
+    cursor_shape_manager: Option<CursorShapeManager>,
+    cursor_shape_device: Option<WpCursorShapeDeviceV1>,
+    pointer_enter_serial: Option<u32>,
     locked: bool,
+    // Visibility: hidden by IPC, or by a reported fullscreen window
+    hidden: bool,
+    fullscreen: bool,
     dragging: bool,
+    /// Set once a press has moved past DRAG_THRESHOLD; until then it is a click.
+    drag_active: bool,
     drag_start: (f64, f64),
     drag_margins: (i32, i32, i32, i32), // (top, right, bottom, left) at drag start
+    /// Axes that were anchored to both edges before this drag unpinned them.
+    drag_unpinned: Anchor,
     anchor: Anchor,
 
     // IPC
     ipc_listener: UnixListener,
-    ipc_socket_path: PathBuf,
+    ipc_endpoint: ipc::Endpoint,
 
     // Pending initial output move (applied after first configure when outputs are known)
     pending_output_move: Option<String>,
+    // Set when every output has gone away; drawing pauses until one returns
+    output_lost: bool,
+    // Output the clock was moved off when it was unplugged, for `return_to_output`
+    displaced_from: Option<String>,
 
     // Gallery
     gallery: GalleryState,
+    background_animation: BackgroundAnimation,
 
-    // Auto-contrast: cached text color and dirty flag
+    // Auto-contrast: cached global/per-element contrast and dirty flag
     contrast_dirty: bool,
-    cached_text_color: [u8; 4],
-
+    cached_contrast: ContrastInfo,
+    cached_elements: ElementContrast,
+
+    /// When the daemon started, for `ping` uptime
+    started: std::time::Instant,
+    /// When the charging animation last advanced
+    last_charge_frame: std::time::Instant,
+    /// When an eased second hand last advanced
+    last_sweep_frame: std::time::Instant,
+    /// When the hour flash last advanced, and whether the last frame showed it
+    last_flash_frame: std::time::Instant,
+    flash_drawn: bool,
+    /// Whether the pointer is over the clock, and when it last moved or left
+    pointer_inside: bool,
+    last_pointer_activity: std::time::Instant,
+    /// How far the idle dim has faded in, 0 (full opacity) to 1 (`idle_opacity`),
+    /// when it last advanced, and whether a fade was under way at the last check
+    idle_dim: f32,
+    last_dim_frame: std::time::Instant,
+    dim_fade_running: bool,
+    /// `[hooks]` commands, and the local hour `on_hour` last saw
+    hooks: HookRunner,
+    last_hook_hour: i64,
+    /// When the drag lock was last toggled, for the confirming padlock
+    lock_toggled_at: Option<std::time::Instant>,
+    /// Pending one-shot alarms, soonest first, and when the last one went off
+    alarms: Vec<NaiveTime>,
+    alarm_went_off: Option<Instant>,
+    /// Last battery reading and when it was taken; re-read at most once a second
+    battery: Option<(std::time::Instant, Option<crate::battery::BatteryInfo>)>,
     should_quit: bool,
 }
 
-pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<PathBuf>) -> Result<()> {
+/// Hours since the epoch in local time; changes at the start of each local hour.
+fn local_hour() -> i64 {
+    chrono::Local::now().naive_local().and_utc().timestamp().div_euclid(3600)
+}
+
+/// Globals needed for fractional scaling: both must be present to use it.
+struct FractionalScaling {
+    manager: wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+    viewporter: wp_viewporter::WpViewporter,
+}
+
+impl FractionalScaling {
+    fn bind(globals: &GlobalList, qh: &QueueHandle<Clockie>) -> Option<Self> {
+        let manager = globals.bind(qh, 1..=1, ()).ok();
+        let viewporter = globals.bind(qh, 1..=1, ()).ok();
+        match (manager, viewporter) {
+            (Some(manager), Some(viewporter)) => Some(Self { manager, viewporter }),
+            _ => {
+                log::info!("wp_fractional_scale_v1/wp_viewporter not available, rendering at 1x");
+                None
+            }
+        }
+    }
+
+    fn create(&self, qh: &QueueHandle<Clockie>, surface: &wl_surface::WlSurface) -> SurfaceScale {
+        SurfaceScale {
+            fractional: self.manager.get_fractional_scale(surface, qh, ()),
+            viewport: self.viewporter.get_viewport(surface, qh, ()),
+        }
+    }
+}
+
+/// Per-surface fractional scale objects.
+struct SurfaceScale {
+    fractional: wp_fractional_scale_v1::WpFractionalScaleV1,
+    viewport: wp_viewport::WpViewport,
+}
+
+impl Drop for SurfaceScale {
+    fn drop(&mut self) {
+        self.fractional.destroy();
+        self.viewport.destroy();
+    }
+}
+
+/// Shell protocol the clock's window is created with.
+enum Shell {
+    Layer(LayerShell),
+    Xdg(XdgShell),
+}
+
+/// The clock's shell surface. A layer surface is placed by anchor and margins;
+/// an xdg toplevel is an ordinary window the compositor places, so those
+/// requests are ignored for it.
+enum ClockSurface {
+    Layer(LayerSurface),
+    Xdg(Window),
+}
+
+impl ClockSurface {
+    /// Create the shell surface for `surface` at `size`, placed on `output`
+    /// when the shell allows it, and commit its initial state.
+    fn create(
+        shell: &Shell,
+        qh: &QueueHandle<Clockie>,
+        surface: wl_surface::WlSurface,
+        window: &config::WindowConfig,
+        anchor: Anchor,
+        output: Option<&wl_output::WlOutput>,
+        (width, height): (u32, u32),
+    ) -> Self {
+        let clock_surface = match shell {
+            Shell::Layer(layer_shell) => {
+                let layer = match window.layer.as_str() {
+                    "background" => Layer::Background,
+                    "bottom" => Layer::Bottom,
+                    "top" => Layer::Top,
+                    "overlay" => Layer::Overlay,
+                    _ => Layer::Top,
+                };
+                let layer_surface = layer_shell.create_layer_surface(qh, surface, layer, Some("clockie"), output);
+                layer_surface.set_anchor(anchor);
+                layer_surface.set_margin(window.margin_top, window.margin_right, window.margin_bottom, window.margin_left);
+                // No exclusive zone, no keyboard grab
+                layer_surface.set_exclusive_zone(0);
+                layer_surface.set_keyboard_interactivity(KeyboardInteractivity::None);
+                ClockSurface::Layer(layer_surface)
+            }
+            Shell::Xdg(xdg_shell) => {
+                let toplevel = xdg_shell.create_window(surface, WindowDecorations::None, qh);
+                toplevel.set_title("clockie");
+                toplevel.set_app_id("clockie");
+                ClockSurface::Xdg(toplevel)
+            }
+        };
+        clock_surface.set_size(width, height);
+        clock_surface.wl_surface().commit();
+        clock_surface
+    }
+
+    fn wl_surface(&self) -> &wl_surface::WlSurface {
+        match self {
+            ClockSurface::Layer(layer_surface) => layer_surface.wl_surface(),
+            ClockSurface::Xdg(window) => window.wl_surface(),
+        }
+    }
+
+    fn is_layer(&self) -> bool {
+        matches!(self, ClockSurface::Layer(_))
+    }
+
+    /// Request a size; a toplevel is pinned to it with equal min and max sizes.
+    fn set_size(&self, width: u32, height: u32) {
+        match self {
+            ClockSurface::Layer(layer_surface) => layer_surface.set_size(width, height),
+            ClockSurface::Xdg(window) => {
+                window.set_min_size(Some((width, height)));
+                window.set_max_size(Some((width, height)));
+            }
+        }
+    }
+
+    fn set_anchor(&self, anchor: Anchor) {
+        if let ClockSurface::Layer(layer_surface) = self {
+            layer_surface.set_anchor(anchor);
+        }
+    }
+
+    fn set_margin(&self, top: i32, right: i32, bottom: i32, left: i32) {
+        if let ClockSurface::Layer(layer_surface) = self {
+            layer_surface.set_margin(top, right, bottom, left);
+        }
+    }
+}
+
+pub fn run(config: ClockConfig, config_path: PathBuf, ipc_endpoint: ipc::Endpoint) -> Result<()> {
     let conn = Connection::connect_to_env().context(
         "Failed to connect to Wayland. Ensure a Wayland compositor with wlr-layer-shell support is running."
     )?;
@@ -192,61 +528,35 @@ pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<Pa
 
     let compositor = CompositorState::bind(&globals, &qh)
         .context("wl_compositor not available")?;
-    let layer_shell = LayerShell::bind(&globals, &qh)
-        .context("wlr-layer-shell not available. Your compositor must support the wlr_layer_shell_v1 protocol.")?;
+    let shell = match config.window.backend {
+        Backend::Layer => Shell::Layer(LayerShell::bind(&globals, &qh).context(
+            "wlr-layer-shell not available. Your compositor must support the wlr_layer_shell_v1 protocol, \
+             or set backend = \"xdg\" in [window] to run clockie as a normal window.",
+        )?),
+        Backend::Xdg => Shell::Xdg(XdgShell::bind(&globals, &qh).context("xdg_wm_base not available")?),
+    };
     let shm = Shm::bind(&globals, &qh)
         .context("wl_shm not available")?;
 
     let surface = compositor.create_surface(&qh);
 
-    // Parse layer
-    let layer = match config.window.layer.as_str() {
-        "background" => Layer::Background,
-        "bottom" => Layer::Bottom,
-        "top" => Layer::Top,
-        "overlay" => Layer::Overlay,
-        _ => Layer::Top,
-    };
-
-    let layer_surface = layer_shell.create_layer_surface(&qh, surface, layer, Some("clockie"), None);
+    let fractional_scaling = FractionalScaling::bind(&globals, &qh);
+    let cursor_shape_manager = CursorShapeManager::bind(&globals, &qh).ok();
+    if cursor_shape_manager.is_none() {
+        log::info!("wp_cursor_shape_v1 not available, hover cursor will not change");
+    }
+    let surface_scale = fractional_scaling.as_ref().map(|f| f.create(&qh, &surface));
 
     // Compute initial size from content
     let compact = config.window.compact;
-    let font = FontState::new(&config.clock.font);
-    let (init_w, init_h) = renderer::compute_size(&config, &font, compact);
-
-    // Set size
-    layer_surface.set_size(init_w, init_h);
-
-    // Parse and set anchor
-    let mut anchor = Anchor::empty();
-    for part in config.window.anchor.split_whitespace() {
-        match part.to_lowercase().as_str() {
-            "top" => anchor |= Anchor::TOP,
-            "bottom" => anchor |= Anchor::BOTTOM,
-            "left" => anchor |= Anchor::LEFT,
-            "right" => anchor |= Anchor::RIGHT,
-            _ => {}
-        }
-    }
-    layer_surface.set_anchor(anchor);
-
-    // Set margins
-    layer_surface.set_margin(
-        config.window.margin_top,
-        config.window.margin_right,
-        config.window.margin_bottom,
-        config.window.margin_left,
-    );
-
-    // No exclusive zone
-    layer_surface.set_exclusive_zone(0);
+    let font = FontState::new(&config.clock.font)?;
+    // The output isn't known yet; output size mode resizes on surface enter
+    let (init_w, init_h) = renderer::compute_size(&config, &font, compact, None);
 
-    // No keyboard grab
-    layer_surface.set_keyboard_interactivity(KeyboardInteractivity::None);
+    let anchor = parse_anchor(&config.window.anchor);
 
-    // Commit initial state
-    layer_surface.commit();
+    // Create the shell surface and commit its initial state
+    let shell_surface = ClockSurface::create(&shell, &qh, surface, &config.window, anchor, None, (init_w, init_h));
 
     let pool = SlotPool::new(
         (init_w * init_h * 4) as usize,
@@ -254,11 +564,10 @@ pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<Pa
     ).context("Failed to create SHM pool")?;
 
     // IPC setup
-    let ipc_socket_path = ipc::socket_path(socket_override.as_ref());
-    let ipc_listener = ipc::create_listener(&ipc_socket_path)?;
+    let ipc_listener = ipc::create_listener(&ipc_endpoint)?;
 
     let pending_output_move = config.window.output.clone();
-    let initial_fg_color = config.theme.fg_color;
+    let initial_contrast = ContrastInfo::resolve(&config.theme, None);
     let gallery = GalleryState::from_config(&config);
 
     let mut clockie = Clockie {
@@ -266,11 +575,19 @@ pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<Pa
         seat_state: SeatState::new(&globals, &qh),
         output_state: OutputState::new(&globals, &qh),
         compositor_state: compositor,
-        layer_shell,
+        shell,
         shm,
         pool,
-        layer_surface,
+        buffers: Vec::new(),
+        prev_frame: Vec::new(),
+        full_damage: true,
+        shell_surface,
         current_output: None,
+        fractional_scaling,
+        surface_scale,
+        scale_120: 120,
+        buffer_scale: 1,
+        buffer_transform: wl_output::Transform::Normal,
         width: init_w,
         height: init_h,
         configured: false,
@@ -279,18 +596,46 @@ pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<Pa
         config_path,
         compact,
         font,
+        content_scale: 1.0,
         pointer: None,
+        cursor_shape_manager,
+        cursor_shape_device: None,
+        pointer_enter_serial: None,
         locked: false,
+        hidden: false,
+        fullscreen: false,
         dragging: false,
+        drag_active: false,
         drag_start: (0.0, 0.0),
         drag_margins: (0, 0, 0, 0),
+        drag_unpinned: Anchor::empty(),
         anchor,
         ipc_listener,
-        ipc_socket_path,
+        ipc_endpoint,
         pending_output_move,
+        output_lost: false,
+        displaced_from: None,
         gallery,
+        background_animation: BackgroundAnimation::new(),
         contrast_dirty: true,
-        cached_text_color: initial_fg_color,
+        cached_contrast: initial_contrast,
+        cached_elements: ElementContrast::uniform(initial_contrast),
+        started: std::time::Instant::now(),
+        battery: None,
+        last_charge_frame: std::time::Instant::now(),
+        last_sweep_frame: std::time::Instant::now(),
+        last_flash_frame: std::time::Instant::now(),
+        flash_drawn: false,
+        pointer_inside: false,
+        last_pointer_activity: std::time::Instant::now(),
+        idle_dim: 0.0,
+        last_dim_frame: std::time::Instant::now(),
+        dim_fade_running: false,
+        hooks: HookRunner::default(),
+        last_hook_hour: local_hour(),
+        lock_toggled_at: None,
+        alarms: Vec::new(),
+        alarm_went_off: None,
         should_quit: false,
     };
 
@@ -303,31 +648,38 @@ pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<Pa
         clockie.apply_pending_output_move(&qh);
     }
 
-    // Signal handling
+    // Signal handling: the handler also writes to a wake pipe so a long poll returns promptly
     let running = Arc::new(AtomicBool::new(true));
+    let (wake_rx, mut wake_tx) = UnixStream::pair().context("Failed to create wake pipe")?;
     {
         let r = running.clone();
         ctrlc::set_handler(move || {
             r.store(false, Ordering::SeqCst);
+            let _ = wake_tx.write_all(&[1]);
         }).expect("Failed to set signal handler");
     }
 
     // Main event loop
-    let mut last_second = 0u32;
+    let mut last_tick = 0i64;
 
     loop {
         if clockie.should_quit || !running.load(Ordering::SeqCst) {
             break;
         }
 
-        // Dispatch Wayland events (blocking with timeout)
+        // Dispatch Wayland events, sleeping until the next timer is due
+        // (IPC connections and signals also wake the poll)
         event_queue.flush()?;
         if let Some(guard) = event_queue.prepare_read() {
-            // Use a short timeout so we can check the timer
-            let fd = guard.connection_fd();
-            let mut fds = [nix::poll::PollFd::new(fd, nix::poll::PollFlags::POLLIN)];
-            let _ = nix::poll::poll(&mut fds, nix::poll::PollTimeout::from(100u16));
-            if fds[0].revents().map_or(false, |r| r.contains(nix::poll::PollFlags::POLLIN)) {
+            let timeout = nix::poll::PollTimeout::try_from(clockie.poll_timeout())
+                .unwrap_or(nix::poll::PollTimeout::MAX);
+            let mut fds = [
+                nix::poll::PollFd::new(guard.connection_fd(), nix::poll::PollFlags::POLLIN),
+                nix::poll::PollFd::new(clockie.ipc_listener.as_fd(), nix::poll::PollFlags::POLLIN),
+                nix::poll::PollFd::new(wake_rx.as_fd(), nix::poll::PollFlags::POLLIN),
+            ];
+            let _ = nix::poll::poll(&mut fds, timeout);
+            if fds[0].revents().is_some_and(|r| r.contains(nix::poll::PollFlags::POLLIN)) {
                 guard.read()?;
             } else {
                 drop(guard);
@@ -338,14 +690,69 @@ pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<Pa
         // Check for IPC connections
         clockie.poll_ipc(&qh);
 
-        // 1Hz timer: check if second changed
-        let now = chrono::Local::now();
-        let current_second = chrono::Timelike::second(&now);
-        if current_second != last_second {
-            last_second = current_second;
+        // Clock timer: redraw when the displayed second (or minute) changes
+        let tick = clockie.current_tick();
+        if tick != last_tick {
+            last_tick = tick;
             clockie.needs_redraw = true;
         }
 
+        // Animated background frame timer
+        if clockie.background_animation.advance() {
+            clockie.needs_redraw = true;
+        }
+
+        // Charging animation frame timer
+        if clockie.charge_animating() && clockie.last_charge_frame.elapsed() >= clockie.config.window.frame_interval() {
+            clockie.last_charge_frame = std::time::Instant::now();
+            clockie.needs_redraw = true;
+        }
+
+        // Second hand easing frame timer
+        if clockie.second_hand_moving() && clockie.last_sweep_frame.elapsed() >= clockie.config.window.frame_interval() {
+            clockie.last_sweep_frame = std::time::Instant::now();
+            clockie.needs_redraw = true;
+        }
+
+        // Hour hook: the loop wakes on every second or minute boundary, so this runs on time
+        let hour = local_hour();
+        if hour != clockie.last_hook_hour {
+            clockie.last_hook_hour = hour;
+            clockie.hooks.run("on_hour", &clockie.config.hooks.on_hour);
+        }
+
+        // Alarms set over IPC; checked every wakeup, which covers each minute
+        clockie.check_alarms();
+
+        // Hour and alarm flashes: redraw every frame while they fade, then once more to clear them
+        if clockie.flashing() {
+            if clockie.last_flash_frame.elapsed() >= clockie.config.window.frame_interval() {
+                clockie.last_flash_frame = std::time::Instant::now();
+                clockie.needs_redraw = true;
+            }
+        } else if clockie.flash_drawn {
+            clockie.needs_redraw = true;
+        }
+
+        // Idle dimming: fade out once the pointer has been away long enough, and back in on enter
+        if !clockie.dim_fading() {
+            clockie.dim_fade_running = false;
+        } else if !clockie.dim_fade_running {
+            // A fade is starting: time its first step from now, not from the last fade
+            clockie.dim_fade_running = true;
+            clockie.last_dim_frame = std::time::Instant::now();
+        } else if clockie.last_dim_frame.elapsed() >= clockie.config.window.frame_interval() {
+            clockie.advance_idle_dim();
+            clockie.needs_redraw = true;
+        }
+
+        // Gallery schedule: switch image sets as time slots start and end
+        // (the loop wakes at least once a minute, so a slot starts on time)
+        if clockie.gallery.apply_schedule(chrono::Local::now()) {
+            clockie.needs_redraw = true;
+            clockie.contrast_dirty = true;
+        }
+
         // Gallery auto-rotate timer
         if clockie.gallery.rotate_active
             && clockie.gallery.rotate_interval > Duration::ZERO
@@ -364,11 +771,35 @@ pub fn run(config: ClockConfig, config_path: PathBuf, socket_override: Option<Pa
     }
 
     // Cleanup
-    ipc::cleanup_socket(&clockie.ipc_socket_path);
+    ipc::cleanup_socket(&clockie.ipc_endpoint);
 
     Ok(())
 }
 
+/// Pointer travel (logical px) before a press becomes a drag.
+const DRAG_THRESHOLD: f64 = 4.0;
+
+/// How long the idle dim takes to fade fully in or out.
+const IDLE_FADE: Duration = Duration::from_millis(400);
+
+/// Buffers kept for drawing, so one is free while the compositor reads the other.
+const BUFFER_COUNT: usize = 2;
+
+/// Parse an anchor string like "top right"; unknown words are ignored.
+fn parse_anchor(anchor: &str) -> Anchor {
+    let mut parsed = Anchor::empty();
+    for part in anchor.split_whitespace() {
+        match part.to_lowercase().as_str() {
+            "top" => parsed |= Anchor::TOP,
+            "bottom" => parsed |= Anchor::BOTTOM,
+            "left" => parsed |= Anchor::LEFT,
+            "right" => parsed |= Anchor::RIGHT,
+            _ => {}
+        }
+    }
+    parsed
+}
+
 /// Format an Anchor bitfield back to a string like "top right".
 fn format_anchor(anchor: Anchor) -> String {
     let mut parts = Vec::new();
@@ -389,6 +820,262 @@ enum Direction {
 }
 
 impl Clockie {
+    /// Whether anything on screen shows seconds, requiring 1Hz redraws.
+    /// The analogue face always has a second hand; otherwise the sub-clocks
+    /// follow `subclock_show_seconds`, and the main time `show_seconds` unless
+    /// `time_format` is set.
+    fn needs_second_ticks(&self) -> bool {
+        let clock = &self.config.clock;
+        if clock.face == FaceMode::Analogue {
+            return true;
+        }
+        if clock.effective_secondary_line().is_some_and(time_utils::format_shows_seconds) {
+            return true;
+        }
+        if clock.subclock_seconds() && !self.config.timezone.is_empty() {
+            return true;
+        }
+        match clock.effective_time_format() {
+            Some(fmt) => time_utils::format_shows_seconds(fmt),
+            None => clock.show_seconds,
+        }
+    }
+
+    /// A counter that changes whenever the displayed time does: Unix seconds,
+    /// or Unix minutes when no seconds are shown.
+    fn current_tick(&self) -> i64 {
+        let now = chrono::Utc::now().timestamp();
+        if self.needs_second_ticks() { now } else { now.div_euclid(60) }
+    }
+
+    /// How long the event loop may sleep before a timer needs servicing:
+    /// the next clock tick, animation frame, or gallery rotation.
+    fn poll_timeout(&self) -> Duration {
+        let now = chrono::Utc::now();
+        let millis_into_tick = if self.needs_second_ticks() {
+            now.timestamp_subsec_millis() as u64
+        } else {
+            now.timestamp().rem_euclid(60) as u64 * 1000 + now.timestamp_subsec_millis() as u64
+        };
+        let tick_len = if self.needs_second_ticks() { 1000 } else { 60_000 };
+        // Land just past the boundary so the tick has definitely changed
+        let mut timeout = Duration::from_millis(tick_len - millis_into_tick + 1);
+
+        // Animations wake at most once per tick_ms; late background frames are skipped
+        let frame_interval = self.config.window.frame_interval();
+        if let Some(frame) = self.background_animation.time_to_next_frame() {
+            timeout = timeout.min(frame.max(frame_interval));
+        }
+        if self.charge_animating() {
+            timeout = timeout.min(frame_interval.saturating_sub(self.last_charge_frame.elapsed()));
+        }
+        if self.second_hand_moving() {
+            timeout = timeout.min(frame_interval.saturating_sub(self.last_sweep_frame.elapsed()));
+        }
+        if self.flashing() {
+            timeout = timeout.min(frame_interval.saturating_sub(self.last_flash_frame.elapsed()));
+        }
+        if self.dim_fading() {
+            timeout = timeout.min(frame_interval.saturating_sub(self.last_dim_frame.elapsed()));
+        } else if let Some(idle_in) = self.time_to_idle() {
+            timeout = timeout.min(idle_in);
+        }
+        if self.gallery.rotate_active && self.gallery.rotate_interval > Duration::ZERO {
+            timeout = timeout.min(self.gallery.rotate_interval.saturating_sub(self.gallery.last_rotate.elapsed()));
+        }
+        timeout
+    }
+
+    /// The current battery state, cached for a second so animated redraws
+    /// don't hit sysfs every frame.
+    fn battery_info(&mut self) -> Option<crate::battery::BatteryInfo> {
+        if !self.config.battery.enabled {
+            self.battery = None;
+            return None;
+        }
+        match &self.battery {
+            Some((read_at, info)) if read_at.elapsed() < Duration::from_secs(1) => info.clone(),
+            _ => {
+                let info = crate::battery::read_battery();
+                self.battery = Some((std::time::Instant::now(), info.clone()));
+                info
+            }
+        }
+    }
+
+    /// Switch the main time and sub-clocks between 12- and 24-hour display.
+    /// The AM/PM suffix changes the width, so the window is resized.
+    fn set_hour_format(&mut self, format: u8) {
+        self.config.clock.hour_format = format;
+        self.update_size();
+        config::save_hour_format_to_config(&self.config_path, format);
+    }
+
+    /// Load the configured font after a reload. A failure keeps the current
+    /// font rather than taking the daemon down or falling back mid-session.
+    fn reload_font(&mut self) {
+        let name = &self.config.clock.font;
+        let loaded = if FontState::is_path(name) {
+            FontState::from_path(name).ok_or_else(|| anyhow::anyhow!("cannot load {}", name))
+        } else {
+            FontState::new(name)
+        };
+        match loaded {
+            Ok(font) => self.font = font,
+            Err(e) => log::error!("Font {:?} failed to load, keeping the previous font: {}", name, e),
+        }
+    }
+
+    /// Whether the charging animation is running, which needs sub-second redraws.
+    fn charge_animating(&self) -> bool {
+        self.config.battery.enabled
+            && renderer::battery::animates_charge(&self.config)
+            && self.battery.as_ref().is_some_and(|(_, info)| info.as_ref().is_some_and(|b| b.charging))
+            && self.visible()
+    }
+
+    /// Whether an eased analogue second hand is between ticks, which needs sub-second redraws.
+    fn second_hand_moving(&self) -> bool {
+        self.config.clock.face == FaceMode::Analogue
+            && self.visible()
+            && renderer::analogue::second_hand_moving(self.config.analogue.sweep_easing, chrono::Utc::now().timestamp_subsec_millis())
+    }
+
+    /// Whether the hour, alarm or lock flash is fading right now, which needs sub-second redraws.
+    fn flashing(&self) -> bool {
+        self.visible()
+            && (self.alarm_flash().is_some()
+                || self.lock_flash().is_some()
+                || renderer::hour_flash_strength(&self.config, &time_utils::current_time("", None, None, chrono::Locale::POSIX)).is_some())
+    }
+
+    /// How long until the clock counts as idle, while the pointer is away and
+    /// `idle_dim_after` is set. Zero once it is idle.
+    fn time_to_idle(&self) -> Option<Duration> {
+        let after = self.config.window.idle_dim_after;
+        if after == 0 || self.pointer_inside {
+            return None;
+        }
+        Some(Duration::from_secs(after).saturating_sub(self.last_pointer_activity.elapsed()))
+    }
+
+    /// Whether the idle dim is fading in or out, which needs sub-second redraws.
+    fn dim_fading(&self) -> bool {
+        let idle = self.time_to_idle() == Some(Duration::ZERO);
+        self.visible() && self.idle_dim != if idle { 1.0 } else { 0.0 }
+    }
+
+    /// Move the idle dim one frame toward fully dimmed or fully bright.
+    fn advance_idle_dim(&mut self) {
+        let step = self.last_dim_frame.elapsed().min(IDLE_FADE).as_secs_f32() / IDLE_FADE.as_secs_f32();
+        self.last_dim_frame = std::time::Instant::now();
+        if self.time_to_idle() == Some(Duration::ZERO) {
+            self.idle_dim = (self.idle_dim + step).min(1.0);
+        } else {
+            self.idle_dim = (self.idle_dim - step).max(0.0);
+        }
+    }
+
+    /// Window opacity, eased toward `idle_opacity` while the clock is idle.
+    fn opacity(&self) -> f32 {
+        let window = &self.config.window;
+        window.opacity + (window.idle_opacity - window.opacity) * self.idle_dim
+    }
+
+    /// Strength of the flash for the alarm that last went off, while it lasts.
+    fn alarm_flash(&self) -> Option<f32> {
+        self.alarm_went_off.and_then(|at| renderer::alarm_flash_strength(at.elapsed()))
+    }
+
+    /// Set a one-shot alarm for the next time the local clock reads `at`.
+    fn set_alarm(&mut self, at: NaiveTime) {
+        if !self.alarms.contains(&at) {
+            self.alarms.push(at);
+            self.alarms.sort();
+        }
+        self.needs_redraw = true;
+        log::info!("Alarm set for {}", at.format("%H:%M"));
+    }
+
+    /// Fire and clear alarms due this minute: flash the clock and run `on_alarm`.
+    fn check_alarms(&mut self) {
+        let now = chrono::Local::now().time();
+        let due = |alarm: &NaiveTime| alarm.hour() == now.hour() && alarm.minute() == now.minute();
+        if !self.alarms.iter().any(due) {
+            return;
+        }
+        self.alarms.retain(|alarm| !due(alarm));
+        log::info!("Alarm at {}", now.format("%H:%M"));
+        self.alarm_went_off = Some(Instant::now());
+        self.needs_redraw = true;
+        self.hooks.run("on_alarm", &self.config.hooks.on_alarm);
+    }
+
+    /// Show a move cursor while hovering an unlocked clock, the default one otherwise.
+    /// Needs the serial of the pointer's last enter event on our surface.
+    fn update_cursor(&self) {
+        if let (Some(device), Some(serial)) = (&self.cursor_shape_device, self.pointer_enter_serial) {
+            let shape = if self.locked { CursorShape::Default } else { CursorShape::Move };
+            device.set_shape(serial, shape);
+        }
+    }
+
+    /// Lock or unlock dragging, flashing a padlock to confirm a change.
+    fn set_locked(&mut self, locked: bool) {
+        if locked != self.locked {
+            self.lock_toggled_at = Some(std::time::Instant::now());
+            self.needs_redraw = true;
+        }
+        self.locked = locked;
+        self.update_cursor();
+    }
+
+    /// Opacity of the padlock confirming the last lock toggle, while it shows.
+    fn lock_flash(&self) -> Option<f32> {
+        self.lock_toggled_at.and_then(|at| renderer::lock::flash_alpha(at.elapsed()))
+    }
+
+    /// The padlock to draw: the fading confirmation of a toggle, or the faint
+    /// `show_lock_indicator` one while locked outside compact mode.
+    fn lock_glyph(&self) -> Option<LockGlyph> {
+        let indicator = self.locked && self.config.window.show_lock_indicator && !self.compact;
+        let resting = indicator.then_some(renderer::lock::INDICATOR_ALPHA);
+        let alpha = match (self.lock_flash(), resting) {
+            (Some(flash), Some(resting)) => flash.max(resting),
+            (flash, resting) => flash.or(resting)?,
+        };
+        Some(LockGlyph { locked: self.locked, alpha })
+    }
+
+    /// Whether the clock should currently be shown.
+    fn visible(&self) -> bool {
+        let fullscreen_hides = self.fullscreen && self.config.window.hide_when_fullscreen;
+        !self.hidden && !fullscreen_hides
+    }
+
+    /// Map or unmap the surface after a visibility change. Hiding attaches a
+    /// null buffer; showing commits so the compositor re-configures the
+    /// surface before the next frame is attached.
+    fn apply_visibility(&mut self, was_visible: bool) {
+        let visible = self.visible();
+        if visible == was_visible {
+            return;
+        }
+        let surface = self.shell_surface.wl_surface();
+        if visible {
+            surface.commit();
+            self.needs_redraw = true;
+        } else {
+            surface.attach(None, 0, 0);
+            surface.commit();
+            self.configured = false;
+            self.buffers.clear();
+            self.prev_frame.clear();
+            self.full_damage = true;
+        }
+        log::info!("Clock {}", if visible { "shown" } else { "hidden" });
+    }
+
     /// Get the name of the current output, if known.
     fn get_output_name(&self) -> Option<String> {
         self.current_output.as_ref().and_then(|wl_out| {
@@ -396,41 +1083,49 @@ impl Clockie {
         })
     }
 
-    /// Recreate the layer surface on a different output.
+    /// Remember the current anchor and margins for the current output.
+    fn remember_position(&mut self) {
+        let Some(name) = self.get_output_name() else { return };
+        let position = self.config.window.position();
+        if self.config.window.positions.get(&name) == Some(&position) {
+            return;
+        }
+        config::save_output_position_to_config(&self.config_path, &name, &position);
+        self.config.window.positions.insert(name, position);
+    }
+
+    /// Take the anchor and margins last used on `output`, when some were saved.
+    /// Returns whether the position changed; the caller applies it.
+    fn restore_position(&mut self, output: &wl_output::WlOutput) -> bool {
+        let Some(name) = self.output_state.info(output).and_then(|info| info.name) else { return false };
+        let Some(position) = self.config.window.positions.get(&name).cloned() else { return false };
+        if position == self.config.window.position() {
+            return false;
+        }
+        log::info!("Restoring the position saved for {}", name);
+        self.config.window.set_position(&position);
+        self.anchor = parse_anchor(&position.anchor);
+        true
+    }
+
+    /// Recreate the shell surface, on a different output when the shell allows it.
     fn recreate_surface(&mut self, qh: &QueueHandle<Self>, target_output: Option<&wl_output::WlOutput>) {
-        // Parse layer
-        let layer = match self.config.window.layer.as_str() {
-            "background" => Layer::Background,
-            "bottom" => Layer::Bottom,
-            "top" => Layer::Top,
-            "overlay" => Layer::Overlay,
-            _ => Layer::Top,
-        };
+        // In output size mode the new output decides the size
+        self.current_output = target_output.cloned();
+        (self.width, self.height) = self.window_size();
 
-        // Create new surface
+        // Create new surface, configured identically
         let surface = self.compositor_state.create_surface(qh);
-        let new_layer_surface = self.layer_shell.create_layer_surface(
-            qh, surface, layer, Some("clockie"), target_output,
+        self.surface_scale = self.fractional_scaling.as_ref().map(|f| f.create(qh, &surface));
+        let new_shell_surface = ClockSurface::create(
+            &self.shell, qh, surface, &self.config.window, self.anchor, target_output, (self.width, self.height),
         );
 
-        // Configure identically
-        new_layer_surface.set_size(self.width, self.height);
-        new_layer_surface.set_anchor(self.anchor);
-        new_layer_surface.set_margin(
-            self.config.window.margin_top,
-            self.config.window.margin_right,
-            self.config.window.margin_bottom,
-            self.config.window.margin_left,
-        );
-        new_layer_surface.set_exclusive_zone(0);
-        new_layer_surface.set_keyboard_interactivity(KeyboardInteractivity::None);
-        new_layer_surface.commit();
-
         // Replace old surface (dropping it destroys the old one)
-        self.layer_surface = new_layer_surface;
-        self.current_output = target_output.cloned();
+        self.shell_surface = new_shell_surface;
         self.configured = false;
         self.needs_redraw = true;
+        self.full_damage = true;
 
         log::info!("Recreated surface on output: {:?}", self.get_output_name());
     }
@@ -475,7 +1170,7 @@ impl Clockie {
                     Direction::Right | Direction::Left => ((oy + oh / 2) - (cy + ch / 2)).abs(),
                     Direction::Up | Direction::Down => ((ox + ow / 2) - (cx + cw / 2)).abs(),
                 };
-                if best.as_ref().map_or(true, |(_, d)| dist < *d) {
+                if best.as_ref().is_none_or(|(_, d)| dist < *d) {
                     best = Some((wl_output, dist));
                 }
             }
@@ -519,8 +1214,11 @@ impl Clockie {
     /// Apply a pending output move (used at startup).
     fn apply_pending_output_move(&mut self, qh: &QueueHandle<Self>) {
         if let Some(name) = self.pending_output_move.take() {
-            if let Some(target) = self.find_output_by_name(&name) {
+            if !self.shell_surface.is_layer() {
+                log::info!("Ignoring output '{}': the xdg backend lets the compositor place the window", name);
+            } else if let Some(target) = self.find_output_by_name(&name) {
                 log::info!("Moving to configured output: {}", name);
+                self.restore_position(&target);
                 self.recreate_surface(qh, Some(&target));
             } else {
                 log::warn!("Configured output '{}' not found, staying on default", name);
@@ -528,8 +1226,49 @@ impl Clockie {
         }
     }
 
-    /// Recompute window size from content and apply if changed.
-    /// Clamps margins so the window stays within the current output.
+    /// Move the surface to a remaining output after ours went away, preferring
+    /// the configured output, and persist the one it lands on. With no outputs
+    /// left, drawing pauses until `new_output` brings one back.
+    fn recover_surface(&mut self, qh: &QueueHandle<Self>, gone: Option<&wl_output::WlOutput>) {
+        let remaining: Vec<_> = self.output_state.outputs()
+            .filter(|o| Some(o) != gone)
+            .collect();
+        let preferred = self.config.window.output.as_deref().and_then(|name| {
+            remaining.iter().find(|o| {
+                self.output_state.info(o).and_then(|info| info.name).as_deref() == Some(name)
+            })
+        });
+        // Remember the output we were moved off, for `return_to_output`; when the
+        // compositor only closed the surface, that is the configured one if it went away
+        let gone_name = gone.and_then(|o| self.output_state.info(o)).and_then(|info| info.name);
+        if let Some(name) = gone_name.or_else(|| preferred.is_none().then(|| self.config.window.output.clone()).flatten()) {
+            self.displaced_from = Some(name);
+        }
+        match preferred.or(remaining.first()).cloned() {
+            Some(target) => {
+                self.output_lost = false;
+                self.move_to_output(qh, &target);
+            }
+            None => {
+                log::warn!("No outputs left, pausing until one is connected");
+                self.output_lost = true;
+                self.configured = false;
+                self.current_output = None;
+            }
+        }
+    }
+
+    /// Recreate the surface on `output` at its saved position and persist it
+    /// as the configured output.
+    fn move_to_output(&mut self, qh: &QueueHandle<Self>, output: &wl_output::WlOutput) {
+        self.restore_position(output);
+        self.recreate_surface(qh, Some(output));
+        if let Some(name) = self.output_state.info(output).and_then(|info| info.name) {
+            config::save_output_to_config(&self.config_path, &name);
+            self.config.window.output = Some(name);
+        }
+    }
+
     fn save_gallery_indices(&self) {
         config::save_gallery_indices_to_config(
             &self.config_path,
@@ -538,37 +1277,114 @@ impl Clockie {
         );
     }
 
+    /// Window size for the current config and output, updating the content
+    /// scale used to fill it in output size mode.
+    fn window_size(&mut self) -> (u32, u32) {
+        let (out_w, out_h) = self.output_size();
+        let output = (out_w > 0 && out_h > 0).then_some((out_w as u32, out_h as u32));
+        let (w, h) = renderer::compute_size(&self.config, &self.font, self.compact, output);
+        self.content_scale = renderer::render_scale(&self.config, &self.font, self.compact, w, h);
+        (w, h)
+    }
+
+    /// Recompute window size from content and apply if changed.
+    /// Clamps margins so the window stays within the current output.
     fn update_size(&mut self) {
-        let (new_w, new_h) = renderer::compute_size(&self.config, &self.font, self.compact);
+        let (new_w, new_h) = self.window_size();
         if new_w != self.width || new_h != self.height {
             self.width = new_w;
             self.height = new_h;
-            self.layer_surface.set_size(self.width, self.height);
+            self.shell_surface.set_size(self.width, self.height);
 
             // Clamp margins so the window doesn't overflow the output
-            self.clamp_margins();
-
-            self.layer_surface.set_margin(
-                self.config.window.margin_top,
-                self.config.window.margin_right,
-                self.config.window.margin_bottom,
-                self.config.window.margin_left,
-            );
-            self.layer_surface.wl_surface().commit();
+            self.apply_margins();
+            self.shell_surface.wl_surface().commit();
         }
+        // Text regions move with layout changes, so resample contrast
+        self.contrast_dirty = true;
         self.needs_redraw = true;
     }
 
-    /// Clamp margins so the window fits within the current output bounds.
-    fn clamp_margins(&mut self) {
-        let (out_w, out_h) = self.current_output.as_ref()
+    /// Logical size of the current output, or (0, 0) when it isn't known yet.
+    fn output_size(&self) -> (i32, i32) {
+        self.current_output.as_ref()
             .and_then(|o| self.output_state.info(o))
             .and_then(|info| info.logical_size)
-            .unwrap_or((0, 0));
+            .unwrap_or((0, 0))
+    }
 
-        if out_w == 0 || out_h == 0 {
+    /// A clock anchored to both edges of an axis is centred and can't be moved
+    /// along it. Re-anchor such axes to the left/top edge at the current
+    /// position so a drag can move it; the drop picks the nearer edge.
+    fn unpin_centered_axes(&mut self) {
+        let (out_w, out_h) = self.output_size();
+        let w = &mut self.config.window;
+        let mut released = Anchor::empty();
+        if self.anchor.contains(Anchor::LEFT | Anchor::RIGHT) && out_w > 0 {
+            let x = w.margin_left + (out_w - w.margin_left - w.margin_right - self.width as i32) / 2;
+            w.margin_left = x.max(0);
+            w.margin_right = 0;
+            released |= Anchor::LEFT | Anchor::RIGHT;
+            self.anchor.remove(Anchor::RIGHT);
+        }
+        if self.anchor.contains(Anchor::TOP | Anchor::BOTTOM) && out_h > 0 {
+            let y = w.margin_top + (out_h - w.margin_top - w.margin_bottom - self.height as i32) / 2;
+            w.margin_top = y.max(0);
+            w.margin_bottom = 0;
+            released |= Anchor::TOP | Anchor::BOTTOM;
+            self.anchor.remove(Anchor::BOTTOM);
+        }
+        if released.is_empty() {
             return;
         }
+        self.drag_unpinned = released;
+        self.drag_margins = (w.margin_top, w.margin_right, w.margin_bottom, w.margin_left);
+        self.shell_surface.set_anchor(self.anchor);
+        self.shell_surface.set_margin(w.margin_top, w.margin_right, w.margin_bottom, w.margin_left);
+    }
+
+    /// After dropping an unpinned clock, anchor each released axis to the edge
+    /// nearer the clock's centre and persist the new anchor.
+    fn settle_unpinned_axes(&mut self) {
+        if self.drag_unpinned.is_empty() {
+            return;
+        }
+        let (out_w, out_h) = self.output_size();
+        let w = &mut self.config.window;
+        if self.drag_unpinned.contains(Anchor::LEFT) && self.anchor.contains(Anchor::LEFT)
+            && (w.margin_left + self.width as i32 / 2) * 2 > out_w
+        {
+            w.margin_right = (out_w - w.margin_left - self.width as i32).max(0);
+            w.margin_left = 0;
+            self.anchor = (self.anchor - Anchor::LEFT) | Anchor::RIGHT;
+        }
+        if self.drag_unpinned.contains(Anchor::TOP) && self.anchor.contains(Anchor::TOP)
+            && (w.margin_top + self.height as i32 / 2) * 2 > out_h
+        {
+            w.margin_bottom = (out_h - w.margin_top - self.height as i32).max(0);
+            w.margin_top = 0;
+            self.anchor = (self.anchor - Anchor::TOP) | Anchor::BOTTOM;
+        }
+        self.drag_unpinned = Anchor::empty();
+        w.anchor = format_anchor(self.anchor);
+        self.shell_surface.set_anchor(self.anchor);
+        self.shell_surface.set_margin(w.margin_top, w.margin_right, w.margin_bottom, w.margin_left);
+        self.shell_surface.wl_surface().commit();
+        config::save_anchor_to_config(&self.config_path, &w.anchor);
+    }
+
+    /// The configured margins, pulled in where the window would overflow the
+    /// current output. They are returned unchanged while the output's size is
+    /// unknown. The configured margins themselves are never rewritten, so a
+    /// clamp against a size that was stale mid hot-plug is undone when
+    /// `update_output` brings the real one.
+    fn clamped_margins(&self) -> (i32, i32, i32, i32) {
+        let w = &self.config.window;
+        let (mut top, mut right, mut bottom, mut left) = (w.margin_top, w.margin_right, w.margin_bottom, w.margin_left);
+        let (out_w, out_h) = self.output_size();
+        if out_w <= 0 || out_h <= 0 {
+            return (top, right, bottom, left);
+        }
 
         let has_left = self.anchor.contains(Anchor::LEFT);
         let has_right = self.anchor.contains(Anchor::RIGHT);
@@ -576,130 +1392,212 @@ impl Clockie {
         let has_bottom = self.anchor.contains(Anchor::BOTTOM);
 
         // Horizontal: margin_left + width + margin_right <= output_width
+        let max = (out_w as u32).saturating_sub(self.width) as i32;
         if has_left && !has_right {
-            let max = (out_w as u32).saturating_sub(self.width) as i32;
-            self.config.window.margin_left = self.config.window.margin_left.clamp(0, max);
+            left = left.clamp(0, max);
         } else if has_right && !has_left {
-            let max = (out_w as u32).saturating_sub(self.width) as i32;
-            self.config.window.margin_right = self.config.window.margin_right.clamp(0, max);
+            right = right.clamp(0, max);
         }
 
         // Vertical: margin_top + height + margin_bottom <= output_height
+        let max = (out_h as u32).saturating_sub(self.height) as i32;
         if has_top && !has_bottom {
-            let max = (out_h as u32).saturating_sub(self.height) as i32;
-            self.config.window.margin_top = self.config.window.margin_top.clamp(0, max);
+            top = top.clamp(0, max);
         } else if has_bottom && !has_top {
-            let max = (out_h as u32).saturating_sub(self.height) as i32;
-            self.config.window.margin_bottom = self.config.window.margin_bottom.clamp(0, max);
+            bottom = bottom.clamp(0, max);
         }
+        (top, right, bottom, left)
+    }
+
+    /// Set the surface's margins to the clamped ones; the caller commits.
+    fn apply_margins(&mut self) {
+        let (top, right, bottom, left) = self.clamped_margins();
+        self.shell_surface.set_margin(top, right, bottom, left);
     }
 
     fn draw(&mut self, qh: &QueueHandle<Self>) {
         let width = self.width;
         let height = self.height;
 
-        if width == 0 || height == 0 { return; }
+        if width == 0 || height == 0 || !self.visible() { return; }
 
-        let stride = width as i32 * 4;
-        let buf_size = (stride * height as i32) as usize;
-
-        // Ensure pool is big enough
-        if self.pool.len() < buf_size {
-            self.pool.resize(buf_size).expect("Failed to resize SHM pool");
-        }
-
-        let (buffer, canvas_data) = self.pool
-            .create_buffer(width as i32, height as i32, stride, wl_shm::Format::Argb8888)
-            .expect("Failed to create buffer");
-
-        // Render to canvas
-        let mut canvas = Canvas::new(width, height);
-        let time = time_utils::current_time(&self.config.clock.date_format);
-        let battery = if self.config.battery.enabled {
-            crate::battery::read_battery()
-        } else {
-            None
+        // Render to canvas at the preferred fractional scale, or the integer
+        // buffer scale when the compositor can't do fractional scaling
+        let scale = match self.surface_scale {
+            Some(_) => self.scale_120 as f32 / 120.0,
+            None => self.buffer_scale as f32,
         };
+        let mut canvas = Canvas::with_scale(width, height, scale);
+        canvas.set_antialias(self.config.window.antialias);
+        let time = time_utils::current_time(
+            &self.config.clock.date_format,
+            self.config.clock.effective_time_format(),
+            self.config.clock.effective_secondary_line(),
+            self.config.clock.locale(),
+        );
+        let battery = self.battery_info();
 
         // Patch config with current gallery images
         let mut render_config = self.config.clone();
         render_config.background.digital_image = self.gallery.current_digital_image().to_string();
         render_config.background.analogue_face_image = self.gallery.current_analogue_image().to_string();
+        if self.content_scale != 1.0 {
+            renderer::scale_content(&mut render_config, &self.config, self.content_scale);
+        }
 
-        // Determine if auto-contrast is active
-        let has_gallery = !self.gallery.digital_images.is_empty()
-            || !self.gallery.analogue_images.is_empty();
-        let auto_contrast_active = match render_config.theme.auto_contrast.as_str() {
-            "always" => true,
-            "never" => false,
-            _ /* "auto" */ => has_gallery,
+        // Animated digital backgrounds are decoded once and played back frame by frame;
+        // switching away from the digital face drops the animation so its timer stops
+        let animated_path = match render_config.clock.face {
+            FaceMode::Digital => render_config.background.digital_image.as_str(),
+            FaceMode::Analogue => "",
         };
+        self.background_animation.sync(animated_path);
+        let background_frame = self.background_animation.current_frame();
+
+        // Determine if auto-contrast is active
+        let auto_contrast_active = renderer::auto_contrast_active(&render_config.theme, self.gallery.has_images());
 
         // Phase 1: render background
+        let bg_contrast = ContrastInfo::resolve(&render_config.theme, None);
         let bg_state = ClockState {
             config: render_config.clone(),
             time: time.clone(),
             compact: self.compact,
             battery: battery.clone(),
-            contrast: ContrastInfo {
-                text_color: render_config.theme.fg_color,
-                use_outline: false,
-            },
+            contrast: bg_contrast,
+            elements: ElementContrast::uniform(bg_contrast),
+            background_frame: background_frame.clone(),
+            alarm_pending: !self.alarms.is_empty(),
+            lock_glyph: None,
         };
         renderer::render_background(&mut canvas, &bg_state, &self.font);
 
-        // Phase 2: resolve contrast (sample background luminance if needed)
+        // Phase 2: resolve contrast per text element (sample background luminance if needed)
         if auto_contrast_active && self.contrast_dirty {
-            let lum = crate::canvas::sample_region_luminance(&canvas, 0, 0, width, height);
-            self.cached_text_color = if lum > 140.0 {
-                [0x1a, 0x1a, 0x1a, 0xFF] // dark text for light backgrounds
-            } else {
-                render_config.theme.fg_color
-            };
+            let (global, elements) = renderer::sample_element_contrast(&canvas, &bg_state, &self.font, width, height);
+            self.cached_contrast = global;
+            self.cached_elements = elements;
             self.contrast_dirty = false;
         } else if !auto_contrast_active {
-            self.cached_text_color = render_config.theme.fg_color;
+            self.cached_contrast = bg_contrast;
+            self.cached_elements = ElementContrast::uniform(bg_contrast);
         }
 
-        let text_color = self.cached_text_color;
-        let use_outline = render_config.theme.text_outline;
-
         // Phase 3: render foreground with resolved contrast
         let state = ClockState {
             config: render_config,
             time,
             compact: self.compact,
             battery,
-            contrast: ContrastInfo {
-                text_color,
-                use_outline,
-            },
+            contrast: self.cached_contrast,
+            elements: self.cached_elements.clone(),
+            background_frame,
+            alarm_pending: !self.alarms.is_empty(),
+            lock_glyph: self.lock_glyph(),
         };
         renderer::render_foreground(&mut canvas, &state, &self.font);
-
-        // Apply window opacity (scale all channels — data is premultiplied RGBA)
-        let opacity = self.config.window.opacity;
-        if opacity < 1.0 {
-            let data = canvas.pixmap.data_mut();
-            let scale = (opacity * 255.0) as u32;
-            for i in (0..data.len()).step_by(4) {
-                data[i]     = ((data[i]     as u32 * scale) / 255) as u8;
-                data[i + 1] = ((data[i + 1] as u32 * scale) / 255) as u8;
-                data[i + 2] = ((data[i + 2] as u32 * scale) / 255) as u8;
-                data[i + 3] = ((data[i + 3] as u32 * scale) / 255) as u8;
-            }
+        let alarm_flash = self.alarm_flash();
+        if let Some(strength) = alarm_flash {
+            canvas.brighten(strength);
         }
+        self.flash_drawn = alarm_flash.is_some()
+            || self.lock_flash().is_some()
+            || renderer::hour_flash_strength(&state.config, &state.time).is_some();
+
+        // Apply window opacity, dimmed while idle
+        canvas.apply_opacity(self.opacity());
+
+        // Convert pixels with RGBA→BGRA swizzle, then pre-rotate them to match the
+        // output transform so the compositor can scan the buffer out directly
+        let (dev_w, dev_h) = canvas.device_size();
+        let (pixels, buf_w, buf_h) =
+            transform_pixels(canvas.pixels_argb8888(), dev_w, dev_h, self.buffer_transform);
+
+        // Damage only what changed since the last committed frame
+        let full = self.full_damage || self.prev_frame.len() != pixels.len();
+        let damage = if full {
+            Some((0, 0, buf_w, buf_h))
+        } else {
+            crate::canvas::diff_bounds(&self.prev_frame, &pixels, buf_w, buf_h)
+        };
+        let Some((dx, dy, dw, dh)) = damage else { return };
+
+        let stride = buf_w as i32 * 4;
+        let row_len = buf_w as usize * 4;
+
+        // Buffers of another size are stale; busy ones are destroyed on release
+        self.buffers.retain(|buf| buf.height() == buf_h as i32 && buf.stride() == stride);
+
+        // Draw into a buffer the compositor has released. The last committed one
+        // still holds the previous frame, so only the damaged rows need copying;
+        // an older one gets the whole frame
+        let free = (0..self.buffers.len()).find(|&i| self.buffers[i].canvas(&mut self.pool).is_some());
+        if let Some(i) = free {
+            let buffer = self.buffers.remove(i);
+            if let Some(data) = buffer.canvas(&mut self.pool) {
+                if i == 0 && !full {
+                    let (x0, x1) = (dx as usize * 4, (dx + dw) as usize * 4);
+                    for row in dy as usize..(dy + dh) as usize {
+                        let start = row * row_len;
+                        data[start + x0..start + x1].copy_from_slice(&pixels[start + x0..start + x1]);
+                    }
+                } else {
+                    data[..pixels.len()].copy_from_slice(&pixels);
+                }
+            }
+            self.buffers.insert(0, buffer);
+        } else {
+            // All buffers are busy (or none exist yet): allocate another. A failed
+            // allocation drops this frame rather than the daemon; the next redraw
+            // tries again with full damage
+            let buf_size = (stride * buf_h as i32) as usize;
+            let in_use = self.buffers.len().min(BUFFER_COUNT - 1) + 1;
+
+            // Ensure the pool fits every buffer we keep
+            if self.pool.len() < buf_size * in_use {
+                if let Err(e) = self.pool.resize(buf_size * in_use) {
+                    log::warn!("Failed to resize SHM pool to {} bytes, skipping frame: {}", buf_size * in_use, e);
+                    self.full_damage = true;
+                    return;
+                }
+            }
 
-        // Copy pixels with RGBA→BGRA swizzle
-        let pixels = canvas.pixels_argb8888();
-        canvas_data[..pixels.len()].copy_from_slice(&pixels);
+            let (buffer, canvas_data) = match self.pool
+                .create_buffer(buf_w as i32, buf_h as i32, stride, wl_shm::Format::Argb8888)
+            {
+                Ok(created) => created,
+                Err(e) => {
+                    log::warn!("Failed to create a {}x{} buffer, skipping frame: {}", buf_w, buf_h, e);
+                    self.full_damage = true;
+                    return;
+                }
+            };
+            canvas_data[..pixels.len()].copy_from_slice(&pixels);
+            self.buffers.insert(0, buffer);
+            // Dropping the oldest busy buffer frees its slot once it is released
+            self.buffers.truncate(BUFFER_COUNT);
+        }
 
         // Attach and commit
-        let surface = self.layer_surface.wl_surface();
-        buffer.attach_to(surface).expect("Failed to attach buffer");
-        surface.damage_buffer(0, 0, width as i32, height as i32);
+        let Some(buffer) = self.buffers.first() else { return };
+        let surface = self.shell_surface.wl_surface();
+        if let Err(e) = buffer.attach_to(surface) {
+            log::warn!("Failed to attach buffer, skipping frame: {}", e);
+            self.full_damage = true;
+            return;
+        }
+        surface.set_buffer_transform(self.buffer_transform);
+        // The viewport maps the device-sized buffer back onto the logical surface size
+        match &self.surface_scale {
+            Some(ss) => ss.viewport.set_destination(width as i32, height as i32),
+            None => surface.set_buffer_scale(self.buffer_scale),
+        }
+        surface.damage_buffer(dx as i32, dy as i32, dw as i32, dh as i32);
         surface.frame(qh, surface.clone());
         surface.commit();
+
+        self.prev_frame = pixels;
+        self.full_damage = false;
     }
 
     fn poll_ipc(&mut self, qh: &QueueHandle<Self>) {
@@ -718,17 +1616,23 @@ impl Clockie {
     }
 
     fn handle_ipc_connection(&mut self, stream: std::os::unix::net::UnixStream, qh: &QueueHandle<Self>) {
-        let cmd = match ipc::read_command(&stream) {
-            Ok(cmd) => cmd,
+        let mut framing = ipc::Framing::Line;
+        let response = match ipc::read_command(&stream, &mut framing) {
+            Ok(Some(Ok(cmd))) => self.handle_command(cmd, qh),
+            Ok(None) => {
+                log::debug!("IPC client disconnected without sending a command");
+                return;
+            }
+            Ok(Some(Err(msg))) => {
+                log::warn!("IPC request rejected: {}", msg);
+                ipc::IpcResponse::err(msg)
+            }
             Err(e) => {
                 log::warn!("IPC read error: {}", e);
                 return;
             }
         };
-
-        let response = self.handle_command(cmd, qh);
-        let mut stream = stream;
-        if let Err(e) = ipc::write_response(&mut stream, &response) {
+        if let Err(e) = ipc::write_response(&stream, &response, framing) {
             log::warn!("IPC write error: {}", e);
         }
     }
@@ -771,13 +1675,13 @@ impl Clockie {
                 ipc::IpcResponse::ok()
             }
             ipc::IpcCommand::SetFontSize { size } => {
-                self.config.clock.font_size = size.max(10.0);
+                self.config.clock.font_size = self.config.clock.clamp_font_size(size);
                 self.update_size();
                 config::save_font_size_to_config(&self.config_path, self.config.clock.font_size);
                 ipc::IpcResponse::ok()
             }
             ipc::IpcCommand::SetDiameter { diameter } => {
-                self.config.clock.diameter = diameter.max(40);
+                self.config.clock.diameter = self.config.clock.clamp_diameter(diameter as i64);
                 self.update_size();
                 config::save_diameter_to_config(&self.config_path, self.config.clock.diameter);
                 ipc::IpcResponse::ok()
@@ -785,25 +1689,68 @@ impl Clockie {
             ipc::IpcCommand::ScaleBy { delta } => {
                 match self.config.clock.face {
                     FaceMode::Digital => {
-                        self.config.clock.font_size = (self.config.clock.font_size + delta as f32).max(10.0);
+                        self.config.clock.font_size = self.config.clock.clamp_font_size(self.config.clock.font_size + delta as f32);
                         config::save_font_size_to_config(&self.config_path, self.config.clock.font_size);
                     }
                     FaceMode::Analogue => {
-                        self.config.clock.diameter = (self.config.clock.diameter as i32 + delta).max(40) as u32;
+                        self.config.clock.diameter = self.config.clock.clamp_diameter(self.config.clock.diameter as i64 + delta as i64);
                         config::save_diameter_to_config(&self.config_path, self.config.clock.diameter);
                     }
                 }
                 self.update_size();
                 ipc::IpcResponse::ok()
             }
+            ipc::IpcCommand::SetHourFormat { format } => {
+                if format != 12 && format != 24 {
+                    return ipc::IpcResponse::err(format!("Invalid hour format: {} (expected 12 or 24)", format));
+                }
+                self.set_hour_format(format);
+                ipc::IpcResponse::ok()
+            }
+            ipc::IpcCommand::ToggleHourFormat => {
+                let format = if self.config.clock.hour_format == 12 { 24 } else { 12 };
+                self.set_hour_format(format);
+                ipc::IpcResponse::ok()
+            }
+            ipc::IpcCommand::SetDateFormat { format } => {
+                // chrono panics while formatting a bad strftime string, so reject it up front
+                if !time_utils::is_valid_format(&format) {
+                    return ipc::IpcResponse::err(format!("Invalid date format: {:?}", format));
+                }
+                self.config.clock.date_format = format;
+                self.update_size();
+                config::save_date_format_to_config(&self.config_path, &self.config.clock.date_format);
+                ipc::IpcResponse::ok()
+            }
             ipc::IpcCommand::SetLocked { locked } => {
-                self.locked = locked;
+                self.set_locked(locked);
                 ipc::IpcResponse::ok()
             }
             ipc::IpcCommand::ToggleLocked => {
-                self.locked = !self.locked;
+                self.set_locked(!self.locked);
+                ipc::IpcResponse::ok()
+            }
+            ipc::IpcCommand::SetVisible { visible } => {
+                let was_visible = self.visible();
+                self.hidden = !visible;
+                self.apply_visibility(was_visible);
+                ipc::IpcResponse::ok()
+            }
+            ipc::IpcCommand::ToggleVisible => {
+                let was_visible = self.visible();
+                self.hidden = !self.hidden;
+                self.apply_visibility(was_visible);
+                ipc::IpcResponse::ok()
+            }
+            ipc::IpcCommand::SetFullscreen { fullscreen } => {
+                let was_visible = self.visible();
+                self.fullscreen = fullscreen;
+                self.apply_visibility(was_visible);
                 ipc::IpcResponse::ok()
             }
+            ipc::IpcCommand::MoveToOutput { .. } if !self.shell_surface.is_layer() => {
+                ipc::IpcResponse::err("Moving between outputs needs backend = \"layer\"")
+            }
             ipc::IpcCommand::MoveToOutput { name } => {
                 let target = match name.as_str() {
                     "next" => self.find_output_cycle(true),
@@ -812,9 +1759,12 @@ impl Clockie {
                 };
                 match target {
                     Some(output) => {
+                        self.restore_position(&output);
                         self.recreate_surface(qh, Some(&output));
                         // Persist the output name
                         let output_name = self.get_output_name().unwrap_or_else(|| name.clone());
+                        // A deliberate move replaces any pending return to an unplugged output
+                        self.displaced_from = None;
                         self.config.window.output = Some(output_name.clone());
                         config::save_output_to_config(&self.config_path, &output_name);
                         ipc::IpcResponse::ok()
@@ -826,42 +1776,38 @@ impl Clockie {
                 match config::load_config(&self.config_path) {
                     Ok(new_config) => {
                         // Preserve runtime state
+                        let was_visible = self.visible();
                         let face = self.config.clock.face;
                         let compact = self.compact;
 
                         // Apply anchor
-                        let mut anchor = Anchor::empty();
-                        for part in new_config.window.anchor.split_whitespace() {
-                            match part.to_lowercase().as_str() {
-                                "top" => anchor |= Anchor::TOP,
-                                "bottom" => anchor |= Anchor::BOTTOM,
-                                "left" => anchor |= Anchor::LEFT,
-                                "right" => anchor |= Anchor::RIGHT,
-                                _ => {}
-                            }
-                        }
-                        self.layer_surface.set_anchor(anchor);
+                        let anchor = parse_anchor(&new_config.window.anchor);
+                        self.shell_surface.set_anchor(anchor);
                         self.anchor = anchor;
 
                         // Apply margins
-                        self.layer_surface.set_margin(
+                        self.shell_surface.set_margin(
                             new_config.window.margin_top,
                             new_config.window.margin_right,
                             new_config.window.margin_bottom,
                             new_config.window.margin_left,
                         );
 
+                        let font_changed = new_config.clock.font != self.config.clock.font;
                         self.config = new_config;
                         self.config.clock.face = face;
                         self.compact = compact;
-                        self.font = FontState::new(&self.config.clock.font);
+                        if font_changed {
+                            self.reload_font();
+                        }
                         self.gallery.reload_from_config(&self.config);
                         self.contrast_dirty = true;
 
                         // Recompute size from new config
                         self.update_size();
                         // Commit geometry changes
-                        self.layer_surface.wl_surface().commit();
+                        self.shell_surface.wl_surface().commit();
+                        self.apply_visibility(was_visible);
                         ipc::IpcResponse::ok()
                     }
                     Err(e) => ipc::IpcResponse::err(format!("Config reload failed: {}", e)),
@@ -882,20 +1828,25 @@ impl Clockie {
                     self.config.clock.diameter,
                     &self.config_path.to_string_lossy(),
                     self.locked,
+                    self.visible(),
                     output_name.as_deref(),
-                ).with_gallery(
+                ).with_font_path(self.font.path()).with_gallery(
                     self.gallery.digital_index,
                     self.gallery.analogue_index,
                     self.gallery.digital_images.len(),
                     self.gallery.analogue_images.len(),
                     self.gallery.rotate_active,
                     self.gallery.rotate_interval.as_secs(),
-                )
+                ).with_alarms(self.alarms.iter().map(|alarm| alarm.format("%H:%M").to_string()))
             }
             ipc::IpcCommand::Quit => {
                 self.should_quit = true;
                 ipc::IpcResponse::ok()
             }
+            ipc::IpcCommand::Version => ipc::IpcResponse::version(),
+            ipc::IpcCommand::Ping => ipc::IpcResponse::ping(self.started.elapsed().as_secs()),
+            // Rejected while parsing, with the command name
+            ipc::IpcCommand::Unknown => ipc::IpcResponse::err("unknown command"),
             ipc::IpcCommand::GalleryNext => {
                 match self.config.clock.face {
                     FaceMode::Digital => self.gallery.next_digital(),
@@ -957,6 +1908,29 @@ impl Clockie {
                 self.gallery.rotate_active = false;
                 ipc::IpcResponse::ok()
             }
+            ipc::IpcCommand::SetAlarm { at } => match time_utils::parse_alarm_time(&at) {
+                Some(time) => {
+                    self.set_alarm(time);
+                    ipc::IpcResponse::ok()
+                }
+                None => ipc::IpcResponse::err(format!("Invalid alarm time {:?}, expected HH:MM", at)),
+            },
+            ipc::IpcCommand::ClearAlarm { at: None } => {
+                self.alarms.clear();
+                self.needs_redraw = true;
+                ipc::IpcResponse::ok()
+            }
+            ipc::IpcCommand::ClearAlarm { at: Some(at) } => {
+                let Some(time) = time_utils::parse_alarm_time(&at) else {
+                    return ipc::IpcResponse::err(format!("Invalid alarm time {:?}, expected HH:MM", at));
+                };
+                if !self.alarms.contains(&time) {
+                    return ipc::IpcResponse::err(format!("No alarm set for {}", time.format("%H:%M")));
+                }
+                self.alarms.retain(|alarm| *alarm != time);
+                self.needs_redraw = true;
+                ipc::IpcResponse::ok()
+            }
             ipc::IpcCommand::GalleryRotateInterval { seconds } => {
                 self.gallery.rotate_interval = Duration::from_secs(seconds);
                 if self.gallery.rotate_active {
@@ -971,11 +1945,21 @@ impl Clockie {
 // SCTK handler implementations
 
 impl CompositorHandler for Clockie {
-    fn scale_factor_changed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _surface: &wl_surface::WlSurface, _new_factor: i32) {
+    fn scale_factor_changed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _surface: &wl_surface::WlSurface, new_factor: i32) {
+        // Only used without wp_fractional_scale_v1, which reports its own scale
+        if new_factor > 0 && new_factor != self.buffer_scale {
+            self.buffer_scale = new_factor;
+            self.full_damage = true;
+        }
         self.needs_redraw = true;
     }
 
-    fn transform_changed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _surface: &wl_surface::WlSurface, _new_transform: wl_output::Transform) {
+    fn transform_changed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _surface: &wl_surface::WlSurface, new_transform: wl_output::Transform) {
+        if new_transform != self.buffer_transform {
+            log::info!("Output transform changed to {:?}", new_transform);
+            self.buffer_transform = new_transform;
+            self.full_damage = true;
+        }
         self.needs_redraw = true;
     }
 
@@ -984,10 +1968,24 @@ impl CompositorHandler for Clockie {
     }
 
     fn surface_enter(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _surface: &wl_surface::WlSurface, output: &wl_output::WlOutput) {
+        let changed = self.current_output.as_ref() != Some(output);
         self.current_output = Some(output.clone());
         if let Some(info) = self.output_state.info(output) {
             log::info!("Surface entered output: {:?}", info.name);
         }
+        // The output sizes an output-sized clock and limits any other
+        if changed {
+            self.update_size();
+        }
+        // The output's size is known now, so the margins can be clamped to it,
+        // after going back to where the clock was last put on this output
+        if changed && self.configured {
+            if self.shell_surface.is_layer() && self.restore_position(output) {
+                self.shell_surface.set_anchor(self.anchor);
+            }
+            self.apply_margins();
+            self.shell_surface.wl_surface().commit();
+        }
     }
     fn surface_leave(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _surface: &wl_surface::WlSurface, output: &wl_output::WlOutput) {
         if self.current_output.as_ref() == Some(output) {
@@ -997,8 +1995,19 @@ impl CompositorHandler for Clockie {
 }
 
 impl LayerShellHandler for Clockie {
-    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface) {
-        self.should_quit = true;
+    fn closed(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, layer: &LayerSurface) {
+        // Ignore late events for a surface we have already replaced
+        if layer.wl_surface() != self.shell_surface.wl_surface() {
+            return;
+        }
+        // The compositor closes layer surfaces whose output was removed; by then the
+        // surface has left every output, so move elsewhere instead of quitting
+        if self.current_output.is_none() {
+            log::info!("Surface closed after losing its output, recovering");
+            self.recover_surface(qh, None);
+        } else {
+            self.should_quit = true;
+        }
     }
 
     fn configure(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _layer: &LayerSurface, configure: LayerSurfaceConfigure, _serial: u32) {
@@ -1013,14 +2022,77 @@ impl LayerShellHandler for Clockie {
     }
 }
 
+impl WindowHandler for Clockie {
+    fn request_close(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, window: &Window) {
+        if window.wl_surface() == self.shell_surface.wl_surface() {
+            self.should_quit = true;
+        }
+    }
+
+    fn configure(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _window: &Window, configure: WindowConfigure, _serial: u32) {
+        // A tiling compositor may impose a size; the faces centre themselves in it
+        if let (Some(w), Some(h)) = configure.new_size {
+            self.width = w.get();
+            self.height = h.get();
+        }
+        self.configured = true;
+        self.needs_redraw = true;
+    }
+}
+
 impl OutputHandler for Clockie {
     fn output_state(&mut self) -> &mut OutputState {
         &mut self.output_state
     }
 
-    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
-    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
-    fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
+    fn new_output(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, output: wl_output::WlOutput) {
+        // Outputs announced during startup are handled by the pending output move;
+        // a toplevel is never moved between outputs
+        if self.pending_output_move.is_some() || !self.shell_surface.is_layer() {
+            return;
+        }
+        let name = self.output_state.info(&output).and_then(|info| info.name);
+        if self.output_lost {
+            log::info!("Output {:?} connected, resuming", name);
+            self.output_lost = false;
+            if name.is_some() && name == self.displaced_from {
+                self.displaced_from = None;
+            }
+            self.move_to_output(qh, &output);
+        } else if self.config.window.return_to_output && name.is_some() && name == self.displaced_from {
+            log::info!("Output {:?} reconnected, moving back", name);
+            self.displaced_from = None;
+            self.move_to_output(qh, &output);
+        }
+    }
+    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: wl_output::WlOutput) {
+        if self.current_output.as_ref() != Some(&output) {
+            return;
+        }
+        // A mode change on our output resizes an output-sized clock, or one it limits
+        self.update_size();
+        // and re-clamps the margins against its new size
+        if self.configured {
+            self.apply_margins();
+            self.shell_surface.wl_surface().commit();
+        }
+    }
+    fn output_destroyed(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, output: wl_output::WlOutput) {
+        // A toplevel outlives its output; the compositor moves it
+        if !self.shell_surface.is_layer() {
+            return;
+        }
+        // The surface usually leaves the output first, leaving current_output unset
+        let ours = match &self.current_output {
+            Some(current) => current == &output,
+            None => !self.output_lost,
+        };
+        if ours {
+            log::info!("Output {:?} removed, moving the clock", self.output_state.info(&output).and_then(|i| i.name));
+            self.current_output = None;
+            self.recover_surface(qh, Some(&output));
+        }
+    }
 }
 
 impl SeatHandler for Clockie {
@@ -1031,11 +2103,18 @@ impl SeatHandler for Clockie {
     fn new_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {}
     fn new_capability(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, seat: wl_seat::WlSeat, capability: SeatCapability) {
         if capability == SeatCapability::Pointer && self.pointer.is_none() {
-            self.pointer = Some(self.seat_state.get_pointer(qh, &seat).expect("Failed to get pointer"));
+            let pointer = self.seat_state.get_pointer(qh, &seat).expect("Failed to get pointer");
+            self.cursor_shape_device = self.cursor_shape_manager.as_ref()
+                .map(|manager| manager.get_shape_device(&pointer, qh));
+            self.pointer = Some(pointer);
         }
     }
     fn remove_capability(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat, capability: SeatCapability) {
         if capability == SeatCapability::Pointer {
+            if let Some(device) = self.cursor_shape_device.take() {
+                device.destroy();
+            }
+            self.pointer_enter_serial = None;
             if let Some(pointer) = self.pointer.take() {
                 pointer.release();
             }
@@ -1060,179 +2139,291 @@ impl ProvidesRegistryState for Clockie {
     registry_handlers![OutputState, SeatState];
 }
 
+/// Maps a surface pixel `(x, y, width, height)` to its position in the buffer.
+type PixelMap = fn(usize, usize, usize, usize) -> (usize, usize);
+
+/// Rearrange upright ARGB pixels into buffer order for `wl_surface.set_buffer_transform`.
+/// Returns the pixels with the buffer's width and height (swapped for 90/270 rotations).
+fn transform_pixels(pixels: Vec<u8>, w: u32, h: u32, transform: wl_output::Transform) -> (Vec<u8>, u32, u32) {
+    use wl_output::Transform as T;
+
+    let (w_us, h_us) = (w as usize, h as usize);
+    // Map a surface pixel (x, y) to its buffer position, as the compositor will undo it
+    let (buf_w, buf_h, map): (u32, u32, PixelMap) = match transform {
+        T::_90 => (h, w, |x, y, _w, h| (h - 1 - y, x)),
+        T::_180 => (w, h, |x, y, w, h| (w - 1 - x, h - 1 - y)),
+        T::_270 => (h, w, |x, y, w, _h| (y, w - 1 - x)),
+        T::Flipped => (w, h, |x, y, w, _h| (w - 1 - x, y)),
+        T::Flipped90 => (h, w, |x, y, w, h| (h - 1 - y, w - 1 - x)),
+        T::Flipped180 => (w, h, |x, y, _w, h| (x, h - 1 - y)),
+        T::Flipped270 => (h, w, |x, y, _w, _h| (y, x)),
+        _ => return (pixels, w, h),
+    };
+
+    let mut out = vec![0u8; pixels.len()];
+    for y in 0..h_us {
+        for x in 0..w_us {
+            let (bx, by) = map(x, y, w_us, h_us);
+            let src = (y * w_us + x) * 4;
+            let dst = (by * buf_w as usize + bx) * 4;
+            out[dst..dst + 4].copy_from_slice(&pixels[src..src + 4]);
+        }
+    }
+    (out, buf_w, buf_h)
+}
+
+/// Snap a margin to the near edge, the centre line or the far edge of an
+/// output axis when it lies within `threshold` pixels of one of them.
+fn snap_margin(margin: i32, extent: i32, size: i32, threshold: i32) -> i32 {
+    if extent <= 0 {
+        return margin;
+    }
+    let far = (extent - size).max(0);
+    [0, far / 2, far]
+        .into_iter()
+        .filter(|target| (margin - target).abs() <= threshold)
+        .min_by_key(|target| (margin - target).abs())
+        .unwrap_or(margin)
+}
+
 const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+
+/// The evdev code `wl_pointer` reports for a configured button.
+fn button_code(button: DragButton) -> u32 {
+    match button {
+        DragButton::Left => BTN_LEFT,
+        DragButton::Middle => BTN_MIDDLE,
+        DragButton::Right => BTN_RIGHT,
+    }
+}
 
 impl PointerHandler for Clockie {
     fn pointer_frame(
         &mut self,
         _conn: &Connection,
         qh: &QueueHandle<Self>,
-        _pointer: &wl_pointer::WlPointer,
+        pointer: &wl_pointer::WlPointer,
         events: &[PointerEvent],
     ) {
+        let drag_button = button_code(self.config.window.drag_button);
         for event in events {
+            // Any pointer event over the clock counts as activity for idle dimming
+            self.last_pointer_activity = std::time::Instant::now();
+            if let PointerEventKind::Leave { .. } = event.kind {
+                self.pointer_inside = false;
+            }
             match event.kind {
-                PointerEventKind::Press { button, .. } if button == BTN_LEFT => {
-                    if !self.locked {
-                        self.dragging = true;
-                        self.drag_start = event.position;
-                        self.drag_margins = (
-                            self.config.window.margin_top,
-                            self.config.window.margin_right,
-                            self.config.window.margin_bottom,
-                            self.config.window.margin_left,
-                        );
+                PointerEventKind::Enter { serial } => {
+                    self.pointer_inside = true;
+                    self.pointer_enter_serial = Some(serial);
+                    self.update_cursor();
+                }
+                PointerEventKind::Press { button, .. }
+                    if button == BTN_RIGHT && drag_button != BTN_RIGHT && self.config.window.right_click_lock =>
+                {
+                    self.set_locked(!self.locked);
+                    log::info!("Drag lock {}", if self.locked { "on" } else { "off" });
+                }
+                PointerEventKind::Press { button, serial, .. } if button == drag_button && !self.locked => {
+                    // A toplevel has no margins; the compositor moves it
+                    if let ClockSurface::Xdg(window) = &self.shell_surface {
+                        if let Some(data) = pointer.data::<PointerData>() {
+                            window.move_(data.seat(), serial);
+                        }
+                        continue;
                     }
+                    self.dragging = true;
+                    self.drag_active = false;
+                    self.drag_start = event.position;
+                    // Pick the clock up where it is shown, which may be clamped
+                    let shown = self.clamped_margins();
+                    let w = &mut self.config.window;
+                    (w.margin_top, w.margin_right, w.margin_bottom, w.margin_left) = shown;
+                    self.drag_margins = (
+                        self.config.window.margin_top,
+                        self.config.window.margin_right,
+                        self.config.window.margin_bottom,
+                        self.config.window.margin_left,
+                    );
                 }
                 PointerEventKind::Motion { .. } if self.dragging => {
                     let dx = event.position.0 - self.drag_start.0;
                     let dy = event.position.1 - self.drag_start.1;
 
+                    // A press that jitters by a pixel or two is still a click
+                    if !self.drag_active {
+                        if dx.hypot(dy) < DRAG_THRESHOLD {
+                            continue;
+                        }
+                        self.drag_active = true;
+                        self.unpin_centered_axes();
+                    }
+
                     let has_left = self.anchor.contains(Anchor::LEFT);
                     let has_right = self.anchor.contains(Anchor::RIGHT);
                     let has_top = self.anchor.contains(Anchor::TOP);
                     let has_bottom = self.anchor.contains(Anchor::BOTTOM);
 
+                    let (out_w, out_h) = self.output_size();
+                    let snap = self.config.window.snap;
+                    let threshold = self.config.window.snap_threshold;
+                    let snap_h = |m: i32| if snap { snap_margin(m, out_w, self.width as i32, threshold) } else { m };
+                    let snap_v = |m: i32| if snap { snap_margin(m, out_h, self.height as i32, threshold) } else { m };
+
                     // Horizontal
                     if has_left && !has_right {
-                        self.config.window.margin_left = (self.drag_margins.3 + dx as i32).max(0);
+                        self.config.window.margin_left = snap_h((self.drag_margins.3 + dx as i32).max(0));
                     } else if has_right && !has_left {
-                        self.config.window.margin_right = (self.drag_margins.1 - dx as i32).max(0);
+                        self.config.window.margin_right = snap_h((self.drag_margins.1 - dx as i32).max(0));
                     }
 
                     // Vertical
                     if has_top && !has_bottom {
-                        self.config.window.margin_top = (self.drag_margins.0 + dy as i32).max(0);
+                        self.config.window.margin_top = snap_v((self.drag_margins.0 + dy as i32).max(0));
                     } else if has_bottom && !has_top {
-                        self.config.window.margin_bottom = (self.drag_margins.2 - dy as i32).max(0);
+                        self.config.window.margin_bottom = snap_v((self.drag_margins.2 - dy as i32).max(0));
                     }
 
-                    self.layer_surface.set_margin(
+                    self.shell_surface.set_margin(
                         self.config.window.margin_top,
                         self.config.window.margin_right,
                         self.config.window.margin_bottom,
                         self.config.window.margin_left,
                     );
-                    self.layer_surface.wl_surface().commit();
+                    self.shell_surface.wl_surface().commit();
                 }
-                PointerEventKind::Release { button, .. } if button == BTN_LEFT => {
-                    if self.dragging {
-                        self.dragging = false;
-                        let current = (
-                            self.config.window.margin_top,
-                            self.config.window.margin_right,
-                            self.config.window.margin_bottom,
-                            self.config.window.margin_left,
+                PointerEventKind::Release { button, .. } if button == drag_button && self.dragging => {
+                    self.dragging = false;
+                    if !self.drag_active {
+                        // Released in place: a click, the window never moved
+                        continue;
+                    }
+                    self.settle_unpinned_axes();
+                    let current = (
+                        self.config.window.margin_top,
+                        self.config.window.margin_right,
+                        self.config.window.margin_bottom,
+                        self.config.window.margin_left,
+                    );
+                    if current != self.drag_margins {
+                        config::save_margins_to_config(
+                            &self.config_path,
+                            current.0,
+                            current.1,
+                            current.2,
+                            current.3,
                         );
-                        if current != self.drag_margins {
-                            config::save_margins_to_config(
-                                &self.config_path,
-                                current.0,
-                                current.1,
-                                current.2,
-                                current.3,
-                            );
-                        }
                     }
+                    self.remember_position();
                 }
-                PointerEventKind::Leave { .. } => {
-                    if self.dragging {
-                        self.dragging = false;
+                PointerEventKind::Leave { .. } if self.dragging && self.drag_active => {
+                    self.dragging = false;
+                    self.pointer_enter_serial = None;
+                    self.settle_unpinned_axes();
 
-                        let has_left = self.anchor.contains(Anchor::LEFT);
-                        let has_right = self.anchor.contains(Anchor::RIGHT);
-                        let has_top = self.anchor.contains(Anchor::TOP);
-                        let has_bottom = self.anchor.contains(Anchor::BOTTOM);
+                    let has_left = self.anchor.contains(Anchor::LEFT);
+                    let has_right = self.anchor.contains(Anchor::RIGHT);
+                    let has_top = self.anchor.contains(Anchor::TOP);
+                    let has_bottom = self.anchor.contains(Anchor::BOTTOM);
 
-                        // Detect which direction the clock was dragged to the edge
-                        // A margin is "at edge" if it's 0 and wasn't 0 at drag start
-                        let direction = if has_left && !has_right && self.config.window.margin_left == 0 && self.drag_margins.3 > 0 {
+                    // Detect which direction the clock was dragged to the edge
+                    // A margin is "at edge" if it's 0 and wasn't 0 at drag start
+                    let direction = if has_left && !has_right && self.config.window.margin_left == 0 && self.drag_margins.3 > 0 {
+                        Some(Direction::Left)
+                    } else if has_right && !has_left && self.config.window.margin_right == 0 && self.drag_margins.1 > 0 {
+                        Some(Direction::Right)
+                    } else if has_top && !has_bottom && self.config.window.margin_top == 0 && self.drag_margins.0 > 0 {
+                        Some(Direction::Up)
+                    } else if has_bottom && !has_top && self.config.window.margin_bottom == 0 && self.drag_margins.2 > 0 {
+                        Some(Direction::Down)
+                    } else {
+                        // Also check: margin was already 0 at drag start but we're leaving in that direction
+                        // This handles the case where the clock was already at the edge
+                        if has_left && !has_right && self.config.window.margin_left == 0 {
                             Some(Direction::Left)
-                        } else if has_right && !has_left && self.config.window.margin_right == 0 && self.drag_margins.1 > 0 {
+                        } else if has_right && !has_left && self.config.window.margin_right == 0 {
                             Some(Direction::Right)
-                        } else if has_top && !has_bottom && self.config.window.margin_top == 0 && self.drag_margins.0 > 0 {
+                        } else if has_top && !has_bottom && self.config.window.margin_top == 0 {
                             Some(Direction::Up)
-                        } else if has_bottom && !has_top && self.config.window.margin_bottom == 0 && self.drag_margins.2 > 0 {
+                        } else if has_bottom && !has_top && self.config.window.margin_bottom == 0 {
                             Some(Direction::Down)
                         } else {
-                            // Also check: margin was already 0 at drag start but we're leaving in that direction
-                            // This handles the case where the clock was already at the edge
-                            if has_left && !has_right && self.config.window.margin_left == 0 {
-                                Some(Direction::Left)
-                            } else if has_right && !has_left && self.config.window.margin_right == 0 {
-                                Some(Direction::Right)
-                            } else if has_top && !has_bottom && self.config.window.margin_top == 0 {
-                                Some(Direction::Up)
-                            } else if has_bottom && !has_top && self.config.window.margin_bottom == 0 {
-                                Some(Direction::Down)
-                            } else {
-                                None
-                            }
-                        };
-
-                        let moved = if let Some(dir) = direction {
-                            if let Some(target) = self.find_adjacent_output(dir) {
-                                // Set margin on the arriving edge to 0, keep perpendicular margins
-                                match dir {
-                                    Direction::Left => {
-                                        // Arriving from the right side of the new output
-                                        // Flip anchor to right side
-                                        self.anchor = (self.anchor & !(Anchor::LEFT)) | Anchor::RIGHT;
-                                        self.config.window.anchor = format_anchor(self.anchor);
-                                        self.config.window.margin_right = 0;
-                                        self.config.window.margin_left = 0;
-                                    }
-                                    Direction::Right => {
-                                        self.anchor = (self.anchor & !(Anchor::RIGHT)) | Anchor::LEFT;
-                                        self.config.window.anchor = format_anchor(self.anchor);
-                                        self.config.window.margin_left = 0;
-                                        self.config.window.margin_right = 0;
-                                    }
-                                    Direction::Up => {
-                                        self.anchor = (self.anchor & !(Anchor::TOP)) | Anchor::BOTTOM;
-                                        self.config.window.anchor = format_anchor(self.anchor);
-                                        self.config.window.margin_bottom = 0;
-                                        self.config.window.margin_top = 0;
-                                    }
-                                    Direction::Down => {
-                                        self.anchor = (self.anchor & !(Anchor::BOTTOM)) | Anchor::TOP;
-                                        self.config.window.anchor = format_anchor(self.anchor);
-                                        self.config.window.margin_top = 0;
-                                        self.config.window.margin_bottom = 0;
-                                    }
+                            None
+                        }
+                    };
+
+                    let moved = if let Some(dir) = direction {
+                        if let Some(target) = self.find_adjacent_output(dir) {
+                            // Set margin on the arriving edge to 0, keep perpendicular margins
+                            match dir {
+                                Direction::Left => {
+                                    // Arriving from the right side of the new output
+                                    // Flip anchor to right side
+                                    self.anchor = (self.anchor & !(Anchor::LEFT)) | Anchor::RIGHT;
+                                    self.config.window.anchor = format_anchor(self.anchor);
+                                    self.config.window.margin_right = 0;
+                                    self.config.window.margin_left = 0;
+                                }
+                                Direction::Right => {
+                                    self.anchor = (self.anchor & !(Anchor::RIGHT)) | Anchor::LEFT;
+                                    self.config.window.anchor = format_anchor(self.anchor);
+                                    self.config.window.margin_left = 0;
+                                    self.config.window.margin_right = 0;
+                                }
+                                Direction::Up => {
+                                    self.anchor = (self.anchor & !(Anchor::TOP)) | Anchor::BOTTOM;
+                                    self.config.window.anchor = format_anchor(self.anchor);
+                                    self.config.window.margin_bottom = 0;
+                                    self.config.window.margin_top = 0;
+                                }
+                                Direction::Down => {
+                                    self.anchor = (self.anchor & !(Anchor::BOTTOM)) | Anchor::TOP;
+                                    self.config.window.anchor = format_anchor(self.anchor);
+                                    self.config.window.margin_top = 0;
+                                    self.config.window.margin_bottom = 0;
                                 }
-                                self.recreate_surface(qh, Some(&target));
-                                true
-                            } else {
-                                false
                             }
+                            self.recreate_surface(qh, Some(&target));
+                            true
                         } else {
                             false
-                        };
-
-                        // Save state
-                        let current = (
-                            self.config.window.margin_top,
-                            self.config.window.margin_right,
-                            self.config.window.margin_bottom,
-                            self.config.window.margin_left,
-                        );
-                        if moved || current != self.drag_margins {
-                            config::save_margins_to_config(
-                                &self.config_path,
-                                current.0,
-                                current.1,
-                                current.2,
-                                current.3,
-                            );
                         }
-                        if moved {
-                            if let Some(output_name) = self.get_output_name() {
-                                self.config.window.output = Some(output_name.clone());
-                                config::save_output_to_config(&self.config_path, &output_name);
-                            }
+                    } else {
+                        false
+                    };
+
+                    // Save state
+                    let current = (
+                        self.config.window.margin_top,
+                        self.config.window.margin_right,
+                        self.config.window.margin_bottom,
+                        self.config.window.margin_left,
+                    );
+                    if moved || current != self.drag_margins {
+                        config::save_margins_to_config(
+                            &self.config_path,
+                            current.0,
+                            current.1,
+                            current.2,
+                            current.3,
+                        );
+                    }
+                    if moved {
+                        config::save_anchor_to_config(&self.config_path, &self.config.window.anchor);
+                        if let Some(output_name) = self.get_output_name() {
+                            self.displaced_from = None;
+                            self.config.window.output = Some(output_name.clone());
+                            config::save_output_to_config(&self.config_path, &output_name);
                         }
                     }
+                    self.remember_position();
+                }
+                PointerEventKind::Leave { .. } => {
+                    // The compositor restores its own cursor once we lose the pointer
+                    self.dragging = false;
+                    self.pointer_enter_serial = None;
                 }
                 _ => {}
             }
@@ -1240,6 +2431,105 @@ impl PointerHandler for Clockie {
     }
 }
 
+impl Dispatch<wp_fractional_scale_v1::WpFractionalScaleV1, ()> for Clockie {
+    fn event(
+        state: &mut Self,
+        _proxy: &wp_fractional_scale_v1::WpFractionalScaleV1,
+        event: wp_fractional_scale_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = event {
+            if scale != state.scale_120 && scale > 0 {
+                log::info!("Preferred fractional scale: {:.3}", scale as f32 / 120.0);
+                state.scale_120 = scale;
+                state.full_damage = true;
+                state.needs_redraw = true;
+            }
+        }
+    }
+}
+
+/// Minimal client state for `--list-outputs`: only output globals are bound
+/// and no surface is created.
+struct OutputLister {
+    registry_state: RegistryState,
+    output_state: OutputState,
+}
+
+impl OutputHandler for OutputLister {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
+    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
+    fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
+}
+
+impl ProvidesRegistryState for OutputLister {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState];
+}
+
+/// Print every connected output's name, description, logical position and size.
+pub fn list_outputs(json: bool) -> Result<()> {
+    let conn = Connection::connect_to_env().context("Failed to connect to Wayland")?;
+    let (globals, mut event_queue) = registry_queue_init(&conn)
+        .context("Failed to initialize Wayland registry")?;
+    let qh = event_queue.handle();
+
+    let mut lister = OutputLister {
+        registry_state: RegistryState::new(&globals),
+        output_state: OutputState::new(&globals, &qh),
+    };
+    // The first roundtrip binds the outputs, the second collects their info
+    event_queue.roundtrip(&mut lister)?;
+    event_queue.roundtrip(&mut lister)?;
+
+    let infos: Vec<_> = lister.output_state.outputs()
+        .filter_map(|o| lister.output_state.info(&o))
+        .collect();
+
+    if json {
+        let list: Vec<_> = infos.iter().map(|info| {
+            let (x, y) = info.logical_position.unwrap_or((0, 0));
+            let (w, h) = info.logical_size.unwrap_or((0, 0));
+            serde_json::json!({
+                "name": info.name,
+                "description": info.description,
+                "x": x,
+                "y": y,
+                "width": w,
+                "height": h,
+            })
+        }).collect();
+        println!("{}", serde_json::to_string_pretty(&list)?);
+    } else {
+        for info in &infos {
+            let (x, y) = info.logical_position.unwrap_or((0, 0));
+            let (w, h) = info.logical_size.unwrap_or((0, 0));
+            println!(
+                "{}  {}x{} at {},{}  {}",
+                info.name.as_deref().unwrap_or("?"),
+                w, h, x, y,
+                info.description.as_deref().unwrap_or(""),
+            );
+        }
+    }
+    Ok(())
+}
+
+delegate_output!(OutputLister);
+delegate_registry!(OutputLister);
+
+wayland_client::delegate_noop!(Clockie: ignore wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1);
+wayland_client::delegate_noop!(Clockie: ignore wp_viewporter::WpViewporter);
+wayland_client::delegate_noop!(Clockie: ignore wp_viewport::WpViewport);
+
 delegate_compositor!(Clockie);
 delegate_layer!(Clockie);
 delegate_output!(Clockie);
@@ -1247,3 +2537,5 @@ delegate_pointer!(Clockie);
 delegate_registry!(Clockie);
 delegate_seat!(Clockie);
 delegate_shm!(Clockie);
+delegate_xdg_shell!(Clockie);
+delegate_xdg_window!(Clockie);
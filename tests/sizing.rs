@@ -0,0 +1,82 @@
+//! Window sizes measured with the embedded font, so they are the same on every
+//! machine. A change here means windows change size for existing configs.
+
+use clockie::canvas::FontState;
+use clockie::config::{ClockConfig, FaceMode, TimezoneEntry};
+use clockie::renderer::{compute_digital_size, compute_size, compute_subclock_size};
+
+fn config(face: FaceMode, show_seconds: bool, subclocks: bool) -> ClockConfig {
+    let mut config = ClockConfig::default();
+    config.clock.face = face;
+    config.clock.show_seconds = show_seconds;
+    // Pin the locale so the date line doesn't follow the environment
+    config.clock.locale = "en_US".into();
+    if subclocks {
+        config.timezone = [("Tokyo", "Asia/Tokyo"), ("London", "Europe/London")]
+            .into_iter()
+            .map(|(label, tz)| TimezoneEntry { label: label.into(), tz: tz.into(), color: None, label_color: None, primary: false })
+            .collect();
+    }
+    config
+}
+
+fn font() -> FontState {
+    FontState::embedded().expect("embedded font loads")
+}
+
+fn assert_close((w, h): (f32, f32), expected: (f32, f32)) {
+    assert!((w - expected.0).abs() < 1e-3 && (h - expected.1).abs() < 1e-3, "({w}, {h}) != {expected:?}");
+}
+
+#[test]
+fn digital_sizes() {
+    let font = font();
+    let cases = [
+        // (show_seconds, compact, subclocks) => (w, h)
+        ((true, false, false), (357, 92)),
+        ((true, false, true), (373, 145)),
+        ((true, true, false), (250, 51)),
+        ((true, true, true), (250, 51)),
+        ((false, false, false), (270, 92)),
+        ((false, false, true), (287, 145)),
+        ((false, true, false), (189, 51)),
+        ((false, true, true), (189, 51)),
+    ];
+    for ((seconds, compact, subclocks), expected) in cases {
+        let config = config(FaceMode::Digital, seconds, subclocks);
+        let label = format!("seconds={seconds} compact={compact} subclocks={subclocks}");
+        assert_eq!(compute_digital_size(&config, &font, compact), expected, "{label}");
+        assert_eq!(compute_size(&config, &font, compact, None), expected, "{label}");
+    }
+}
+
+#[test]
+fn analogue_sizes() {
+    let font = font();
+    let cases = [
+        // (show_seconds, compact, subclocks) => (w, h)
+        ((true, false, false), (204, 204)),
+        ((true, false, true), (204, 304)),
+        ((true, true, false), (159, 159)),
+        ((true, true, true), (159, 159)),
+        ((false, false, false), (204, 204)),
+        ((false, false, true), (204, 304)),
+    ];
+    for ((seconds, compact, subclocks), expected) in cases {
+        let config = config(FaceMode::Analogue, seconds, subclocks);
+        let label = format!("seconds={seconds} compact={compact} subclocks={subclocks}");
+        assert_eq!(compute_size(&config, &font, compact, None), expected, "{label}");
+    }
+}
+
+#[test]
+fn subclock_sizes() {
+    let font = font();
+    assert_close(compute_subclock_size(&config(FaceMode::Digital, true, true), &font, false), (333.90402, 53.184));
+    assert_close(compute_subclock_size(&config(FaceMode::Digital, false, true), &font, false), (248.07562, 53.184));
+    assert_close(compute_subclock_size(&config(FaceMode::Analogue, true, true), &font, false), (156.51749, 99.72));
+    assert_close(compute_subclock_size(&config(FaceMode::Analogue, false, true), &font, false), (116.28545, 99.72));
+    // Nothing is reserved without sub-clocks or in compact mode
+    assert_eq!(compute_subclock_size(&config(FaceMode::Digital, true, false), &font, false), (0.0, 0.0));
+    assert_eq!(compute_subclock_size(&config(FaceMode::Digital, true, true), &font, true), (0.0, 0.0));
+}
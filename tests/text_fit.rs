@@ -0,0 +1,82 @@
+//! Text drawn into a window of the measured size must not touch its edges,
+//! even with a font whose glyphs rise well above the em box.
+
+use chrono::{Locale, TimeZone, Utc};
+use clockie::canvas::{Canvas, FontState};
+use clockie::config::{ClockConfig, TimezoneEntry};
+use clockie::renderer::{self, ClockState, ContrastInfo, ElementContrast};
+use clockie::time_utils;
+
+/// The embedded font with its units-per-em halved, which doubles every glyph
+/// and metric: digits stand well over one em tall and the ascent nearly two.
+fn tall_font() -> FontState {
+    let mut data = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/fonts/DejaVuSansMono.ttf")).expect("embedded font file");
+    let be16 = |d: &[u8], at: usize| u16::from_be_bytes([d[at], d[at + 1]]) as usize;
+    let be32 = |d: &[u8], at: usize| u32::from_be_bytes([d[at], d[at + 1], d[at + 2], d[at + 3]]) as usize;
+    let head = (0..be16(&data, 4))
+        .map(|i| 12 + i * 16)
+        .find(|&record| &data[record..record + 4] == b"head")
+        .map(|record| be32(&data, record + 8))
+        .expect("head table");
+    let units_per_em = be16(&data, head + 18) as u16;
+    data[head + 18..head + 20].copy_from_slice(&(units_per_em / 2).to_be_bytes());
+
+    let path = std::env::temp_dir().join(format!("clockie-tall-{}.ttf", std::process::id()));
+    std::fs::write(&path, data).expect("write font");
+    let font = FontState::from_path(path.to_str().expect("utf-8 path")).expect("patched font loads");
+    let _ = std::fs::remove_file(&path);
+    font
+}
+
+fn state(config: ClockConfig, compact: bool) -> ClockState {
+    let instant = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    let time = time_utils::clock_time_at(instant, &config.clock.date_format, None, None, Locale::POSIX);
+    let contrast = ContrastInfo::resolve(&config.theme, None);
+    ClockState {
+        config,
+        time,
+        compact,
+        battery: None,
+        contrast,
+        elements: ElementContrast::uniform(contrast),
+        background_frame: None,
+        alarm_pending: false,
+        lock_glyph: None,
+    }
+}
+
+/// Render the text on a transparent canvas of the measured size and check
+/// that nothing reaches the top or bottom row.
+fn assert_edges_clear(config: ClockConfig, compact: bool, label: &str) {
+    let font = tall_font();
+    let (w, h) = renderer::compute_size(&config, &font, compact, None);
+    let state = state(config, compact);
+    let mut canvas = Canvas::new(w, h);
+    renderer::render_foreground(&mut canvas, &state, &font);
+
+    let row_len = w as usize * 4;
+    let data = canvas.pixmap.data();
+    for (name, row) in [("top", &data[..row_len]), ("bottom", &data[data.len() - row_len..])] {
+        let covered = row.chunks_exact(4).filter(|px| px[3] != 0).count();
+        assert_eq!(covered, 0, "{label}: {covered} pixel(s) of text in the {name} row of {w}x{h}");
+    }
+}
+
+#[test]
+fn tall_glyphs_fit_digital_window() {
+    assert_edges_clear(ClockConfig::default(), false, "time and date");
+    assert_edges_clear(ClockConfig::default(), true, "compact");
+
+    let mut no_date = ClockConfig::default();
+    no_date.clock.show_date = false;
+    assert_edges_clear(no_date, false, "time only");
+}
+
+#[test]
+fn tall_glyphs_fit_with_subclocks() {
+    let config = ClockConfig {
+        timezone: vec![TimezoneEntry { label: "Tokyo".into(), tz: "Asia/Tokyo".into(), color: None, label_color: None, primary: false }],
+        ..ClockConfig::default()
+    };
+    assert_edges_clear(config, false, "sub-clock");
+}